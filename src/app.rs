@@ -1,258 +1,1122 @@
-use std::{collections::HashMap, fs, io::Read, mem, os::fd::AsRawFd, path::Path};
+mod analyz;
+mod arp;
+mod ndp;
+mod ping;
+mod trace;
 
-use libc::posix_spawn_file_actions_addchdir_np;
-use socket2::{Domain, SockAddr, Socket, Type};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ops::Range,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use self::arp::{ArpCache, ARP_CACHE_TTL};
 
 use crate::{
-    head::{EtherHdr, EtherKind, Header, IPHdr, Protocol},
-    socket::PackSocket,
+    cli::{CaptureArgs, FilterArgs, SendArgs},
+    dissect::{self, HexDump, Node, Section, Tree},
+    filter::{bpf, Expr, Packet},
+    frag::{self, Reassembler, FRAG_TIMEOUT},
+    head::{
+        checksum::Verdict, EtherHdr, EtherKind, Header, IPHdr, Icmpv6, Icmpv6Msg, Ipv6Hdr, Layer,
+        LlcHdr, NetHdr, ParseError, Ping, Protocol, TcpHdr, UdpHdr, VlanTag, ICMP, TPID_CTAG,
+        TPID_STAG,
+    },
+    i18n::msg,
+    iface::{self, interfaces, Interface},
+    log::{LogWriter, Rotation},
+    output::{self, Column, OutputFormat, RecordWriter, Value, SCHEMA_VERSION},
+    pcap::{PcapReader, PcapWriter},
+    route, signal,
+    socket::{link_addr, PackSocket},
+    source::{Frame, PacketSource},
 };
 
+/// 帧的来源
+#[derive(Debug)]
+enum Source<const S: usize> {
+    /// 网络接口
+    Live(PackSocket<S>),
+    /// 捕获文件（离线模式）
+    File(PcapReader<BufReader<File>>),
+}
+
+impl<const S: usize> PacketSource for Source<S> {
+    fn next_frame(&mut self) -> std::io::Result<Option<Frame>> {
+        match self {
+            Source::Live(socket) => socket.next_frame(),
+            Source::File(reader) => reader.next_frame(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct App<const S: usize> {
-    socket: PackSocket<S>,
-    addr: SockAddr,
-    arp: PackSocket<64>,
-    macs: Vec<(String, [u8; 6])>,
-    log: bool,
+    source: Source<S>,
+    /// 本机所有网络接口
+    ifaces: Vec<Interface>,
+    /// 通过 `--interface` 选定的接口，未选定时按路由选择出接口
+    iface: Option<Interface>,
+    /// ARP 缓存，以出接口索引和 IP 地址为键
+    arp: ArpCache<(i32, [u8; 4])>,
+    /// IPv6 邻居缓存
+    ndp: ArpCache<[u8; 16]>,
+    /// 过滤结果的日志
+    log: Option<LogWriter>,
+    /// 捕获文件，以及文件中各接口编号对应的接口索引
+    pcap: Option<(PcapWriter<BufWriter<File>>, Vec<i32>)>,
 }
 
 impl<const S: usize> App<S> {
-    pub fn new() -> std::io::Result<Self> {
-        let socket = PackSocket::<S>::new(libc::ETH_P_IP)?;
-        let addr = unsafe {
-            // Initialise a `SocketAddr` byte calling `getsockname(2)`.
-            let mut addr_storage: libc::sockaddr_storage = mem::zeroed();
-            let mut len = mem::size_of_val(&addr_storage) as libc::socklen_t;
-
-            // The `getsockname(2)` system call will intiliase `storage` for
-            // us, setting `len` to the correct length.
-            let res = libc::getsockname(
-                socket.socket.as_raw_fd(),
-                (&mut addr_storage as *mut libc::sockaddr_storage).cast(),
-                &mut len,
-            );
-            if res == -1 {
-                return Err(std::io::Error::last_os_error());
-            }
-            SockAddr::new(addr_storage, 20)
-        };
+    /// 打开网络接口。选定接口时只在该接口上收发，否则捕获所有接口
+    pub fn new(interface: Option<&str>) -> std::io::Result<Self> {
+        let ifaces = interfaces()?;
+        let iface = interface
+            .map(|name| iface::find(&ifaces, name).cloned())
+            .transpose()?;
 
-        Ok(App {
-            socket,
-            addr,
-            arp: PackSocket::new(libc::ETH_P_ALL)?,
-            macs: get_macs()
-                .into_iter()
-                .map(|(ifc, mac)| {
-                    let mac = mac
-                        .trim()
-                        .split(':')
-                        .map(|num| {
-                            u8::from_str_radix(num, 16)
-                                .expect("MAC address must be write like `ff:ff:ff:ff:ff:ff`")
-                        })
-                        .collect::<Vec<u8>>()
-                        .try_into()
-                        .unwrap();
-                    (ifc, mac)
-                })
-                .collect(),
-            log: false,
-        })
+        let socket = PackSocket::<S>::new(libc::ETH_P_ALL)?;
+        if let Some(iface) = &iface {
+            socket.bind(&link_addr(iface.index, libc::ETH_P_ALL))?;
+        }
+
+        Ok(Self::with_source(Source::Live(socket), ifaces, iface))
+    }
+
+    /// 以离线模式打开捕获文件，不需要访问网络接口
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let source = Source::File(PcapReader::open(path)?);
+        Ok(Self::with_source(source, interfaces()?, None))
+    }
+
+    fn with_source(source: Source<S>, ifaces: Vec<Interface>, iface: Option<Interface>) -> Self {
+        let arp = match &iface {
+            Some(iface) => ArpCache::from_proc(ARP_CACHE_TTL, std::slice::from_ref(iface)),
+            None => ArpCache::from_proc(ARP_CACHE_TTL, &ifaces),
+        };
+        App {
+            source,
+            ifaces,
+            iface,
+            arp,
+            ndp: ArpCache::new(ARP_CACHE_TTL),
+            log: None,
+            pcap: None,
+        }
     }
 
-    pub fn get_mac(&mut self, dhost: [u8; 4]) -> std::io::Result<[u8; 6]> {
-        let shost = Socket::new(Domain::IPV4, Type::DGRAM, None)?
-            .local_addr()?
-            .as_socket_ipv4()
-            .unwrap()
-            .ip()
-            .octets();
-        let smac = self.macs[0].1;
-        // 设置目标 IP 地址和硬件地址
-        let dest_hw = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]; // Broadcast MAC address
-
-        // 构建 ARP 请求报文
-        let mut arp_packet = [0u8; 42];
-
-        let ethdr = EtherHdr {
-            dhost: dest_hw,
-            shost: smac,
-            etype: EtherKind::ARP,
+    /// 发往 `dstip` 的出接口：选定的接口，或路由表中的出接口
+    fn out_iface(&self, dstip: impl Into<IpAddr>) -> std::io::Result<Interface> {
+        if let Some(iface) = &self.iface {
+            return Ok(iface.clone());
         }
-        .to_bytes();
-
-        arp_packet[0..14].copy_from_slice(&ethdr);
-        arp_packet[14] = 0x00;
-        arp_packet[15] = 0x01; // 硬件类型 (Ethernet)
-        arp_packet[16] = 0x08;
-        arp_packet[17] = 0x00; // 协议类型 (IPv4)
-        arp_packet[18] = 0x06; // 硬件地址长度
-        arp_packet[19] = 0x04; // 协议地址长度
-        arp_packet[20..22].copy_from_slice(&[0x00, 0x01]); // 操作类型 (ARP Request)
-        arp_packet[22..28].copy_from_slice(&smac); // 发送方硬件地址
-        arp_packet[28..32].copy_from_slice(&[10, 38, 255, 149]); // 发送方协议地址
-        arp_packet[32..38].copy_from_slice(&dest_hw); // 目标硬件地址
-        arp_packet[38..42].copy_from_slice(&dhost); // 目标协议地址
-
-        let dst_addr = unsafe {
-            // Initialise a `SocketAddr` byte calling `getsockname(2)`.
-            let mut addr_storage: libc::sockaddr_storage = mem::zeroed();
-            let mut len = mem::size_of_val(&addr_storage) as libc::socklen_t;
-
-            // The `getsockname(2)` system call will intiliase `storage` for
-            // us, setting `len` to the correct length.
-            let res = libc::getsockname(
-                self.arp.socket.as_raw_fd(),
-                (&mut addr_storage as *mut libc::sockaddr_storage).cast(),
-                &mut len,
-            );
-            if res == -1 {
-                return Err(std::io::Error::last_os_error());
-            }
-            SockAddr::new(addr_storage, 20)
+        let name = match dstip.into() {
+            IpAddr::V4(addr) => route::lookup(addr.octets())?.iface,
+            IpAddr::V6(addr) => route::lookup6(addr.octets())?.iface,
         };
+        iface::find(&self.ifaces, &name).cloned()
+    }
 
-        // let arp_packet = [255, 255, 255, 255, 255, 255, 152, 141, 70, 92, 99, 59, 8, 6, 0, 1, 8, 0, 6, 4, 0, 1, 152, 141, 70, 92, 99, 59, 10, 38, 255, 149, 255, 255, 255, 255, 255, 255, 13, 107, 21, 200];
+    /// 通过接口 `iface` 发送一帧，离线模式下返回错误
+    fn send_frame(&self, iface: &Interface, frame: &[u8]) -> std::io::Result<usize> {
+        let etype = match frame.get(12..14) {
+            Some(&[hi, lo]) => u16::from_be_bytes([hi, lo]) as libc::c_int,
+            _ => libc::ETH_P_IP,
+        };
+        match &self.source {
+            Source::Live(socket) => socket.send_to(frame, &link_addr(iface.index, etype)),
+            Source::File(_) => Err(offline()),
+        }
+    }
 
-        println!("{:?}", dst_addr.domain());
+    /// 按参数打开捕获文件，此后捕获到的帧都会写入其中
+    pub fn open_capture(&mut self, args: &CaptureArgs) -> std::io::Result<()> {
+        if let Some(path) = &args.write {
+            let macs = self
+                .ifaces
+                .iter()
+                .map(|iface| (iface.name.clone(), iface.mac))
+                .collect::<Vec<_>>();
+            let writer = PcapWriter::create(path, args.format, S as u32, &macs)?;
+            let ifindexes = self.ifaces.iter().map(|iface| iface.index).collect();
+            self.pcap = Some((writer, ifindexes));
+        }
+        Ok(())
+    }
 
-        // 绑定到指定 IP 地址和接口
-        // self.arp.bind(&dst_addr)?;
-        // println!("connect success!");
-        // self.arp.bind(&SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0).into())?;
-        self.arp.send_to(&arp_packet, &dst_addr)?;
-        println!("send success!");
+    /// 按参数打开过滤结果的日志，文件头记录接口、捕获文件与过滤条件
+    pub fn open_log(&mut self, args: &FilterArgs, expr: Option<&Expr>) -> std::io::Result<()> {
+        let logging = &args.logging;
+        let Some(dir) = &logging.log else {
+            return Ok(());
+        };
+        let rotation = Rotation {
+            size: Some(logging.log_size).filter(|&size| size > 0),
+            interval: logging
+                .log_interval
+                .filter(|&secs| secs > 0.0)
+                .map(Duration::from_secs_f64),
+            keep: logging.log_keep,
+            gzip: logging.log_gzip,
+        };
+        let iface = self.iface.as_ref().map(|iface| iface.name.clone());
+        let file = args.capture.read.as_ref();
+        let file = file.map(|path| path.display().to_string());
+        let header = Value::object([
+            ("version", SCHEMA_VERSION.into()),
+            ("type", "header".into()),
+            ("interface", iface.into()),
+            ("file", file.into()),
+            ("filter", expr.map(|expr| expr.to_string()).into()),
+        ]);
+        self.log = Some(LogWriter::create(dir, rotation, header)?);
+        Ok(())
+    }
 
-        let (data, addr) = dbg!(self.arp.recive()?);
+    /// 捕获时在内核中按 `expr` 预先过滤。BPF 无法判断的帧仍会送到用户态，由 `expr` 求值决定
+    fn attach_filter(&self, expr: &Expr) -> std::io::Result<()> {
+        let Source::Live(socket) = &self.source else {
+            return Ok(());
+        };
+        match bpf::compile(Some(expr)) {
+            Some(program) => socket.attach_filter(program.insns()),
+            None => {
+                eprintln!("{}", msg!("filter.bpf_fallback"));
+                Ok(())
+            }
+        }
+    }
 
-        if data.len() > 42 && &data[20..22] == [0x00, 0x02] {
-            Ok(data[22..28].try_into().unwrap())
-        } else {
-            Err(std::io::Error::from_raw_os_error(22))
+    /// 将一帧写入捕获文件（若已打开）
+    fn record(&mut self, frame: &Frame) -> std::io::Result<()> {
+        if let Some((writer, ifindexes)) = &mut self.pcap {
+            let iface = frame
+                .ifindex
+                .and_then(|idx| ifindexes.iter().position(|&i| i == idx))
+                .unwrap_or(0);
+            writer.write_packet(iface as u32, frame.ts, &frame.data, frame.origlen)?;
+            writer.flush()?;
         }
+        Ok(())
     }
 
-    pub fn send_file(
-        &self,
-        ident: u16,
-        dhost: [u8; 6],
-        dstip: [u8; 4],
-        protocol: Protocol,
-        file: &Path,
-        radix: u32,
-    ) -> std::io::Result<usize> {
-        let content = fs::read_to_string(file)?;
-        self.send(ident, dhost, dstip, protocol, &content, radix)
+    pub fn send(&mut self, ident: u16, args: &SendArgs) -> std::io::Result<usize> {
+        let buf = match &args.file {
+            Some(file) => fs::read(file)?,
+            None => args.text.clone().unwrap_or_default().into_bytes(),
+        };
+        let content = args.encoding.decode(&buf)?;
+
+        match (args.destip, args.srcip) {
+            (IpAddr::V4(dstip), None | Some(IpAddr::V4(_))) => {
+                self.send_v4(ident, dstip.octets(), args, content)
+            }
+            (IpAddr::V6(dstip), None | Some(IpAddr::V6(_))) => {
+                self.send_v6(dstip.octets(), args, content)
+            }
+            _ => Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                msg!("send.version_mismatch"),
+            )),
+        }
     }
 
-    pub fn send(
-        &self,
+    fn send_v4(
+        &mut self,
         ident: u16,
-        dhost: [u8; 6],
         dstip: [u8; 4],
-        protocol: Protocol,
-        buf: &str,
-        radix: u32,
+        args: &SendArgs,
+        mut content: Vec<u8>,
     ) -> std::io::Result<usize> {
-        let smac = self.macs[0].1;
+        let iface = self.out_iface(dstip)?;
+        let dhost = match args.dhost {
+            Some(mac) => mac,
+            None => self.get_mac(dstip)?,
+        };
 
-        let content = buf
-            .chars()
-            .map(|c| u8::from_str_radix(&c.to_string(), radix).expect("content must be bytes!!!"))
-            .collect::<Vec<_>>();
+        let ehdr = EtherHdr::new(dhost, iface.mac, EtherKind::IP).vlans(stack_vlans(&args.vlan));
+
+        let ippacket = IPHdr::new(args.ident.unwrap_or(ident))
+            .source(match args.srcip {
+                Some(IpAddr::V4(srcip)) => srcip.octets(),
+                _ => iface.addr()?,
+            })
+            .destination(dstip)
+            .protocol(args.protocol)
+            .ttl(args.ttl)
+            .tos(args.tos)
+            .flag(args.df, args.mf)
+            .offset(args.offset);
+        let ippacket = ip_options(ippacket, args)?;
+
+        if args.protocol == Protocol::UDP {
+            let udphdr = UdpHdr::new(args.sport, args.dport).checksum(&ippacket, &content);
+            let mut datagram = udphdr.to_bytes();
+            datagram.extend(content);
+            content = datagram;
+        }
 
-        let ehdr = EtherHdr {
-            dhost,
-            shost: smac,
-            etype: EtherKind::IP,
+        let len = u16::try_from(content.len())
+            .ok()
+            .filter(|&len| len <= u16::MAX - ippacket.ihl as u16)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    msg!("send.too_long", len = content.len()),
+                )
+            })?;
+        if !frag::fits(&ippacket, content.len()) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                msg!(
+                    "send.offset_too_large",
+                    offset = args.offset,
+                    len = content.len()
+                ),
+            ));
+        }
+
+        let mtu = (iface.mtu as usize).saturating_sub(ippacket.ihl as usize);
+        let size = match args.frag_size {
+            Some(_) if args.df => {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    msg!("send.df_frag_size"),
+                ))
+            }
+            Some(size) => Some(size as usize),
+            None if iface.mtu > 0 && content.len() > mtu => Some(mtu),
+            None => None,
+        };
+        let packets = match size {
+            Some(_) if args.df => {
+                return Err(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    msg!(
+                        "send.mtu_df",
+                        len = ippacket.ihl as usize + content.len(),
+                        iface = iface.name,
+                        mtu = iface.mtu
+                    ),
+                ))
+            }
+            Some(size) => frag::fragment(&ippacket, &content, size),
+            // 所有字段确定之后再计算首部校验和
+            None => vec![(ippacket.payload_len(len).checksum(), content)],
         };
 
-        let ippacket = IPHdr::new(ident)
-            .destination(dstip)
-            .protocol(protocol)
-            .checksum();
+        let mut sent = 0;
+        for (iphdr, data) in packets {
+            let mut output = (ehdr.clone(), iphdr).to_bytes();
+            output.extend(data);
+            sent += self.send_frame(&iface, &output)?;
+        }
+        Ok(sent)
+    }
+
+    /// 发送 IPv6 报文。IPv6 的分片只能由源主机完成，这里不支持，超过 MTU 时返回错误
+    fn send_v6(
+        &mut self,
+        dstip: [u8; 16],
+        args: &SendArgs,
+        mut content: Vec<u8>,
+    ) -> std::io::Result<usize> {
+        let iface = self.out_iface(dstip)?;
+        let dhost = match args.dhost {
+            Some(mac) => mac,
+            None => self.get_mac6(dstip)?,
+        };
+        let srcip = match args.srcip {
+            Some(IpAddr::V6(srcip)) => srcip.octets(),
+            _ => iface.addr6(dstip)?,
+        };
+
+        let ehdr = EtherHdr::new(dhost, iface.mac, EtherKind::IPv6).vlans(stack_vlans(&args.vlan));
+        let mut iphdr = Ipv6Hdr::new(srcip, dstip, args.protocol).hlim(args.ttl);
+        iphdr.tclass = args.tos;
+
+        if args.protocol == Protocol::UDP {
+            let udphdr = UdpHdr::new(args.sport, args.dport).checksum(&iphdr, &content);
+            let mut datagram = udphdr.to_bytes();
+            datagram.extend(content);
+            content = datagram;
+        }
+
+        if iface.mtu > 0 && 40 + content.len() > iface.mtu as usize {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                msg!(
+                    "send.mtu",
+                    len = 40 + content.len(),
+                    iface = iface.name,
+                    mtu = iface.mtu
+                ),
+            ));
+        }
+        let iphdr = iphdr.payload_len(content.len() as u16);
 
-        let mut output = (ehdr, ippacket).to_bytes();
+        let mut output = (ehdr, iphdr).to_bytes();
         output.extend(content);
+        self.send_frame(&iface, &output)
+    }
 
-        self.socket.send_to(&output, &self.addr)
+    /// 在 `until` 之前接收一帧，超时返回 `None`
+    fn recive_until(&mut self, until: Instant) -> std::io::Result<Option<Vec<u8>>> {
+        match &mut self.source {
+            Source::Live(socket) => socket.recive_until(until),
+            Source::File(_) => Err(offline()),
+        }
     }
 
-    pub fn analyz(&mut self) -> std::io::Result<()> {
-        let mut table = HashMap::new();
+    pub fn filter(&mut self, args: &FilterArgs, expr: Option<&Expr>) -> std::io::Result<()> {
+        if let Some(expr) = expr {
+            self.attach_filter(expr)?;
+        }
+        signal::catch_interrupt()?;
+        let mut writer = RecordWriter::new(std::io::stdout(), args.output, &PACKET_COLUMNS)?;
+        let mut malformed = 0usize;
+        let mut total = 0usize;
+        let mut matched = 0usize;
+        let mut reasm = Reassembler::new(FRAG_TIMEOUT);
         loop {
-            let (data, addr) = self.socket.recive()?;
-            let ((_, iphdr), buf) = <(EtherHdr, IPHdr)>::from_bytes(&data);
-            let num = table.get(&iphdr.protocol).unwrap_or(&0);
-            table.insert(iphdr.protocol, num + 1);
-            println!("============IP报文数据分析============");
-            for (protocol, num) in &table {
-                print!("  协议：{protocol:?}=>{num},");
+            let mut frame = match self.source.next_frame() {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => match signal::interrupted() {
+                    true => break,
+                    false => continue,
+                },
+                Err(e) => return Err(e),
+            };
+            total += 1;
+            if let Some(snaplen) = args.snaplen {
+                frame.data.truncate(snaplen as usize);
+            }
+            let (ethdr, nethdr, buf) = match decode_net(&frame.data) {
+                Ok(packet) => packet,
+                // 非 IP 帧
+                Err(ParseError::UnknownType {
+                    layer: Layer::Ether,
+                    ..
+                }) => continue,
+                Err(e) => {
+                    malformed += 1;
+                    eprintln!("{}", msg!("filter.malformed", count = malformed, error = e));
+                    continue;
+                }
+            };
+            let ip_verdict = match &nethdr {
+                NetHdr::V4(iphdr) => Some(Verdict::new(iphdr.verify(), false, || {
+                    iphdr.clone().checksum().chksum
+                })),
+                NetHdr::V6(_) => None,
+            };
+            // 去掉链路层的填充。报文被截断时无法检验传输层校验和
+            let (buf, complete) = match nethdr.payload_len() {
+                Some(len) if len <= buf.len() => (&buf[..len], true),
+                Some(_) => (buf, false),
+                None => (buf, true),
+            };
+            let at = ethdr.size() + nethdr.header_len();
+            let reassembled;
+            let upper = match &nethdr {
+                NetHdr::V4(iphdr) if iphdr.is_fragment() => {
+                    match reasm.push(iphdr, buf, frame.ts) {
+                        Some(datagram) => {
+                            reassembled = datagram;
+                            Upper::Reassembled(reassembled.0.protocol, &reassembled.1[..])
+                        }
+                        None => Upper::Cached,
+                    }
+                }
+                NetHdr::V6(hdr) if hdr.is_fragment() => Upper::Fragment(buf),
+                _ => Upper::Data(nethdr.protocol(), buf, complete),
+            };
+            let (transport, data) = match upper {
+                Upper::Data(protocol, buf, complete) => {
+                    decode_transport(&nethdr, protocol, buf, complete, frame.offloaded)
+                }
+                Upper::Reassembled(protocol, buf) => {
+                    decode_transport(&nethdr, protocol, buf, true, frame.offloaded)
+                }
+                Upper::Cached | Upper::Fragment(_) => (Transport::Other, buf),
+            };
+            if expr.is_some_and(|expr| !expr.matches(&transport.packet(&ethdr, &nethdr))) {
+                continue;
+            }
+            let bad = ip_verdict.is_some_and(|v| v.is_invalid())
+                || transport.verdict().is_some_and(|v| v.is_invalid());
+            if args.bad_checksum && !bad {
+                continue;
+            }
+
+            matched += 1;
+            self.record(&frame)?;
+            let layers = Layers {
+                ether: &ethdr,
+                net: &nethdr,
+                ip_verdict,
+                upper: &upper,
+                upper_range: at..at + buf.len(),
+                transport: &transport,
+                data,
+            };
+            // 日志与结构化输出共用同一条记录，只在需要时构造
+            let structured = args.output != OutputFormat::Text;
+            let record = (self.log.is_some() || structured).then(|| layers.record(&frame));
+            if let Some(record) = record {
+                if let Some(log) = &mut self.log {
+                    log.write(&record, frame.ts)?;
+                }
+                if structured {
+                    writer.write(&record)?;
+                    continue;
+                }
+            }
+            if args.verbose > 0 {
+                layers.print_tree(&frame, args);
+                continue;
+            }
+            println!("{}", msg!("filter.title"));
+            if !ethdr.vlans.is_empty() {
+                let tags = ethdr
+                    .vlans
+                    .iter()
+                    .map(|tag| tag.to_string())
+                    .collect::<Vec<_>>();
+                println!("{}", msg!("filter.vlans", tags = tags.join(" -> ")));
+            }
+            match (&nethdr, ip_verdict) {
+                (NetHdr::V4(iphdr), Some(verdict)) => print_ipv4(iphdr, verdict),
+                (NetHdr::V6(hdr), _) => print_ipv6(hdr),
+                _ => {}
+            }
+            match upper {
+                Upper::Cached => {
+                    println!("{}", msg!("filter.frag_cached"));
+                    println!("{}", msg!("filter.end"));
+                    continue;
+                }
+                Upper::Fragment(buf) => {
+                    println!("{}", msg!("filter.frag_data", data = HexDump::new(buf)));
+                    println!("{}", msg!("filter.end"));
+                    continue;
+                }
+                Upper::Reassembled(_, buf) => {
+                    let len = nethdr.header_len() + buf.len();
+                    println!("{}", msg!("filter.reassembled", len = len));
+                }
+                Upper::Data(..) => {}
+            }
+            match &transport {
+                Transport::Tcp(tcphdr, verdict) => print_tcp(tcphdr, *verdict),
+                Transport::Udp(udphdr, verdict) => print_udp(udphdr, *verdict),
+                Transport::Icmp(icmp, verdict) => print_icmp(icmp, *verdict),
+                Transport::Icmpv6(icmp, verdict) => print_icmpv6(icmp, *verdict),
+                Transport::Malformed(protocol, e) => {
+                    let protocol = format!("{protocol:?}");
+                    println!(
+                        "{}",
+                        msg!("filter.bad_header", protocol = protocol, error = e)
+                    )
+                }
+                Transport::Other => {}
+            }
+            if !data.is_empty() {
+                println!("{}", msg!("filter.data", data = HexDump::new(data)));
+            }
+            println!("{}", msg!("filter.end"));
+        }
+        if let Some(log) = &mut self.log {
+            log.finish()?;
+        }
+        let done = msg!(
+            "filter.done",
+            total = total,
+            matched = matched,
+            malformed = malformed
+        );
+        if args.output != OutputFormat::Text {
+            // 统计信息输出到标准错误，以免混入记录
+            eprintln!("{done}");
+            return writer.finish(Value::object([
+                ("frames", total.into()),
+                ("matched", matched.into()),
+                ("malformed", malformed.into()),
+                ("reassembly_expired", reasm.expired.into()),
+                ("reassembly_overlaps", reasm.overlaps.into()),
+            ]));
+        }
+        println!("{done}");
+        if reasm.expired > 0 || reasm.overlaps > 0 {
+            let stats = msg!(
+                "filter.reassembly",
+                expired = reasm.expired,
+                overlaps = reasm.overlaps
+            );
+            println!("{stats}");
+        }
+        Ok(())
+    }
+}
+
+/// 网络层之上的数据
+enum Upper<'a> {
+    /// 未分片的报文：协议、数据以及数据是否完整
+    Data(Protocol, &'a [u8], bool),
+    /// 重组完成的数据报
+    Reassembled(Protocol, &'a [u8]),
+    /// 已缓存、等待重组的 IPv4 分片
+    Cached,
+    /// 不重组的 IPv6 分片
+    Fragment(&'a [u8]),
+}
+
+/// 传输层首部及其校验和的检验结果
+enum Transport {
+    Tcp(TcpHdr, Verdict),
+    Udp(UdpHdr, Verdict),
+    Icmp(ICMP, Verdict),
+    Icmpv6(Icmpv6, Verdict),
+    /// 首部解析失败
+    Malformed(Protocol, ParseError),
+    /// 不解析的协议
+    Other,
+}
+
+impl Transport {
+    fn verdict(&self) -> Option<Verdict> {
+        match self {
+            Transport::Tcp(_, verdict)
+            | Transport::Udp(_, verdict)
+            | Transport::Icmp(_, verdict)
+            | Transport::Icmpv6(_, verdict) => Some(*verdict),
+            Transport::Malformed(..) | Transport::Other => None,
+        }
+    }
+
+    /// 供过滤表达式求值的报文
+    fn packet<'a>(&'a self, ether: &'a EtherHdr, net: &'a NetHdr) -> Packet<'a> {
+        let mut packet = Packet::new(ether, net);
+        match self {
+            Transport::Tcp(hdr, _) => packet.tcp = Some(hdr),
+            Transport::Udp(hdr, _) => packet.udp = Some(hdr),
+            Transport::Icmp(hdr, _) => packet.icmp = Some(hdr),
+            Transport::Icmpv6(hdr, _) => packet.icmpv6 = Some(hdr),
+            Transport::Malformed(..) | Transport::Other => {}
+        }
+        packet
+    }
+}
+
+/// 一个匹配报文的各层解析结果
+struct Layers<'a> {
+    ether: &'a EtherHdr,
+    net: &'a NetHdr,
+    ip_verdict: Option<Verdict>,
+    upper: &'a Upper<'a>,
+    /// 网络层之上的数据在帧中的范围
+    upper_range: Range<usize>,
+    transport: &'a Transport,
+    data: &'a [u8],
+}
+
+impl Layers<'_> {
+    /// 结构化输出的记录，各层首部为 `null` 表示该层不存在或未解析
+    fn record(&self, frame: &Frame) -> Value {
+        let ip = match (self.net, self.ip_verdict) {
+            (NetHdr::V4(hdr), Some(verdict)) => output::ipv4(hdr, verdict),
+            (NetHdr::V4(hdr), None) => output::ipv4(hdr, Verdict::Truncated),
+            (NetHdr::V6(hdr), _) => hdr.into(),
+        };
+        let fragment = match self.upper {
+            Upper::Data(..) => None,
+            Upper::Reassembled(..) => Some("reassembled"),
+            Upper::Cached => Some("cached"),
+            Upper::Fragment(_) => Some("unreassembled"),
+        };
+        let mut record = Value::object([
+            ("version", SCHEMA_VERSION.into()),
+            ("ts", output::timestamp(frame.ts)),
+            ("ifindex", frame.ifindex.into()),
+            ("len", frame.origlen.into()),
+            ("caplen", frame.data.len().into()),
+            ("ether", self.ether.into()),
+            ("ip", ip),
+            ("fragment", fragment.into()),
+        ]);
+        let (mut tcp, mut udp, mut icmp, mut icmpv6, mut error) = Default::default();
+        match self.transport {
+            Transport::Tcp(hdr, verdict) => tcp = output::tcp(hdr, *verdict),
+            Transport::Udp(hdr, verdict) => udp = output::udp(hdr, *verdict),
+            Transport::Icmp(hdr, verdict) => icmp = output::icmp(hdr, *verdict),
+            Transport::Icmpv6(hdr, verdict) => icmpv6 = output::icmpv6(hdr, *verdict),
+            Transport::Malformed(protocol, e) => {
+                let protocol = format!("{protocol:?}");
+                let text =
+                    output::english(|| msg!("filter.bad_header", protocol = protocol, error = e));
+                error = Value::from(text)
             }
-            println!("\n=======================================");
+            Transport::Other => {}
         }
+        record.push("tcp", tcp);
+        record.push("udp", udp);
+        record.push("icmp", icmp);
+        record.push("icmpv6", icmpv6);
+        record.push("error", error);
+        record.push("payload_len", self.data.len());
+        record.push("payload", output::hex(self.data));
+        record
     }
 
-    pub fn filter(
-        &mut self,
-        src_mac: Option<[u8; 6]>,
-        dst_mac: Option<[u8; 6]>,
-        shost: Option<[u8; 4]>,
-        dhost: Option<[u8; 4]>,
-        log: bool,
-    ) -> std::io::Result<()> {
-        loop {
-            let (data, addr) = self.socket.recive()?;
-            let ((ethdr, iphdr), buf) = <(EtherHdr, IPHdr)>::from_bytes(&data);
-            let smac_flag = src_mac.is_some_and(|mac| ethdr.shost == mac) || src_mac.is_none();
-            let dmac_flag = dst_mac.is_some_and(|mac| ethdr.dhost == mac) || dst_mac.is_none();
-            let sip_flag = shost.is_some_and(|ip| iphdr.source == ip) || shost.is_none();
-            let dip_flag = dhost.is_some_and(|ip| iphdr.destinaiton == ip) || dhost.is_none();
-
-            if smac_flag && dmac_flag && sip_flag && dip_flag {
-                println!("============IP报文数据分析============");
-                println!(
-                    "IP版本：{}, 首部长：{} byte, TOS：{}",
-                    iphdr.version, iphdr.ihl, iphdr.tos
-                );
-                println!("数据长度: {}, 报文ID：{}", iphdr.totlen, iphdr.ident);
-                println!("允许分片：{}, 已分片：{}", iphdr.flag.df, iphdr.flag.mf);
-                println!("片偏移：{} byte", iphdr.offset);
-                println!("生存期：{} 跳", iphdr.ttl);
-                println!("协议：{:?}", iphdr.protocol);
-                println!("校验和：{}", iphdr.chksum);
-                println!(
-                    "源: {}, 目的IP：{}",
-                    iphdr.source.map(|n| n.to_string()).join("."),
-                    iphdr.destinaiton.map(|n| n.to_string()).join("."),
-                );
-                if !iphdr.opt_section.is_empty() {
-                    println!("额外报首部信息：{:?}", iphdr.opt_section);
-                }
-                println!("数据：\n{:?}", buf);
-                println!("=======================================");
+    /// 帧的字段树。重组出数据报时，其传输层与数据另成一棵树，偏移相对于重组后的数据
+    fn trees<'b>(&'b self, frame: &'b Frame) -> (Tree<'b>, Option<Tree<'b>>) {
+        let mut tree = Tree::new(&frame.data);
+        tree.push(Section::Link, dissect::ether(self.ether, 0));
+        let at = self.ether.size();
+        let net = match (self.net, self.ip_verdict) {
+            (NetHdr::V4(hdr), verdict) => {
+                dissect::ipv4(hdr, verdict.unwrap_or(Verdict::Truncated), at)
+            }
+            (NetHdr::V6(hdr), _) => dissect::ipv6(hdr, at),
+        };
+        tree.push(Section::Network, net);
+        let range = self.upper_range.clone();
+        let fragment = Node::new(msg!("tree.fragment", len = range.len()), range.clone());
+        match self.upper {
+            Upper::Data(..) => {
+                self.push_upper(&mut tree, range);
+                (tree, None)
+            }
+            Upper::Reassembled(_, buf) => {
+                tree.push(Section::Payload, fragment);
+                let mut datagram = Tree::new(buf);
+                self.push_upper(&mut datagram, 0..buf.len());
+                (tree, Some(datagram))
+            }
+            Upper::Cached | Upper::Fragment(_) => {
+                tree.push(Section::Payload, fragment);
+                (tree, None)
+            }
+        }
+    }
+
+    /// 把传输层首部与数据加入字段树，`range` 为网络层之上的数据在树的缓冲区中的范围
+    fn push_upper(&self, tree: &mut Tree, range: Range<usize>) {
+        let at = range.start;
+        let data = dissect::data(self.data.len(), range.end - self.data.len());
+        let node = match self.transport {
+            Transport::Tcp(hdr, verdict) => dissect::tcp(hdr, *verdict, at),
+            Transport::Udp(hdr, verdict) => dissect::udp(hdr, *verdict, at),
+            Transport::Icmp(hdr, verdict) => dissect::icmp(hdr, *verdict, at),
+            Transport::Icmpv6(hdr, verdict) => dissect::icmpv6(hdr, *verdict, at),
+            // 首部解析失败时其余数据都归入传输层
+            Transport::Malformed(protocol, e) => {
+                let protocol = format!("{protocol:?}");
+                let label = msg!("filter.bad_header", protocol = protocol, error = e);
+                tree.push(Section::Transport, Node::new(label, range));
+                return;
+            }
+            Transport::Other => {
+                tree.push(Section::Payload, data);
+                return;
             }
+        };
+        tree.push(Section::Transport, node);
+        if !self.data.is_empty() {
+            tree.push(Section::Payload, data);
+        }
+    }
+
+    /// `-v` 及以上的文本输出：字段树与十六进制转储
+    fn print_tree(&self, frame: &Frame, args: &FilterArgs) {
+        let caplen = frame.data.len();
+        let text = msg!("tree.frame", len = frame.origlen, caplen = caplen);
+        println!("{text}");
+        let (tree, datagram) = self.trees(frame);
+        println!("{}", tree.render(args.verbose));
+        println!("{}", tree.dump(args.highlight));
+        if let (Some(datagram), Upper::Reassembled(_, buf)) = (datagram, self.upper) {
+            let len = self.net.header_len() + buf.len();
+            println!("{}", msg!("filter.reassembled", len = len));
+            println!("{}", datagram.render(args.verbose));
+            println!("{}", datagram.dump(args.highlight));
+        }
+        println!();
+    }
+}
+
+/// `--output csv` 的各列
+const PACKET_COLUMNS: [Column; 20] = [
+    ("ts", |r| r.at(&["ts"])),
+    ("ifindex", |r| r.at(&["ifindex"])),
+    ("len", |r| r.at(&["len"])),
+    ("src_mac", |r| r.at(&["ether", "src"])),
+    ("dst_mac", |r| r.at(&["ether", "dst"])),
+    ("ip_version", |r| r.at(&["ip", "version"])),
+    ("src", |r| r.at(&["ip", "src"])),
+    ("dst", |r| r.at(&["ip", "dst"])),
+    ("protocol", |r| r.at(&["ip", "protocol"])),
+    ("ttl", |r| r.at(&["ip", "ttl"]).or(r.at(&["ip", "hlim"]))),
+    ("ip_checksum_status", |r| r.at(&["ip", "checksum_status"])),
+    ("fragment", |r| r.at(&["fragment"])),
+    ("sport", |r| {
+        r.at(&["tcp", "sport"]).or(r.at(&["udp", "sport"]))
+    }),
+    ("dport", |r| {
+        r.at(&["tcp", "dport"]).or(r.at(&["udp", "dport"]))
+    }),
+    ("tcp_flags", |r| r.at(&["tcp", "flags"])),
+    ("icmp_type", |r| {
+        r.at(&["icmp", "type"]).or(r.at(&["icmpv6", "type"]))
+    }),
+    ("icmp_code", |r| {
+        r.at(&["icmp", "code"]).or(r.at(&["icmpv6", "code"]))
+    }),
+    ("checksum_status", |r| {
+        ["tcp", "udp", "icmp", "icmpv6"]
+            .iter()
+            .map(|layer| r.at(&[layer, "checksum_status"]))
+            .fold(&Value::Null, Value::or)
+    }),
+    ("error", |r| r.at(&["error"])),
+    ("payload_len", |r| r.at(&["payload_len"])),
+];
+
+/// 解析传输层首部并检验校验和，返回首部与其后的数据。
+/// `complete` 表示 `buf` 是完整的上层数据，`offloaded` 表示校验和交由网卡计算。
+fn decode_transport<'a>(
+    nethdr: &NetHdr,
+    protocol: Protocol,
+    buf: &'a [u8],
+    complete: bool,
+    offloaded: bool,
+) -> (Transport, &'a [u8]) {
+    let verdict = |valid: &dyn Fn() -> bool, expected: &dyn Fn() -> u16| match complete {
+        true => Verdict::new(valid(), offloaded, expected),
+        false => Verdict::Truncated,
+    };
+    let result = match (protocol, nethdr) {
+        (Protocol::TCP, _) => TcpHdr::from_bytes(buf).map(|(hdr, rest)| {
+            let verdict = verdict(&|| hdr.verify(nethdr, rest), &|| {
+                hdr.clone().checksum(nethdr, rest).chksum
+            });
+            (Transport::Tcp(hdr, verdict), rest)
+        }),
+        (Protocol::UDP, _) => UdpHdr::from_bytes(buf).map(|(hdr, rest)| {
+            let verdict = match (hdr.chksum, nethdr) {
+                (0, NetHdr::V4(_)) => Verdict::Absent,
+                _ => verdict(&|| hdr.verify(nethdr, rest), &|| {
+                    let len = rest.len().min((hdr.len as usize).saturating_sub(8));
+                    hdr.clone().checksum(nethdr, &rest[..len]).chksum
+                }),
+            };
+            (Transport::Udp(hdr, verdict), rest)
+        }),
+        (Protocol::ICMP, NetHdr::V4(_)) => ICMP::from_bytes(buf).map(|(hdr, rest)| {
+            let verdict = verdict(&|| hdr.verify(rest), &|| hdr.clone().checksum(rest).chksum);
+            (Transport::Icmp(hdr, verdict), rest)
+        }),
+        (Protocol::ICMPv6, NetHdr::V6(iphdr)) => Icmpv6::from_bytes(buf).map(|(hdr, rest)| {
+            // 邻居发现报文的选项已在首部中解析，校验时不能重复计入
+            let verdict = verdict(&|| hdr.verify(iphdr, rest), &|| {
+                hdr.clone().checksum(iphdr, rest).chksum
+            });
+            (Transport::Icmpv6(hdr, verdict), rest)
+        }),
+        _ => return (Transport::Other, buf),
+    };
+    match result {
+        Ok(decoded) => decoded,
+        Err(e) => (Transport::Malformed(protocol, e), buf),
+    }
+}
+
+fn print_ipv4(iphdr: &IPHdr, verdict: Verdict) {
+    let text = msg!(
+        "ipv4.version",
+        version = iphdr.version,
+        ihl = iphdr.ihl,
+        tos = iphdr.tos
+    );
+    println!("{text}");
+    let text = msg!("ipv4.length", len = iphdr.totlen, ident = iphdr.ident);
+    println!("{text}");
+    let text = msg!("ipv4.flags", df = iphdr.flag.df, mf = iphdr.flag.mf);
+    println!("{text}");
+    println!(
+        "{}",
+        msg!("ipv4.offset", offset = iphdr.offset as usize * 8)
+    );
+    println!("{}", msg!("ipv4.ttl", ttl = iphdr.ttl));
+    println!(
+        "{}",
+        msg!("ip.protocol", protocol = format!("{:?}", iphdr.protocol))
+    );
+    print_checksum(iphdr.chksum, verdict);
+    let text = msg!(
+        "ip.addrs",
+        src = Ipv4Addr::from(iphdr.source),
+        dst = Ipv4Addr::from(iphdr.destinaiton)
+    );
+    println!("{text}");
+    if !iphdr.options.is_empty() {
+        let options = iphdr
+            .options
+            .iter()
+            .map(|opt| opt.to_string())
+            .collect::<Vec<_>>();
+        print_options(&options);
+    }
+}
+
+fn print_ipv6(hdr: &Ipv6Hdr) {
+    let text = msg!(
+        "ipv6.version",
+        version = hdr.version,
+        tclass = hdr.tclass,
+        flow = format!("{:#07x}", hdr.flow)
+    );
+    println!("{text}");
+    println!("{}", msg!("ipv6.length", plen = hdr.plen, hlim = hdr.hlim));
+    if !hdr.exts.is_empty() {
+        let exts = hdr
+            .exts
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect::<Vec<_>>();
+        println!("{}", msg!("ipv6.exts", exts = exts.join(" -> ")));
+    }
+    println!(
+        "{}",
+        msg!("ip.protocol", protocol = format!("{:?}", hdr.protocol))
+    );
+    let text = msg!(
+        "ip.addrs",
+        src = Ipv6Addr::from(hdr.source),
+        dst = Ipv6Addr::from(hdr.destination)
+    );
+    println!("{text}");
+}
+
+fn print_checksum(chksum: u16, verdict: Verdict) {
+    println!(
+        "{}",
+        msg!("checksum.line", checksum = chksum, verdict = verdict)
+    );
+}
+
+fn print_options(options: &[String]) {
+    println!("{}", msg!("options.line", options = options.join(", ")));
+}
+
+fn print_ports(sport: u16, dport: u16) {
+    println!("{}", msg!("transport.ports", sport = sport, dport = dport));
+}
+
+fn print_ping(ping: &Ping) {
+    let text = msg!("icmp.ping", ident = ping.ident, seq = ping.seqnum);
+    println!("{text}");
+}
+
+fn print_tcp(tcphdr: &TcpHdr, verdict: Verdict) {
+    println!("{}", msg!("tcp.title"));
+    print_ports(tcphdr.sport, tcphdr.dport);
+    let text = msg!("tcp.seq", seq = tcphdr.seqnum, ack = tcphdr.acknum);
+    println!("{text}");
+    let text = msg!("tcp.doff", doff = tcphdr.doff, flags = tcphdr.flag);
+    println!("{text}");
+    let text = msg!("tcp.window", window = tcphdr.window, urgent = tcphdr.urgent);
+    println!("{text}");
+    print_checksum(tcphdr.chksum, verdict);
+    if !tcphdr.options.is_empty() {
+        let options = tcphdr
+            .options
+            .iter()
+            .map(|opt| opt.to_string())
+            .collect::<Vec<_>>();
+        print_options(&options);
+    }
+}
+
+fn print_udp(udphdr: &UdpHdr, verdict: Verdict) {
+    println!("{}", msg!("udp.title"));
+    print_ports(udphdr.sport, udphdr.dport);
+    println!("{}", msg!("udp.len", len = udphdr.len));
+    print_checksum(udphdr.chksum, verdict);
+}
+
+fn print_icmp(icmp: &ICMP, verdict: Verdict) {
+    println!("{}", msg!("icmp.title"));
+    let text = msg!(
+        "icmp.type",
+        typ = icmp.typ,
+        code = icmp.code,
+        description = icmp.typ_dsc()
+    );
+    println!("{text}");
+    print_checksum(icmp.chksum, verdict);
+    if let Some(ping) = &icmp.msg {
+        print_ping(ping);
+    }
+}
+
+fn print_icmpv6(icmp: &Icmpv6, verdict: Verdict) {
+    println!("{}", msg!("icmpv6.title"));
+    let text = msg!(
+        "icmp.type",
+        typ = icmp.typ,
+        code = icmp.code,
+        description = icmp.typ_dsc()
+    );
+    println!("{text}");
+    print_checksum(icmp.chksum, verdict);
+    match &icmp.msg {
+        Some(Icmpv6Msg::Echo(ping)) => print_ping(ping),
+        Some(Icmpv6Msg::RouterAdvert {
+            hop_limit,
+            managed,
+            other,
+            lifetime,
+            reachable,
+            retrans,
+            ..
+        }) => {
+            let text = msg!(
+                "nd.ra_flags",
+                hop_limit = hop_limit,
+                managed = managed,
+                other = other
+            );
+            println!("{text}");
+            let text = msg!(
+                "nd.ra_timers",
+                lifetime = lifetime,
+                reachable = reachable,
+                retrans = retrans
+            );
+            println!("{text}");
+        }
+        Some(Icmpv6Msg::NeighborSolicit { target, .. }) => {
+            println!("{}", msg!("nd.target", target = Ipv6Addr::from(*target)));
         }
+        Some(Icmpv6Msg::NeighborAdvert {
+            router,
+            solicited,
+            override_,
+            target,
+            ..
+        }) => {
+            println!("{}", msg!("nd.target", target = Ipv6Addr::from(*target)));
+            let text = msg!(
+                "nd.na_flags",
+                router = router,
+                solicited = solicited,
+                override_ = override_
+            );
+            println!("{text}");
+        }
+        Some(Icmpv6Msg::RouterSolicit { .. }) | None => {}
+    }
+    if let Some(msg) = &icmp.msg {
+        if !msg.options().is_empty() {
+            let options = msg
+                .options()
+                .iter()
+                .map(|opt| opt.to_string())
+                .collect::<Vec<_>>();
+            print_options(&options);
+        }
+    }
+}
+
+/// 按参数为 IPv4 首部加上选项
+fn ip_options(mut iphdr: IPHdr, args: &SendArgs) -> std::io::Result<IPHdr> {
+    // 与记录路由一样，源路由选项最多容纳 9 个地址
+    let route = args.lsrr.len().max(args.ssrr.len());
+    if route > 9 {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            msg!("send.route_too_long", len = route),
+        ));
+    }
+    if let Some(n) = args.record_route {
+        iphdr = iphdr.record_route(n as usize);
+    }
+    if let Some(n) = args.timestamp {
+        iphdr = iphdr.timestamp(n as usize);
+    }
+    if !args.lsrr.is_empty() {
+        iphdr = iphdr.source_route(false, args.lsrr.clone());
+    }
+    if !args.ssrr.is_empty() {
+        iphdr = iphdr.source_route(true, args.ssrr.clone());
+    }
+    if args.router_alert {
+        iphdr = iphdr.router_alert();
     }
+    if iphdr.options_overflow() {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            msg!("send.options_overflow", len = iphdr.options_len()),
+        ));
+    }
+    Ok(iphdr)
 }
 
-fn get_macs() -> Vec<(String, String)> {
-    let net = Path::new("/sys/class/net");
-    let entry =
-        std::fs::read_dir(net).expect(&format!("No such directory {}", net.to_str().unwrap()));
-
-    entry
-        .filter_map(|p| p.ok())
-        .map(|p| p.path().file_name().expect("No such file!").to_os_string())
-        .filter_map(|s| s.into_string().ok())
-        .map(|ifc| {
-            let iface = net.join(&ifc).join("address");
-            let mut f = std::fs::File::open(iface).expect("Error");
-            let mut macaddr = String::new();
-            f.read_to_string(&mut macaddr).expect("Error");
-            (ifc, macaddr)
+/// 按由外到内的顺序叠加标签：最内层为 802.1Q 标签，其余为 802.1ad 标签
+fn stack_vlans(tags: &[VlanTag]) -> Vec<VlanTag> {
+    let inner = tags.len().saturating_sub(1);
+    tags.iter()
+        .enumerate()
+        .map(|(idx, tag)| VlanTag {
+            tpid: if idx < inner { TPID_STAG } else { TPID_CTAG },
+            ..*tag
         })
-        .collect::<Vec<_>>()
+        .collect()
+}
+
+/// 以太网首部 `ethdr` 中类型字段的错误
+fn unknown_etype(ethdr: &EtherHdr) -> ParseError {
+    // 以 SNAP 封装时协议号位于首部末尾，否则为 VLAN 标签之后的类型或长度字段
+    let (offset, value) = match ethdr.llc {
+        Some(LlcHdr {
+            snap: Some(snap), ..
+        }) => (ethdr.size() - 2, snap.pid),
+        _ => (12 + ethdr.vlans.len() * 4, u16::from(ethdr.etype)),
+    };
+    ParseError::UnknownType {
+        layer: Layer::Ether,
+        offset,
+        value: value as u32,
+    }
+}
+
+/// 解析一个以太网帧中的 IPv4 报文。非 IPv4 帧返回 [`ParseError::UnknownType`]。
+fn decode(data: &[u8]) -> Result<((EtherHdr, IPHdr), &[u8]), ParseError> {
+    let (ethdr, _) = EtherHdr::from_bytes(data)?;
+    if ethdr.ethertype() != EtherKind::IP {
+        return Err(unknown_etype(&ethdr));
+    }
+    <(EtherHdr, IPHdr)>::from_bytes(data)
+}
+
+/// 解析一个以太网帧中的 IPv4 或 IPv6 报文。其他帧返回 [`ParseError::UnknownType`]。
+fn decode_net(data: &[u8]) -> Result<(EtherHdr, NetHdr, &[u8]), ParseError> {
+    let (ethdr, rest) = EtherHdr::from_bytes(data)?;
+    let consumed = data.len() - rest.len();
+    let (nethdr, rest) = match ethdr.ethertype() {
+        EtherKind::IP => IPHdr::from_bytes(rest).map(|(hdr, rest)| (NetHdr::V4(hdr), rest)),
+        EtherKind::IPv6 => Ipv6Hdr::from_bytes(rest).map(|(hdr, rest)| (NetHdr::V6(hdr), rest)),
+        _ => return Err(unknown_etype(&ethdr)),
+    }
+    .map_err(|e| e.shift(consumed))?;
+    Ok((ethdr, nethdr, rest))
+}
+
+fn offline() -> std::io::Error {
+    std::io::Error::new(ErrorKind::Unsupported, msg!("app.offline"))
 }
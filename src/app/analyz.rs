@@ -0,0 +1,599 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write as _},
+    hash::Hash,
+    io::{ErrorKind, IsTerminal, Write},
+    net::IpAddr,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    cli::AnalyzArgs,
+    head::{EtherHdr, EtherKind, Header, NetHdr, Protocol},
+    i18n::msg,
+    output::{self, mac, OutputFormat, Value, SCHEMA_VERSION},
+    signal,
+    source::{Frame, PacketSource},
+};
+
+use super::{decode_net, App, Source};
+
+/// 帧长分布的各区间下限，与 Wireshark 的分组长度统计一致
+const SIZE_BUCKETS: [usize; 7] = [0, 64, 128, 256, 512, 1024, 1518];
+/// 帧长分布中最长的柱
+const BAR_WIDTH: u64 = 40;
+
+/// 帧数与字节数
+#[derive(Debug, Clone, Copy, Default)]
+struct Counter {
+    packets: u64,
+    bytes: u64,
+}
+
+impl Counter {
+    fn add(&mut self, bytes: u32) {
+        self.packets += 1;
+        self.bytes += bytes as u64;
+    }
+}
+
+/// 按协议分类的键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Class {
+    /// IP 报文，按上层协议分类
+    Upper(Protocol),
+    /// 非 IP 帧，按类型字段分类
+    Link(EtherKind),
+    Malformed,
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Class::Upper(Protocol::Other(p)) => write!(f, "{}", msg!("analyz.ip_proto", p = p)),
+            Class::Upper(protocol) => write!(f, "{protocol:?}"),
+            Class::Link(EtherKind::Length(_)) => write!(f, "802.3"),
+            Class::Link(EtherKind::Other(etype)) => {
+                let etype = format!("{etype:#06x}");
+                write!(f, "{}", msg!("analyz.ethertype", etype = etype))
+            }
+            Class::Link(kind) => write!(f, "{kind:?}"),
+            Class::Malformed => write!(f, "{}", msg!("analyz.malformed")),
+        }
+    }
+}
+
+impl Class {
+    /// 结构化输出中的协议条目
+    fn value(&self, counter: &Counter) -> Value {
+        let mut value = match self {
+            Class::Upper(protocol) => Value::object([
+                ("layer", "ip".into()),
+                ("protocol", u8::from(*protocol).into()),
+            ]),
+            Class::Link(kind) => Value::object([
+                ("layer", "link".into()),
+                ("ethertype", u16::from(*kind).into()),
+            ]),
+            Class::Malformed => Value::object([("layer", "malformed".into())]),
+        };
+        value.push("name", output::english(|| self.to_string()));
+        value.push("packets", counter.packets);
+        value.push("bytes", counter.bytes);
+        value
+    }
+}
+
+/// 流量统计
+#[derive(Debug, Default)]
+struct Stats {
+    total: Counter,
+    classes: HashMap<Class, Counter>,
+    src_ip: HashMap<IpAddr, Counter>,
+    dst_ip: HashMap<IpAddr, Counter>,
+    src_mac: HashMap<[u8; 6], Counter>,
+    dst_mac: HashMap<[u8; 6], Counter>,
+    /// 各帧长区间的帧数
+    sizes: [u64; SIZE_BUCKETS.len()],
+    /// 生存期（跳数限制）为下标值的报文数
+    ttls: Vec<u64>,
+    frags4: u64,
+    frags6: u64,
+    /// 第一帧与最后一帧的时间戳
+    first: Option<SystemTime>,
+    last: Option<SystemTime>,
+}
+
+impl Stats {
+    fn add(&mut self, frame: &Frame) {
+        let len = frame.origlen;
+        self.total.add(len);
+        self.first.get_or_insert(frame.ts);
+        self.last = Some(frame.ts);
+        let bucket = SIZE_BUCKETS.iter().rposition(|&min| len as usize >= min);
+        self.sizes[bucket.unwrap_or(0)] += 1;
+
+        let Ok((ethdr, _)) = EtherHdr::from_bytes(&frame.data) else {
+            self.classes.entry(Class::Malformed).or_default().add(len);
+            return;
+        };
+        self.src_mac.entry(ethdr.shost).or_default().add(len);
+        self.dst_mac.entry(ethdr.dhost).or_default().add(len);
+        let nethdr = match ethdr.ethertype() {
+            EtherKind::IP | EtherKind::IPv6 => match decode_net(&frame.data) {
+                Ok((_, nethdr, _)) => nethdr,
+                Err(_) => {
+                    self.classes.entry(Class::Malformed).or_default().add(len);
+                    return;
+                }
+            },
+            kind => {
+                self.classes.entry(Class::Link(kind)).or_default().add(len);
+                return;
+            }
+        };
+        let class = Class::Upper(nethdr.protocol());
+        self.classes.entry(class).or_default().add(len);
+        self.src_ip.entry(nethdr.source()).or_default().add(len);
+        self.dst_ip
+            .entry(nethdr.destination())
+            .or_default()
+            .add(len);
+        let ttl = match &nethdr {
+            NetHdr::V4(hdr) => {
+                self.frags4 += hdr.is_fragment() as u64;
+                hdr.ttl
+            }
+            NetHdr::V6(hdr) => {
+                self.frags6 += hdr.is_fragment() as u64;
+                hdr.hlim
+            }
+        };
+        if self.ttls.is_empty() {
+            self.ttls = vec![0; 256];
+        }
+        self.ttls[ttl as usize] += 1;
+    }
+
+    /// 捕获文件中第一帧到最后一帧的时间跨度
+    fn span(&self) -> Duration {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => last.duration_since(first).unwrap_or_default(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// 非 IP 帧数
+    fn others(&self) -> u64 {
+        self.classes
+            .iter()
+            .filter(|(class, _)| matches!(class, Class::Link(_)))
+            .map(|(_, c)| c.packets)
+            .sum()
+    }
+
+    fn malformed(&self) -> u64 {
+        self.classes.get(&Class::Malformed).map_or(0, |c| c.packets)
+    }
+
+    /// 按帧数从多到少排列的协议
+    fn protocols(&self) -> Vec<(&Class, &Counter)> {
+        let mut classes = self.classes.iter().collect::<Vec<_>>();
+        classes.sort_by_key(|&(class, c)| (std::cmp::Reverse(c.packets), class.to_string()));
+        classes
+    }
+
+    /// 帧长区间的上限（含），最后一个区间没有上限
+    fn size_max(idx: usize) -> Option<usize> {
+        SIZE_BUCKETS.get(idx + 1).map(|next| next - 1)
+    }
+
+    /// 报文数最多的 `top` 个生存期
+    fn top_ttls(&self, top: usize) -> Vec<(usize, u64)> {
+        let mut ttls = self
+            .ttls
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(ttl, &count)| (ttl, count))
+            .collect::<Vec<_>>();
+        ttls.sort_by_key(|&(ttl, count)| (std::cmp::Reverse(count), ttl));
+        ttls.truncate(top);
+        ttls
+    }
+
+    /// 每秒帧数与比特率
+    fn rates(&self, elapsed: Duration) -> (f64, f64) {
+        let secs = elapsed.as_secs_f64();
+        match secs > 0.0 {
+            true => (
+                self.total.packets as f64 / secs,
+                self.total.bytes as f64 * 8.0 / secs,
+            ),
+            false => (0.0, 0.0),
+        }
+    }
+
+    /// 统计结果的 JSON 文档
+    fn summary(&self, elapsed: Duration, top: usize) -> Value {
+        let (pps, bps) = self.rates(elapsed);
+        let protocols = self
+            .protocols()
+            .into_iter()
+            .map(|(class, counter)| class.value(counter))
+            .collect::<Vec<_>>();
+        let sizes = self
+            .sizes
+            .iter()
+            .enumerate()
+            .map(|(idx, &count)| {
+                Value::object([
+                    ("min", SIZE_BUCKETS[idx].into()),
+                    ("max", Stats::size_max(idx).into()),
+                    ("packets", count.into()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let ttls = self
+            .top_ttls(top)
+            .into_iter()
+            .map(|(ttl, count)| Value::object([("ttl", ttl.into()), ("packets", count.into())]))
+            .collect::<Vec<_>>();
+        let addr = |addr: &IpAddr| addr.to_string();
+        Value::object([
+            ("version", SCHEMA_VERSION.into()),
+            ("packets", self.total.packets.into()),
+            ("bytes", self.total.bytes.into()),
+            ("duration", elapsed.as_secs_f64().into()),
+            ("pps", pps.into()),
+            ("bps", bps.into()),
+            ("first", self.first.map(output::timestamp).into()),
+            ("last", self.last.map(output::timestamp).into()),
+            ("protocols", protocols.into()),
+            ("top_src_ip", talkers_value(&self.src_ip, top, addr)),
+            ("top_dst_ip", talkers_value(&self.dst_ip, top, addr)),
+            ("top_src_mac", talkers_value(&self.src_mac, top, mac)),
+            ("top_dst_mac", talkers_value(&self.dst_mac, top, mac)),
+            ("sizes", sizes.into()),
+            ("ttl", ttls.into()),
+            (
+                "fragments",
+                Value::object([("ipv4", self.frags4.into()), ("ipv6", self.frags6.into())]),
+            ),
+            ("non_ip", self.others().into()),
+            ("malformed", self.malformed().into()),
+        ])
+    }
+
+    /// 统计结果的 CSV 表格，每行为 `section,key,packets,bytes`
+    fn csv(&self, top: usize) -> String {
+        let mut rows = vec![
+            "section,key,packets,bytes".to_string(),
+            format!("total,,{},{}", self.total.packets, self.total.bytes),
+        ];
+        let mut row = |section: &str, key: String, packets: u64, bytes: Option<u64>| {
+            let key = Value::Str(key).csv();
+            let bytes = bytes.map_or(String::new(), |b| b.to_string());
+            rows.push(format!("{section},{key},{packets},{bytes}"));
+        };
+        for (class, c) in self.protocols() {
+            let name = output::english(|| class.to_string());
+            row("protocol", name, c.packets, Some(c.bytes));
+        }
+        for (addr, c) in ranking(&self.src_ip, top) {
+            row("src_ip", addr.to_string(), c.packets, Some(c.bytes));
+        }
+        for (addr, c) in ranking(&self.dst_ip, top) {
+            row("dst_ip", addr.to_string(), c.packets, Some(c.bytes));
+        }
+        for (addr, c) in ranking(&self.src_mac, top) {
+            row("src_mac", mac(&addr), c.packets, Some(c.bytes));
+        }
+        for (addr, c) in ranking(&self.dst_mac, top) {
+            row("dst_mac", mac(&addr), c.packets, Some(c.bytes));
+        }
+        for (idx, &count) in self.sizes.iter().enumerate() {
+            let range = match Stats::size_max(idx) {
+                Some(max) => format!("{}-{max}", SIZE_BUCKETS[idx]),
+                None => format!("{}+", SIZE_BUCKETS[idx]),
+            };
+            row("size", range, count, None);
+        }
+        for (ttl, count) in self.top_ttls(top) {
+            row("ttl", ttl.to_string(), count, None);
+        }
+        row("fragments", "ipv4".into(), self.frags4, None);
+        row("fragments", "ipv6".into(), self.frags6, None);
+        row("non_ip", String::new(), self.others(), None);
+        row("malformed", String::new(), self.malformed(), None);
+        rows.join("\n") + "\n"
+    }
+
+    /// 统计报告，`elapsed` 为用于计算速率的时长，`top` 为排行的条目数
+    fn report(&self, elapsed: Duration, top: usize) -> String {
+        let mut out = String::new();
+        let secs = elapsed.as_secs_f64();
+        let (pps, bps) = self.rates(elapsed);
+        let _ = writeln!(out, "{}", msg!("filter.title"));
+        let summary = msg!(
+            "analyz.total",
+            packets = self.total.packets,
+            bytes = self.total.bytes,
+            secs = format!("{secs:.1}"),
+            pps = format!("{pps:.1}"),
+            rate = rate(bps)
+        );
+        let _ = writeln!(out, "{summary}");
+
+        let _ = writeln!(out, "{}", msg!("analyz.protocols"));
+        for (class, counter) in self.protocols() {
+            let share = counter.packets as f64 * 100.0 / self.total.packets.max(1) as f64;
+            let _ = writeln!(
+                out,
+                "  {:<16} {} {share:>5.1}%",
+                class.to_string(),
+                counts(counter)
+            );
+        }
+
+        let sections = [
+            (
+                msg!("analyz.src_ip"),
+                talkers(&self.src_ip, top, |addr| addr.to_string()),
+            ),
+            (
+                msg!("analyz.dst_ip"),
+                talkers(&self.dst_ip, top, |addr| addr.to_string()),
+            ),
+            (msg!("analyz.src_mac"), talkers(&self.src_mac, top, mac)),
+            (msg!("analyz.dst_mac"), talkers(&self.dst_mac, top, mac)),
+        ];
+        for (title, lines) in sections {
+            if lines.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "{}", msg!("analyz.top", title = title, top = top));
+            for line in lines {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+
+        let _ = writeln!(out, "{}", msg!("analyz.sizes"));
+        let peak = self.sizes.iter().copied().max().unwrap_or(0).max(1);
+        for (idx, &count) in self.sizes.iter().enumerate() {
+            let range = match Stats::size_max(idx) {
+                Some(max) => format!("{}-{max}", SIZE_BUCKETS[idx]),
+                None => format!("{}+", SIZE_BUCKETS[idx]),
+            };
+            let bar = "#".repeat((count * BAR_WIDTH).div_ceil(peak) as usize);
+            let line = format!("  {range:>9} {count:>8} {bar}");
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+
+        if !self.ttls.is_empty() {
+            let _ = writeln!(out, "{}", msg!("analyz.ttls", top = top));
+            for (ttl, count) in self.top_ttls(top) {
+                let _ = writeln!(out, "  TTL {ttl:>3} {count:>8}");
+            }
+        }
+
+        let fragments = msg!("analyz.fragments", ipv4 = self.frags4, ipv6 = self.frags6);
+        let _ = writeln!(out, "{fragments}");
+        let _ = writeln!(out, "{}", msg!("filter.end"));
+        out
+    }
+}
+
+/// 按帧数从多到少排列，取前 `top` 项
+fn ranking<K: Copy + Ord + Hash>(table: &HashMap<K, Counter>, top: usize) -> Vec<(K, Counter)> {
+    let mut entries = table.iter().map(|(&k, &c)| (k, c)).collect::<Vec<_>>();
+    entries.sort_by_key(|&(k, c)| (std::cmp::Reverse(c.packets), k));
+    entries.truncate(top);
+    entries
+}
+
+/// 流量排行的各行
+fn talkers<K: Copy + Ord + Hash>(
+    table: &HashMap<K, Counter>,
+    top: usize,
+    name: impl Fn(&K) -> String,
+) -> Vec<String> {
+    ranking(table, top)
+        .into_iter()
+        .map(|(k, c)| format!("  {:<40} {}", name(&k), counts(&c)))
+        .collect()
+}
+
+/// 对齐的帧数与字节数
+fn counts(c: &Counter) -> String {
+    msg!(
+        "analyz.counts",
+        packets = format!("{:>8}", c.packets),
+        bytes = format!("{:>12}", c.bytes)
+    )
+}
+
+/// 流量排行的 JSON 数组
+fn talkers_value<K: Copy + Ord + Hash>(
+    table: &HashMap<K, Counter>,
+    top: usize,
+    name: impl Fn(&K) -> String,
+) -> Value {
+    ranking(table, top)
+        .into_iter()
+        .map(|(k, c)| {
+            Value::object([
+                ("address", name(&k).into()),
+                ("packets", c.packets.into()),
+                ("bytes", c.bytes.into()),
+            ])
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// 以合适的单位表示比特率
+fn rate(bps: f64) -> String {
+    match bps {
+        bps if bps >= 1e9 => format!("{:.2} Gbps", bps / 1e9),
+        bps if bps >= 1e6 => format!("{:.2} Mbps", bps / 1e6),
+        bps if bps >= 1e3 => format!("{:.2} kbps", bps / 1e3),
+        bps => format!("{bps:.0} bps"),
+    }
+}
+
+/// 在 `until` 之前读取的结果
+enum Poll {
+    Frame(Frame),
+    /// 超时或被 Ctrl-C 中断
+    Idle,
+    /// 数据源已耗尽
+    End,
+}
+
+/// 在终端中原地刷新的输出
+#[derive(Default)]
+struct Screen {
+    /// 上次输出的行数
+    lines: usize,
+}
+
+impl Screen {
+    fn show(&mut self, text: &str) {
+        let mut stdout = std::io::stdout().lock();
+        if self.lines > 0 {
+            // 光标上移到上次输出的开头并清除其后内容
+            let _ = write!(stdout, "\x1b[{}F\x1b[J", self.lines);
+        }
+        let _ = write!(stdout, "{text}");
+        let _ = stdout.flush();
+        self.lines = text.lines().count();
+    }
+}
+
+impl<const S: usize> App<S> {
+    /// 读取下一帧，实时捕获时最多等待到 `until`。`armed` 记录上次设置读超时所对应的
+    /// `until`，只在 `until` 变化时重新设置
+    fn poll_frame(
+        &mut self,
+        until: Option<Instant>,
+        armed: &mut Option<Option<Instant>>,
+    ) -> std::io::Result<Poll> {
+        if let Source::Live(socket) = &self.source {
+            if until.is_some_and(|until| Instant::now() >= until) {
+                return Ok(Poll::Idle);
+            }
+            if *armed != Some(until) {
+                // 超时为 0 表示一直等待，因此至少等待 1 毫秒
+                let timeout = until.map(|until| {
+                    until
+                        .saturating_duration_since(Instant::now())
+                        .max(Duration::from_millis(1))
+                });
+                socket.set_read_timeout(timeout)?;
+                *armed = Some(until);
+            }
+        }
+        match self.source.next_frame() {
+            Ok(Some(frame)) => Ok(Poll::Frame(frame)),
+            Ok(None) => Ok(Poll::End),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted
+                ) =>
+            {
+                Ok(Poll::Idle)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn analyz(&mut self, args: &AnalyzArgs) -> std::io::Result<()> {
+        signal::catch_interrupt()?;
+        let live = matches!(self.source, Source::Live(_));
+        let duration = args.duration.map(Duration::from_secs_f64);
+        // 读取文件时不刷新，只输出最终结果。文本只在终端中原地刷新，NDJSON 每次刷新输出一行
+        let refresh_output = match args.output {
+            OutputFormat::Text => std::io::stdout().is_terminal(),
+            OutputFormat::Ndjson => true,
+            OutputFormat::Json | OutputFormat::Csv => false,
+        };
+        let interval = (live && args.interval > 0.0 && refresh_output)
+            .then(|| Duration::from_secs_f64(args.interval));
+
+        let start = Instant::now();
+        // 实时捕获按实际经过的时间计算，读取文件时按帧的时间戳计算
+        let elapsed = |stats: &Stats| match live {
+            true => start.elapsed(),
+            false => stats.span(),
+        };
+        let mut stats = Stats::default();
+        let mut screen = Screen::default();
+        let mut refresh = interval.map(|interval| start + interval);
+        let mut armed = None;
+        loop {
+            let expired = duration.is_some_and(|d| elapsed(&stats) >= d);
+            let done = args.count.is_some_and(|c| stats.total.packets >= c as u64);
+            if expired || done || signal::interrupted() {
+                break;
+            }
+            let deadline = duration.filter(|_| live).map(|d| start + d);
+            let until = match (refresh, deadline) {
+                (Some(refresh), Some(deadline)) => Some(refresh.min(deadline)),
+                (until, None) | (None, until) => until,
+            };
+            match self.poll_frame(until, &mut armed)? {
+                Poll::Frame(frame) => {
+                    // 读取文件时超出时长的帧不计入
+                    if !live
+                        && duration.is_some_and(|d| {
+                            stats.first.is_some_and(|first| {
+                                frame.ts.duration_since(first).unwrap_or_default() > d
+                            })
+                        })
+                    {
+                        break;
+                    }
+                    self.record(&frame)?;
+                    stats.add(&frame);
+                }
+                Poll::Idle => {}
+                Poll::End => break,
+            }
+            if let (Some(interval), Some(at)) = (interval, refresh) {
+                if Instant::now() >= at {
+                    match args.output {
+                        OutputFormat::Text => screen.show(&stats.report(elapsed(&stats), args.top)),
+                        _ => println!("{}", stats.summary(elapsed(&stats), args.top)),
+                    }
+                    refresh = Some(Instant::now() + interval);
+                }
+            }
+        }
+        if let Source::Live(socket) = &self.source {
+            socket.set_read_timeout(None)?;
+        }
+
+        match args.output {
+            OutputFormat::Text => {
+                screen.show(&stats.report(elapsed(&stats), args.top));
+                let done = msg!(
+                    "analyz.done",
+                    total = stats.total.packets,
+                    others = stats.others(),
+                    malformed = stats.malformed()
+                );
+                println!("{done}");
+            }
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                println!("{}", stats.summary(elapsed(&stats), args.top))
+            }
+            OutputFormat::Csv => print!("{}", stats.csv(args.top)),
+        }
+        Ok(())
+    }
+}
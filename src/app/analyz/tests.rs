@@ -0,0 +1,199 @@
+//! 按协议、地址、帧长与生存期的计数，排行的截取与排序，以及用 `tests/data` 中的捕获文件检查统计结果
+
+use std::{path::Path, time::UNIX_EPOCH};
+
+use super::*;
+use crate::{head::IPHdr, pcap::PcapReader};
+
+/// 读出 `tests/data` 中捕获文件的全部帧
+fn fixture(name: &str) -> Vec<Frame> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name);
+    let mut reader = PcapReader::open(&path).unwrap();
+    let mut frames = vec![];
+    while let Some(frame) = reader.next_frame().unwrap() {
+        frames.push(frame);
+    }
+    frames
+}
+
+/// 第 `secs` 秒捕获、线路上长 `origlen` 字节的帧
+fn frame(data: Vec<u8>, secs: u64, origlen: u32) -> Frame {
+    Frame {
+        data,
+        ts: UNIX_EPOCH + Duration::from_secs(secs),
+        origlen,
+        ifindex: None,
+        offloaded: false,
+    }
+}
+
+/// 从 `source` 发往 192.0.2.1 的 UDP 报文，不带传输层首部
+fn udp(source: [u8; 4], ttl: u8) -> Vec<u8> {
+    let mut data = EtherHdr::new([2; 6], [4; 6], EtherKind::IP).to_bytes();
+    let iphdr = IPHdr::new(1)
+        .source(source)
+        .destination([192, 0, 2, 1])
+        .protocol(Protocol::UDP)
+        .ttl(ttl)
+        .payload_len(0);
+    data.extend(iphdr.to_bytes());
+    data
+}
+
+#[test]
+fn fixture_stats() {
+    let mut stats = Stats::default();
+    for name in ["icmp-echo.pcap", "ipv4-frag.pcap", "qinq.pcapng"] {
+        for frame in fixture(name) {
+            stats.add(&frame);
+        }
+    }
+    assert_eq!(stats.total.packets, 6);
+    assert_eq!(stats.total.bytes, 196 + 150 + 52);
+    let class = |class| stats.classes.get(&class).map(|c| (c.packets, c.bytes));
+    assert_eq!(class(Class::Upper(Protocol::ICMP)), Some((2, 196)));
+    assert_eq!(class(Class::Upper(Protocol::UDP)), Some((4, 202)));
+    // 三个分片各计一次
+    assert_eq!(stats.frags4, 3);
+    assert_eq!(stats.ttls[64], 6);
+    // 分片与 QinQ 帧都短于 64 字节
+    assert_eq!(stats.sizes[..3], [4, 2, 0]);
+    let src = stats.src_ip[&IpAddr::from([127, 0, 0, 1])];
+    assert_eq!((src.packets, src.bytes), (3, 150));
+
+    // 读取文件时速率按第一帧到最后一帧的时间戳计算
+    let mut stats = Stats::default();
+    for frame in fixture("ipv6-nd.pcap") {
+        stats.add(&frame);
+    }
+    assert_eq!(stats.span(), Duration::from_micros(3_079_783));
+    assert_eq!(stats.ttls[255], 3);
+    assert_eq!(stats.classes[&Class::Upper(Protocol::ICMPv6)].packets, 3);
+    let dst = stats.dst_ip[&"ff02::1:ff00:1".parse::<IpAddr>().unwrap()];
+    assert_eq!((dst.packets, dst.bytes), (3, 258));
+}
+
+#[test]
+fn non_ip_and_malformed() {
+    let mut stats = Stats::default();
+    let arp = EtherHdr::new([0xff; 6], [2; 6], EtherKind::ARP).to_bytes();
+    stats.add(&frame(arp, 1, 60));
+    // 以太网首部不完整
+    stats.add(&frame(vec![0; 10], 2, 10));
+    // IP 首部被截断
+    let mut truncated = udp([192, 0, 2, 9], 64);
+    truncated.truncate(20);
+    stats.add(&frame(truncated, 3, 20));
+    assert_eq!(stats.total.packets, 3);
+    assert_eq!(stats.classes[&Class::Link(EtherKind::ARP)].packets, 1);
+    assert_eq!(stats.classes[&Class::Malformed].packets, 2);
+    // 非 IP 帧不计入地址与生存期
+    assert!(stats.src_ip.is_empty());
+    assert!(stats.ttls.is_empty());
+    // 首部完整的帧仍计入 MAC 排行
+    assert_eq!(stats.src_mac.len(), 2);
+    assert_eq!(stats.span(), Duration::from_secs(2));
+}
+
+#[test]
+fn size_buckets_use_wire_length() {
+    let mut stats = Stats::default();
+    // 帧长按线路上的原始长度计，与快照截断后的数据长度无关
+    for origlen in [0, 63, 64, 127, 1517, 1518, 9000] {
+        stats.add(&frame(udp([192, 0, 2, 9], 64), 0, origlen));
+    }
+    assert_eq!(stats.sizes, [2, 2, 0, 0, 0, 1, 2]);
+    assert_eq!(stats.total.bytes, 63 + 64 + 127 + 1517 + 1518 + 9000);
+}
+
+#[test]
+fn report_top_talkers() {
+    let mut stats = Stats::default();
+    let sources = [[10, 0, 0, 3], [10, 0, 0, 1], [10, 0, 0, 2]];
+    for (n, source) in sources.iter().enumerate() {
+        for _ in 0..=n {
+            stats.add(&frame(udp(*source, 30 + n as u8), 0, 100));
+        }
+    }
+    // 按帧数从多到少排列，只取前 2 项
+    let lines = talkers(&stats.src_ip, 2, |addr| addr.to_string());
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].trim_start().starts_with("10.0.0.2 "), "{lines:?}");
+    assert!(lines[1].trim_start().starts_with("10.0.0.1 "), "{lines:?}");
+    // 所有帧的目的 MAC 地址相同
+    let lines = talkers(&stats.dst_mac, 5, mac);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("02:02:02:02:02:02"));
+
+    let report = stats.report(Duration::from_secs(1), 2);
+    assert!(report.contains("10.0.0.2"));
+    assert!(!report.contains("10.0.0.3"));
+    assert!(report.contains(&rate(600.0 * 8.0)));
+    // 生存期分布同样只列出前 2 位
+    let ttl = |ttl: u8| {
+        report
+            .lines()
+            .any(|line| line.ends_with(&format!("TTL {ttl:>3} {:>8}", ttl - 29)))
+    };
+    assert!(!ttl(30) && ttl(31) && ttl(32), "{report}");
+}
+
+#[test]
+fn rate_units() {
+    assert_eq!(rate(0.0), "0 bps");
+    assert_eq!(rate(999.0), "999 bps");
+    assert_eq!(rate(1500.0), "1.50 kbps");
+    assert_eq!(rate(2.5e6), "2.50 Mbps");
+    assert_eq!(rate(1e9), "1.00 Gbps");
+}
+
+#[test]
+fn summary_record() {
+    let mut stats = Stats::default();
+    for name in ["icmp-echo.pcap", "ipv4-frag.pcap"] {
+        for frame in fixture(name) {
+            stats.add(&frame);
+        }
+    }
+    let arp = EtherHdr::new([0xff; 6], [2; 6], EtherKind::ARP).to_bytes();
+    stats.add(&frame(arp, 1, 60));
+
+    let summary = stats.summary(Duration::from_secs(2), 1);
+    assert_eq!(summary.at(&["version"]), &Value::from(SCHEMA_VERSION));
+    assert_eq!(summary.at(&["packets"]), &Value::from(6));
+    assert_eq!(summary.at(&["bytes"]), &Value::from(196 + 150 + 60));
+    assert_eq!(summary.at(&["pps"]), &Value::from(3.0));
+    assert_eq!(summary.at(&["non_ip"]), &Value::from(1));
+    assert_eq!(summary.at(&["fragments", "ipv4"]), &Value::from(3));
+    // 协议按帧数从多到少排列，IP 报文带协议号，非 IP 帧带类型字段
+    let Value::Array(protocols) = summary.at(&["protocols"]) else {
+        panic!("{summary}");
+    };
+    let fields = |p: &Value| (p.at(&["layer"]).clone(), p.at(&["packets"]).clone());
+    assert_eq!(fields(&protocols[0]), ("ip".into(), 3.into()));
+    assert_eq!(protocols[0].at(&["protocol"]), &Value::from(17));
+    assert_eq!(protocols[2].at(&["ethertype"]), &Value::from(0x0806));
+    // 排行与生存期只取前 `top` 项
+    let Value::Array(src) = summary.at(&["top_src_ip"]) else {
+        panic!("{summary}");
+    };
+    assert_eq!(src.len(), 1);
+    assert_eq!(src[0].at(&["address"]), &Value::from("127.0.0.1"));
+    let Value::Array(sizes) = summary.at(&["sizes"]) else {
+        panic!("{summary}");
+    };
+    assert_eq!(sizes.len(), SIZE_BUCKETS.len());
+    assert_eq!(sizes.last().unwrap().at(&["max"]), &Value::Null);
+
+    let csv = stats.csv(1);
+    let rows = csv.lines().collect::<Vec<_>>();
+    assert_eq!(rows[..2], ["section,key,packets,bytes", "total,,6,406"]);
+    assert!(rows.contains(&"src_ip,127.0.0.1,3,150"), "{csv}");
+    assert!(rows.contains(&"size,1518+,0,"), "{csv}");
+    assert!(rows.contains(&"fragments,ipv4,3,"), "{csv}");
+    assert_eq!(rows.last(), Some(&"malformed,,0,"));
+    // 每行的列数相同
+    assert!(rows.iter().all(|row| row.split(',').count() == 4), "{csv}");
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::Hash,
+    io::ErrorKind,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    head::{ArpHdr, EtherHdr, EtherKind, Header, ARP_REPLY},
+    i18n::msg,
+    iface::Interface,
+    route,
+    socket::{link_addr, PackSocket},
+};
+
+use super::App;
+
+/// ARP 缓存表项的有效期
+pub const ARP_CACHE_TTL: Duration = Duration::from_secs(60);
+/// 每次请求等待应答的时间
+const ARP_TIMEOUT: Duration = Duration::from_millis(500);
+/// 请求的最大发送次数
+const ARP_RETRIES: usize = 3;
+
+/// IP 地址到 MAC 地址的缓存，表项在 `ttl` 后过期。`A` 为 IPv4 或 IPv6 地址。
+#[derive(Debug)]
+pub struct ArpCache<A = [u8; 4]> {
+    entries: HashMap<A, ([u8; 6], Instant)>,
+    ttl: Duration,
+}
+
+impl<A: Copy + Eq + Hash> ArpCache<A> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// 查询未过期的表项，过期表项会被移除
+    pub fn get(&mut self, ip: A) -> Option<[u8; 6]> {
+        match self.entries.get(&ip) {
+            Some(&(mac, expiry)) if expiry > Instant::now() => Some(mac),
+            Some(_) => {
+                self.entries.remove(&ip);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, ip: A, mac: [u8; 6]) {
+        self.entries.insert(ip, (mac, Instant::now() + self.ttl));
+    }
+}
+
+impl ArpCache<(i32, [u8; 4])> {
+    /// 以内核 `/proc/net/arp` 中已完成的表项初始化缓存，表项以接口索引和 IP 地址为键。
+    /// 只读取 `ifaces` 中接口上的表项。
+    pub fn from_proc(ttl: Duration, ifaces: &[Interface]) -> Self {
+        let mut cache = Self::new(ttl);
+        let table = fs::read_to_string("/proc/net/arp").unwrap_or_default();
+        for line in table.lines().skip(1) {
+            let cols = line.split_whitespace().collect::<Vec<_>>();
+            let [ip, _, flags, mac, _, device] = cols[..] else {
+                continue;
+            };
+            let Some(iface) = ifaces.iter().find(|iface| iface.name == device) else {
+                continue;
+            };
+            // ATF_COM：表项已完成
+            let complete = u32::from_str_radix(flags.trim_start_matches("0x"), 16)
+                .is_ok_and(|flags| flags & 0x2 != 0);
+            let ip = ip
+                .split('.')
+                .map(|n| n.parse::<u8>())
+                .collect::<Result<Vec<_>, _>>();
+            let mac = mac
+                .split(':')
+                .map(|n| u8::from_str_radix(n, 16))
+                .collect::<Result<Vec<_>, _>>();
+            if let (true, Ok(ip), Ok(mac)) = (complete, ip, mac) {
+                if let (Ok(ip), Ok(mac)) = (ip.try_into(), mac.try_into()) {
+                    cache.insert((iface.index, ip), mac);
+                }
+            }
+        }
+        cache
+    }
+}
+
+impl<const S: usize> App<S> {
+    /// 解析发往 `dstip` 时链路层的目的 MAC 地址。
+    /// 目的地址不在出接口的链路上时解析其网关的地址。
+    pub fn get_mac(&mut self, dstip: [u8; 4]) -> std::io::Result<[u8; 6]> {
+        let iface = self.out_iface(dstip)?;
+        if iface.is_loopback() {
+            return Ok([0; 6]);
+        }
+        let hop = match route::lookup(dstip) {
+            Ok(route) if route.iface == iface.name => route.next_hop(dstip),
+            _ => dstip,
+        };
+        if let Some(mac) = self.arp.get((iface.index, hop)) {
+            return Ok(mac);
+        }
+        let mac = self.arp_resolve(&iface, hop)?;
+        self.arp.insert((iface.index, hop), mac);
+        Ok(mac)
+    }
+
+    /// 在接口 `iface` 上广播 ARP 请求，直到收到 `hop` 的应答或超时
+    fn arp_resolve(&self, iface: &Interface, hop: [u8; 4]) -> std::io::Result<[u8; 6]> {
+        let (smac, spa) = (iface.mac, iface.addr()?);
+        let addr = link_addr(iface.index, libc::ETH_P_ARP);
+        let mut socket = PackSocket::<128>::new(libc::ETH_P_ARP)?;
+        socket.bind(&addr)?;
+
+        let ethdr = EtherHdr::new([0xff; 6], smac, EtherKind::ARP);
+        let mut request = (ethdr, ArpHdr::request(smac, spa, hop)).to_bytes();
+        // 补足以太网最小帧长
+        request.resize(60, 0);
+
+        for _ in 0..ARP_RETRIES {
+            socket.send_to(&request, &addr)?;
+            let until = Instant::now() + ARP_TIMEOUT;
+            while let Some(data) = socket.recive_until(until)? {
+                let Ok(((_, arp), _)) = <(EtherHdr, ArpHdr)>::from_bytes(&data) else {
+                    continue;
+                };
+                if arp.oper == ARP_REPLY && arp.spa == hop {
+                    return Ok(arp.sha);
+                }
+            }
+        }
+        Err(std::io::Error::new(
+            ErrorKind::TimedOut,
+            msg!("arp.timeout", addr = hop.map(|n| n.to_string()).join(".")),
+        ))
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    io::ErrorKind,
+    net::Ipv6Addr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    head::{EtherHdr, EtherKind, Header, Icmpv6, Icmpv6Msg, Ipv6Hdr, NdOption, Protocol},
+    i18n::msg,
+    iface::Interface,
+    route,
+    socket::{link_addr, PackSocket},
+};
+
+use super::App;
+
+/// 每次请求等待通告的时间（RFC 4861 的 RETRANS_TIMER）
+const NDP_TIMEOUT: Duration = Duration::from_secs(1);
+/// 请求的最大发送次数（RFC 4861 的 MAX_MULTICAST_SOLICIT）
+const NDP_RETRIES: usize = 3;
+
+/// 组播地址 `addr` 对应的以太网组播地址 33:33:xx:xx:xx:xx
+fn multicast_mac(addr: [u8; 16]) -> [u8; 6] {
+    [0x33, 0x33, addr[12], addr[13], addr[14], addr[15]]
+}
+
+/// `addr` 的被请求节点组播地址 ff02::1:ffxx:xxxx
+fn solicited_node(addr: [u8; 16]) -> [u8; 16] {
+    let mut group = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0).octets();
+    group[13..].copy_from_slice(&addr[13..]);
+    group
+}
+
+impl<const S: usize> App<S> {
+    /// 解析发往 IPv6 地址 `dstip` 时链路层的目的 MAC 地址。
+    /// 目的地址不在出接口的链路上时解析其网关的地址。
+    pub fn get_mac6(&mut self, dstip: [u8; 16]) -> std::io::Result<[u8; 6]> {
+        let iface = self.out_iface(dstip)?;
+        if iface.is_loopback() {
+            return Ok([0; 6]);
+        }
+        if Ipv6Addr::from(dstip).is_multicast() {
+            return Ok(multicast_mac(dstip));
+        }
+        let hop = match route::lookup6(dstip) {
+            Ok(route) if route.iface == iface.name => route.next_hop(dstip),
+            _ => dstip,
+        };
+        if let Some(mac) = self.ndp.get(hop) {
+            return Ok(mac);
+        }
+        let mac = self.ndp_resolve(&iface, hop)?;
+        self.ndp.insert(hop, mac);
+        Ok(mac)
+    }
+
+    /// 在接口 `iface` 上向 `hop` 的被请求节点组播地址发送邻居请求，直到收到通告或超时
+    fn ndp_resolve(&self, iface: &Interface, hop: [u8; 16]) -> std::io::Result<[u8; 6]> {
+        let srcip = iface.addr6(hop)?;
+        let addr = link_addr(iface.index, libc::ETH_P_IPV6);
+        let mut socket = PackSocket::<1500>::new(libc::ETH_P_IPV6)?;
+        socket.bind(&addr)?;
+
+        let group = solicited_node(hop);
+        let ethdr = EtherHdr::new(multicast_mac(group), iface.mac, EtherKind::IPv6);
+        // 邻居发现报文的跳数限制必须为 255
+        let iphdr = Ipv6Hdr::new(srcip, group, Protocol::ICMPv6).hlim(255);
+        let icmp = Icmpv6::neighbor_solicit(hop, iface.mac)
+            .checksum(&iphdr, &[])
+            .to_bytes();
+        let iphdr = iphdr.payload_len(icmp.len() as u16);
+        let mut request = (ethdr, iphdr).to_bytes();
+        request.extend(icmp);
+
+        for _ in 0..NDP_RETRIES {
+            socket.send_to(&request, &addr)?;
+            let until = Instant::now() + NDP_TIMEOUT;
+            while let Some(data) = socket.recive_until(until)? {
+                let Ok(((ethdr, iphdr), rest)) = <(EtherHdr, Ipv6Hdr)>::from_bytes(&data) else {
+                    continue;
+                };
+                if iphdr.protocol != Protocol::ICMPv6 {
+                    continue;
+                }
+                let Ok((icmp, _)) = Icmpv6::from_bytes(rest) else {
+                    continue;
+                };
+                let Some(Icmpv6Msg::NeighborAdvert {
+                    target, options, ..
+                }) = icmp.msg
+                else {
+                    continue;
+                };
+                if target != hop {
+                    continue;
+                }
+                // 通告中没有目标链路地址选项时以帧的源地址为准
+                let mac = options
+                    .iter()
+                    .find_map(|opt| match opt {
+                        NdOption::TargetLinkAddr(mac) => Some(*mac),
+                        _ => None,
+                    })
+                    .unwrap_or(ethdr.shost);
+                return Ok(mac);
+            }
+        }
+        Err(std::io::Error::new(
+            ErrorKind::TimedOut,
+            msg!("ndp.timeout", addr = Ipv6Addr::from(hop)),
+        ))
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cli::PingArgs,
+    head::{EtherHdr, EtherKind, Header, IPHdr, Protocol, ICMP},
+    i18n::msg,
+    signal,
+};
+
+use super::{decode, App};
+
+/// 往返时间统计
+#[derive(Debug, Default)]
+struct RttStat {
+    sent: usize,
+    received: usize,
+    rtts: Vec<f64>,
+}
+
+impl RttStat {
+    fn report(&self, dstip: [u8; 4], elapsed: Duration) {
+        let dstip = dstip.map(|n| n.to_string()).join(".");
+        let loss = if self.sent > 0 {
+            (self.sent - self.received) as f64 * 100.0 / self.sent as f64
+        } else {
+            0.0
+        };
+        println!("{}", msg!("ping.stats_title", dst = dstip));
+        let text = msg!(
+            "ping.stats",
+            sent = self.sent,
+            received = self.received,
+            loss = format!("{loss:.1}"),
+            time = elapsed.as_millis()
+        );
+        println!("{text}");
+        if !self.rtts.is_empty() {
+            let n = self.rtts.len() as f64;
+            let min = self.rtts.iter().cloned().fold(f64::MAX, f64::min);
+            let max = self.rtts.iter().cloned().fold(0.0, f64::max);
+            let avg = self.rtts.iter().sum::<f64>() / n;
+            let mdev = (self.rtts.iter().map(|t| t * t).sum::<f64>() / n - avg * avg)
+                .max(0.0)
+                .sqrt();
+            let rtts = format!("{min:.3}/{avg:.3}/{max:.3}/{mdev:.3}");
+            println!("{}", msg!("ping.rtt", rtts = rtts));
+        }
+    }
+}
+
+impl<const S: usize> App<S> {
+    pub fn ping(&mut self, args: &PingArgs) -> std::io::Result<()> {
+        let iface = self.out_iface(args.destip)?;
+        let dhost = match args.dhost {
+            Some(mac) => mac,
+            None => self.get_mac(args.destip)?,
+        };
+        let srcip = iface.addr()?;
+        let ident = std::process::id() as u16;
+        let interval = Duration::from_secs_f64(args.interval);
+        let deadline = args.deadline.map(Duration::from_secs_f64);
+        let payload = (0..args.size).map(|n| n as u8).collect::<Vec<_>>();
+
+        let text = msg!(
+            "ping.start",
+            dst = args.destip.map(|n| n.to_string()).join("."),
+            size = args.size,
+            total = args.size + 28
+        );
+        println!("{text}");
+
+        // 按下 Ctrl-C 后停止发送，仍然输出统计
+        signal::catch_interrupt()?;
+        let start = Instant::now();
+        let mut stat = RttStat::default();
+        let mut pending = HashMap::new();
+        let mut seqnum = 0u16;
+        loop {
+            let expired = deadline.is_some_and(|d| start.elapsed() >= d);
+            let done = args.count.is_some_and(|c| stat.sent >= c);
+            if expired || done || signal::interrupted() {
+                break;
+            }
+
+            seqnum = seqnum.wrapping_add(1);
+            let icmp = ICMP::new(8, 0)
+                .with_ident(ident)
+                .with_seqnum(seqnum)
+                .checksum(&payload);
+            let iphdr = IPHdr::new(seqnum)
+                .ttl(args.ttl)
+                .protocol(Protocol::ICMP)
+                .source(srcip)
+                .destination(args.destip)
+                .payload_len((8 + payload.len()) as u16)
+                .checksum();
+            let ehdr = EtherHdr::new(dhost, iface.mac, EtherKind::IP);
+            let mut output = ((ehdr, iphdr), icmp).to_bytes();
+            output.extend_from_slice(&payload);
+
+            self.send_frame(&iface, &output)?;
+            pending.insert(seqnum, Instant::now());
+            stat.sent += 1;
+
+            // 最后一个请求发出后，至多再等待一个间隔（且不少于 1 秒）
+            let last = args.count.is_some_and(|c| stat.sent >= c);
+            let wait = if last {
+                interval.max(Duration::from_secs(1))
+            } else {
+                interval
+            };
+            let until = match deadline {
+                Some(d) => (Instant::now() + wait).min(start + d),
+                None => Instant::now() + wait,
+            };
+            self.wait_replies(args.destip, ident, until, &mut pending, &mut stat)?;
+        }
+
+        stat.report(args.destip, start.elapsed());
+        Ok(())
+    }
+
+    /// 接收回显应答直到 `until`，按源地址、标识符和序号匹配已发出的请求
+    fn wait_replies(
+        &mut self,
+        dstip: [u8; 4],
+        ident: u16,
+        until: Instant,
+        pending: &mut HashMap<u16, Instant>,
+        stat: &mut RttStat,
+    ) -> std::io::Result<()> {
+        loop {
+            let data = match self.recive_until(until) {
+                Ok(Some(data)) => data,
+                Ok(None) => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => match signal::interrupted() {
+                    true => break,
+                    false => continue,
+                },
+                Err(e) => return Err(e),
+            };
+            let Ok(((_, iphdr), buf)) = decode(&data) else {
+                continue;
+            };
+            if iphdr.protocol != Protocol::ICMP || iphdr.source != dstip {
+                continue;
+            }
+            let Ok((icmp, _)) = ICMP::from_bytes(buf) else {
+                continue;
+            };
+            let Some(ping) = icmp.msg.filter(|ping| icmp.typ == 0 && ping.ident == ident) else {
+                continue;
+            };
+            if let Some(sent) = pending.remove(&ping.seqnum) {
+                let rtt = sent.elapsed().as_secs_f64() * 1000.0;
+                stat.received += 1;
+                stat.rtts.push(rtt);
+                let text = msg!(
+                    "ping.reply",
+                    len = iphdr.totlen - iphdr.ihl as u16,
+                    src = iphdr.source.map(|n| n.to_string()).join("."),
+                    seq = ping.seqnum,
+                    ttl = iphdr.ttl,
+                    rtt = format!("{rtt:.3}")
+                );
+                println!("{text}");
+            }
+        }
+        Ok(())
+    }
+}
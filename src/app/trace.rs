@@ -0,0 +1,247 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    cli::TraceArgs,
+    head::{
+        EtherHdr, EtherKind, Header, IPHdr, Protocol, TcpFlag, TcpHdr, TcpOption, UdpHdr, ICMP,
+    },
+    i18n::msg,
+};
+
+use super::{decode, App};
+
+/// UDP 探测缺省的起始目的端口
+const UDP_BASE_PORT: u16 = 33434;
+/// TCP 探测缺省的目的端口
+const TCP_PORT: u16 = 80;
+/// TCP 探测的初始序号，第 n 个探测使用 `TCP_ISN + n`
+const TCP_ISN: u32 = 0x6970_0000;
+
+/// 探测的应答类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reply {
+    /// 中间路由器返回“传输期间生存时间为0”
+    TimeExceeded,
+    /// 探测到达目的主机
+    Reached,
+    /// 返回“目的不可达”，附带代码
+    Unreachable(u8),
+}
+
+/// 一次路由跟踪的参数
+struct Probe {
+    protocol: Protocol,
+    srcip: [u8; 4],
+    destip: [u8; 4],
+    sport: u16,
+    port: u16,
+    ident: u16,
+}
+
+impl Probe {
+    /// 构造序号为 `seq` 的探测报文（不含以太网首部）
+    fn build(&self, seq: u16, ttl: u8) -> (IPHdr, Vec<u8>) {
+        let iphdr = IPHdr::new(seq)
+            .ttl(ttl)
+            .protocol(self.protocol)
+            .source(self.srcip)
+            .destination(self.destip);
+        let payload = [0u8; 32];
+        let segment = match self.protocol {
+            Protocol::UDP => {
+                let mut bytes = UdpHdr::new(self.sport, self.port.wrapping_add(seq))
+                    .checksum(&iphdr, &payload)
+                    .to_bytes();
+                bytes.extend(payload);
+                bytes
+            }
+            Protocol::TCP => TcpHdr::new(self.sport, self.port)
+                .seqnum(TCP_ISN + seq as u32)
+                .flag(TcpFlag {
+                    syn: true,
+                    ..Default::default()
+                })
+                .option(TcpOption::Mss(1460))
+                .checksum(&iphdr, &[])
+                .to_bytes(),
+            _ => {
+                let mut bytes = ICMP::new(8, 0)
+                    .with_ident(self.ident)
+                    .with_seqnum(seq)
+                    .checksum(&payload)
+                    .to_bytes();
+                bytes.extend(payload);
+                bytes
+            }
+        };
+        let iphdr = iphdr.payload_len(segment.len() as u16).checksum();
+        (iphdr, segment)
+    }
+
+    /// 判断差错报文引用的原始报文是否为序号 `seq` 的探测
+    fn quotes(&self, seq: u16, inner: &IPHdr, rest: &[u8]) -> bool {
+        if inner.destinaiton != self.destip || inner.protocol != self.protocol {
+            return false;
+        }
+        match self.protocol {
+            Protocol::UDP => UdpHdr::from_bytes(rest).is_ok_and(|(udphdr, _)| {
+                udphdr.sport == self.sport && udphdr.dport == self.port.wrapping_add(seq)
+            }),
+            // 差错报文只保证引用传输层的前 8 字节：端口号与序号
+            Protocol::TCP => {
+                rest.len() >= 8
+                    && u16::from_be_bytes([rest[0], rest[1]]) == self.sport
+                    && u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]])
+                        == TCP_ISN + seq as u32
+            }
+            _ => ICMP::from_bytes(rest).is_ok_and(|(icmp, _)| {
+                icmp.msg
+                    .is_some_and(|ping| ping.ident == self.ident && ping.seqnum == seq)
+            }),
+        }
+    }
+
+    /// 判断一个 IP 报文是否为序号 `seq` 的探测的应答
+    fn matches(&self, seq: u16, iphdr: &IPHdr, buf: &[u8]) -> Option<Reply> {
+        match iphdr.protocol {
+            Protocol::ICMP => {
+                let (icmp, rest) = ICMP::from_bytes(buf).ok()?;
+                if icmp.typ == 0 {
+                    let ping = icmp.msg?;
+                    let echo = self.protocol == Protocol::ICMP
+                        && iphdr.source == self.destip
+                        && ping.ident == self.ident
+                        && ping.seqnum == seq;
+                    return echo.then_some(Reply::Reached);
+                }
+                let (inner, rest) = icmp.quoted(rest).ok()?;
+                if !self.quotes(seq, &inner, rest) {
+                    return None;
+                }
+                match (icmp.typ, icmp.code) {
+                    (11, _) => Some(Reply::TimeExceeded),
+                    (3, 3) if iphdr.source == self.destip => Some(Reply::Reached),
+                    (3, code) => Some(Reply::Unreachable(code)),
+                    _ => None,
+                }
+            }
+            // 目的主机以 SYN+ACK 或 RST 应答 TCP 探测
+            Protocol::TCP if self.protocol == Protocol::TCP => {
+                let (tcphdr, _) = TcpHdr::from_bytes(buf).ok()?;
+                let answer = iphdr.source == self.destip
+                    && tcphdr.sport == self.port
+                    && tcphdr.dport == self.sport
+                    && tcphdr.acknum == TCP_ISN.wrapping_add(seq as u32 + 1);
+                answer.then_some(Reply::Reached)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<const S: usize> App<S> {
+    pub fn trace(&mut self, args: &TraceArgs) -> std::io::Result<()> {
+        if let p @ (Protocol::ICMPv6 | Protocol::Other(_)) = args.protocol {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                msg!("trace.unsupported", protocol = u8::from(p)),
+            ));
+        }
+        let iface = self.out_iface(args.destip)?;
+        let dhost = match args.dhost {
+            Some(mac) => mac,
+            None => self.get_mac(args.destip)?,
+        };
+        let ident = std::process::id() as u16;
+        let probe = Probe {
+            protocol: args.protocol,
+            srcip: iface.addr()?,
+            destip: args.destip,
+            sport: ident | 0x8000,
+            port: args.port.unwrap_or(match args.protocol {
+                Protocol::UDP => UDP_BASE_PORT,
+                _ => TCP_PORT,
+            }),
+            ident,
+        };
+        let wait = Duration::from_secs_f64(args.wait);
+
+        let text = msg!(
+            "trace.start",
+            dst = args.destip.map(|n| n.to_string()).join("."),
+            hops = args.max_hops,
+            queries = args.queries,
+            protocol = format!("{:?}", args.protocol)
+        );
+        println!("{text}");
+
+        let mut seq = 0u16;
+        for ttl in args.first_ttl..=args.max_hops {
+            print!("{ttl:>3} ");
+            let mut last_hop = None;
+            let mut reached = false;
+            for _ in 0..args.queries {
+                seq = seq.wrapping_add(1);
+                let (iphdr, segment) = probe.build(seq, ttl);
+                let ehdr = EtherHdr::new(dhost, iface.mac, EtherKind::IP);
+                let mut output = (ehdr, iphdr).to_bytes();
+                output.extend(segment);
+
+                let sent = Instant::now();
+                self.send_frame(&iface, &output)?;
+
+                let mut answer = None;
+                while let Some(data) = self.recive_until(sent + wait)? {
+                    let Ok(((_, iphdr), buf)) = decode(&data) else {
+                        continue;
+                    };
+                    if let Some(reply) = probe.matches(seq, &iphdr, buf) {
+                        answer = Some((iphdr.source, reply, sent.elapsed()));
+                        break;
+                    }
+                }
+
+                match answer {
+                    Some((hop, reply, rtt)) => {
+                        if last_hop != Some(hop) {
+                            print!(" {}", hop.map(|n| n.to_string()).join("."));
+                            last_hop = Some(hop);
+                        }
+                        print!("  {:.3} ms", rtt.as_secs_f64() * 1000.0);
+                        match reply {
+                            Reply::TimeExceeded => {}
+                            Reply::Reached => reached = true,
+                            Reply::Unreachable(code) => {
+                                print!(" {}", unreachable_mark(code));
+                                reached = true;
+                            }
+                        }
+                    }
+                    None => print!("  *"),
+                }
+                std::io::stdout().flush()?;
+            }
+            println!();
+            if reached {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 与 traceroute 一致的目的不可达标记
+fn unreachable_mark(code: u8) -> String {
+    match code {
+        0 => "!N".to_string(),
+        1 => "!H".to_string(),
+        2 => "!P".to_string(),
+        4 => "!F".to_string(),
+        5 => "!S".to_string(),
+        9 | 10 | 13 => "!X".to_string(),
+        code => format!("!<{code}>"),
+    }
+}
@@ -1,55 +1,341 @@
-use std::{num::ParseIntError, path::PathBuf};
+#[cfg(test)]
+mod tests;
 
-use clap::{Parser, Subcommand};
+use std::{
+    net::{IpAddr, Ipv6Addr},
+    num::ParseIntError,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use crate::head::Protocol;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use crate::{
+    dissect::Section,
+    encoding::Encoding,
+    filter::{Dir, Expr, FilterError, Primitive},
+    head::{Protocol, VlanTag},
+    i18n::{self, msg, Lang},
+    output::OutputFormat,
+    pcap::PcapFormat,
+};
 
 /// 发送、捕获IP报文并进行过滤与分析。
 #[derive(Debug, Parser)]
 #[command(version, about, long_about=None)]
 pub struct Args {
+    /// 收发报文使用的网络接口。缺省时按路由选择出接口，并在所有接口上捕获
+    #[arg(long, short, global = true)]
+    pub interface: Option<String>,
+    /// 界面语言，可选值有 en、zh-CN。缺省时按环境变量 LC_ALL、LC_MESSAGES、LANG 选择
+    #[arg(value_parser = langp, long, global = true)]
+    pub lang: Option<Lang>,
     #[command(subcommand)]
     pub command: Command,
 }
 
+impl Args {
+    /// 帮助文本已按当前语言替换的命令定义
+    pub fn localized() -> clap::Command {
+        localize(Args::command(), None)
+    }
+
+    /// 按当前语言显示帮助与错误并解析命令行参数
+    pub fn parse_localized() -> Self {
+        let matches = Args::localized().get_matches();
+        Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+}
+
+/// 以消息目录中的 `help.<子命令>.about`、`help.<子命令>.<参数>` 或 `help.<参数>`
+/// 替换命令及其子命令的帮助文本，当前语言的目录中没有时保留文档注释
+fn localize(mut cmd: clap::Command, sub: Option<&str>) -> clap::Command {
+    let key = |name: &str| match sub {
+        Some(sub) => format!("help.{sub}.{name}"),
+        None => format!("help.{name}"),
+    };
+    if let Some(about) = i18n::get_exact(&key("about")) {
+        cmd = cmd.about(about);
+    }
+    let ids = cmd
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .collect::<Vec<_>>();
+    for id in ids {
+        let help = i18n::get_exact(&key(&id)).or_else(|| i18n::get_exact(&format!("help.{id}")));
+        if let Some(help) = help {
+            cmd = cmd.mut_arg(id, |arg| arg.help(help).long_help(None::<&str>));
+        }
+    }
+    let subs = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect::<Vec<_>>();
+    for name in subs {
+        cmd = cmd.mut_subcommand(&name, |sub| localize(sub, Some(&name)));
+    }
+    cmd
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Command {
+    /// 列出本机的网络接口及其地址
+    Interfaces,
     /// 发送IP数据报报文
-    Send {
-        /// 目的MAC地址
-        #[arg(value_parser = ipp, long, short)]
-        dhost: [u8; 6],
-        /// 目的IP地址
-        #[arg(value_parser = ipp, long, short)]
-        destip: [u8; 4],
-        /// 协议类型。可选值有 TCP、UDP、ICMP 以及十进制的一个字节长数字
-        #[arg(value_parser = protocolp, long, short)]
-        protocol: Protocol,
-        /// 解析数据的进制
-        #[arg(long, short)]
-        radix: u32,
-        /// 报文数据
-        #[arg(long, short)]
-        text: Option<String>,
-        /// 报文数据文件路径
-        #[arg(long, short)]
-        file: Option<PathBuf>,
-    },
+    Send(SendArgs),
+    /// 发送 ICMP 回显请求并统计往返时间
+    Ping(PingArgs),
+    /// 逐跳增加生存期以跟踪到目的主机的路由
+    Trace(TraceArgs),
     /// 分析本机接收的IP报文类型和数量
-    Analyz,
+    Analyz(AnalyzArgs),
     /// 过滤显示接收到的IP报文及其首部信息
-    Filter {
-        #[arg(value_parser = ipparser::<6, 16>, long)]
-        src_mac: Option<[u8; 6]>,
-        #[arg(value_parser = ipparser::<6, 16>, long)]
-        dst_mac: Option<[u8; 6]>,
-        #[arg(value_parser = ipp, long, short)]
-        shost: Option<[u8; 4]>,
-        #[arg(value_parser = ipp, long, short)]
-        dhost: Option<[u8; 4]>,
-        #[arg(long, short)]
-        log: bool,
-    },
+    Filter(FilterArgs),
+}
+
+/// 各捕获子命令共用的参数
+#[derive(Debug, clap::Args)]
+pub struct CaptureArgs {
+    /// 从 pcap 或 pcapng 文件读取帧，而不是从网络接口捕获
+    #[arg(long, short)]
+    pub read: Option<PathBuf>,
+    /// 将捕获的帧写入文件
+    #[arg(long, short)]
+    pub write: Option<PathBuf>,
+    /// 写入文件的格式。可选值有 pcap、pcapng
+    #[arg(value_parser = pcapfmtp, long, default_value = "pcapng")]
+    pub format: PcapFormat,
+}
+
+/// 过滤结果日志的参数
+#[derive(Debug, clap::Args)]
+pub struct LogArgs {
+    /// 把匹配的报文记录写入此目录下的日志文件，每行一个 JSON 记录
+    #[arg(long, short)]
+    pub log: Option<PathBuf>,
+    /// 日志文件达到此大小后轮转，可带 K、M、G 后缀，为 0 时不按大小轮转
+    #[arg(value_parser = sizep, long, default_value = "10M")]
+    pub log_size: u64,
+    /// 日志文件写入此秒数后轮转，缺省时不按时间轮转。读取文件时按帧的时间戳计算
+    #[arg(long, value_parser = secsp)]
+    pub log_interval: Option<f64>,
+    /// 保留的已轮转日志文件数，更早的文件被删除
+    #[arg(long, default_value_t = 10)]
+    pub log_keep: usize,
+    /// 以 gzip 压缩已轮转的日志文件
+    #[arg(long)]
+    pub log_gzip: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct AnalyzArgs {
+    /// 分析此数目的帧后结束，缺省时一直捕获到 Ctrl-C 或文件末尾
+    #[arg(long, short)]
+    pub count: Option<usize>,
+    /// 捕获时长上限，单位为秒。读取文件时按帧的时间戳计算
+    #[arg(long, value_parser = secsp)]
+    pub duration: Option<f64>,
+    /// 在终端中刷新统计结果的间隔，单位为秒，为 0 时只输出最终结果
+    #[arg(long, default_value_t = 1.0, value_parser = secsp)]
+    pub interval: f64,
+    /// 流量排行显示的条目数
+    #[arg(long, default_value_t = 5)]
+    pub top: usize,
+    /// 统计结果的格式：text、json（结束时输出一个文档）、ndjson（每次刷新输出一行）或 csv
+    #[arg(value_parser = outputp, long, short, default_value = "text")]
+    pub output: OutputFormat,
+    #[command(flatten)]
+    pub capture: CaptureArgs,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct FilterArgs {
+    /// 过滤表达式，例如 `tcp and port 80`、`net 10.0.0.0/8 and ttl < 5`、
+    /// `icmp.type == 3 or not (host ::1)`。可由多个参数组成，按空格连接
+    pub expression: Vec<String>,
+    #[arg(value_parser = macp, long)]
+    pub src_mac: Option<[u8; 6]>,
+    #[arg(value_parser = macp, long)]
+    pub dst_mac: Option<[u8; 6]>,
+    /// 源IP地址，IPv4 或 IPv6
+    #[arg(value_parser = addrp, long, short)]
+    pub shost: Option<IpAddr>,
+    /// 目的IP地址，IPv4 或 IPv6
+    #[arg(value_parser = addrp, long, short)]
+    pub dhost: Option<IpAddr>,
+    /// 只显示带有此 VLAN ID 标签（任意一层）的帧，可多次指定
+    #[arg(long, value_parser = clap::value_parser!(u16).range(..4096))]
+    pub vlan: Vec<u16>,
+    /// 只显示校验和错误的报文（由网卡计算的校验和不算错误）
+    #[arg(long)]
+    pub bad_checksum: bool,
+    /// 以 `tcpdump -d` 的格式输出过滤条件编译成的 BPF 程序后退出
+    #[arg(long)]
+    pub dump_bpf: bool,
+    /// 报文的输出格式：text、json、ndjson（每行一个报文）或 csv
+    #[arg(value_parser = outputp, long, short, default_value = "text")]
+    pub output: OutputFormat,
+    /// 以字段树和十六进制转储显示报文：`-v` 显示各层，`-vv` 展开各层的字段，`-vvv` 附上字段的原始字节
+    #[arg(long, short, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// 快照长度，每帧只保留前若干字节用于解析、显示和写入文件
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub snaplen: Option<u32>,
+    /// 在十六进制转储中高亮的层：link、network、transport 或 payload，需与 `-v` 一同使用
+    #[arg(value_parser = sectionp, long)]
+    pub highlight: Option<Section>,
+    #[command(flatten)]
+    pub logging: LogArgs,
+    #[command(flatten)]
+    pub capture: CaptureArgs,
+}
+
+impl FilterArgs {
+    /// 由过滤表达式与各选项合成的过滤条件，没有任何条件时为 `None`
+    pub fn filter(&self) -> Result<Option<Expr>, FilterError> {
+        let mut conds = Vec::new();
+        if !self.expression.is_empty() {
+            conds.push(Expr::parse(&self.expression.join(" "))?);
+        }
+        let prims = [
+            self.src_mac.map(|mac| Primitive::Ether(Dir::Src, mac)),
+            self.dst_mac.map(|mac| Primitive::Ether(Dir::Dst, mac)),
+            self.shost.map(|addr| Primitive::Host(Dir::Src, addr)),
+            self.dhost.map(|addr| Primitive::Host(Dir::Dst, addr)),
+        ];
+        conds.extend(prims.into_iter().flatten().map(Expr::from));
+        let vlans = self
+            .vlan
+            .iter()
+            .map(|&vid| Expr::from(Primitive::Vlan(Some(vid))));
+        conds.extend(vlans.reduce(Expr::or));
+        Ok(conds.into_iter().reduce(Expr::and))
+    }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SendArgs {
+    /// 目的MAC地址，缺省时通过 ARP 解析目的主机或网关的地址
+    #[arg(value_parser = macp, long)]
+    pub dhost: Option<[u8; 6]>,
+    /// 为帧加上 VLAN 标签，格式为 `VID[:PCP]`。多次指定时按由外到内的顺序叠加，
+    /// 外层使用 802.1ad 标签。目的MAC地址仍在未加标签的链路上解析，必要时用 `--dhost` 指定
+    #[arg(value_parser = vlanp, long)]
+    pub vlan: Vec<VlanTag>,
+    /// 目的IP地址，IPv4 或 IPv6
+    #[arg(value_parser = addrp, long, short)]
+    pub destip: IpAddr,
+    /// 源IP地址，缺省时使用出接口的地址
+    #[arg(value_parser = addrp, long, short)]
+    pub srcip: Option<IpAddr>,
+    /// 生存期（IPv6 中为跳数限制）
+    #[arg(long, default_value_t = 64)]
+    pub ttl: u8,
+    /// 服务类型（IPv6 中为流量类别）
+    #[arg(long, default_value_t = 0)]
+    pub tos: u8,
+    /// 报文标识，仅用于 IPv4
+    #[arg(long)]
+    pub ident: Option<u16>,
+    /// 设置不分片（DF）标志，仅用于 IPv4
+    #[arg(long)]
+    pub df: bool,
+    /// 设置更多分片（MF）标志，仅用于 IPv4
+    #[arg(long)]
+    pub mf: bool,
+    /// 片偏移，单位为 8 字节，仅用于 IPv4
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u16).range(..0x2000))]
+    pub offset: u16,
+    /// 强制按此大小（字节，向下取整为 8 的倍数）分片，缺省时只对超过接口 MTU 的报文分片。
+    /// 仅用于 IPv4
+    #[arg(long, value_parser = clap::value_parser!(u16).range(8..))]
+    pub frag_size: Option<u16>,
+    /// 加上可记录此数目地址的记录路由选项，仅用于 IPv4
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=9))]
+    pub record_route: Option<u8>,
+    /// 加上可记录此数目时间戳的时间戳选项，仅用于 IPv4
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=9))]
+    pub timestamp: Option<u8>,
+    /// 加上宽松源路由选项，按顺序经过的地址，最多可指定 9 次。仅用于 IPv4
+    #[arg(value_parser = ipp, long)]
+    pub lsrr: Vec<[u8; 4]>,
+    /// 加上严格源路由选项，按顺序经过的地址，最多可指定 9 次。仅用于 IPv4
+    #[arg(value_parser = ipp, long, conflicts_with = "lsrr")]
+    pub ssrr: Vec<[u8; 4]>,
+    /// 加上路由器警告选项，仅用于 IPv4
+    #[arg(long)]
+    pub router_alert: bool,
+    /// 协议类型。可选值有 TCP、UDP、ICMP、ICMPv6 以及十进制的一个字节长数字
+    #[arg(value_parser = protocolp, long, short)]
+    pub protocol: Protocol,
+    /// UDP 源端口
+    #[arg(long, default_value_t = 0)]
+    pub sport: u16,
+    /// UDP 目的端口
+    #[arg(long, default_value_t = 0)]
+    pub dport: u16,
+    /// 报文数据的编码。可选值有 hex、base64、text、escaped、raw
+    #[arg(value_parser = encodingp, long, short, default_value = "text")]
+    pub encoding: Encoding,
+    /// 报文数据
+    #[arg(long, short)]
+    pub text: Option<String>,
+    /// 报文数据文件路径，文件内容按 `--encoding` 解码
+    #[arg(long, short)]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct PingArgs {
+    /// 目的MAC地址，缺省时通过 ARP 解析目的主机或网关的地址
+    #[arg(value_parser = macp, long)]
+    pub dhost: Option<[u8; 6]>,
+    /// 目的IP地址
+    #[arg(value_parser = ipp, long, short)]
+    pub destip: [u8; 4],
+    /// 发送请求的个数，缺省时一直发送
+    #[arg(long, short)]
+    pub count: Option<usize>,
+    /// 相邻请求的间隔，单位为秒
+    #[arg(long, default_value_t = 1.0, value_parser = secsp)]
+    pub interval: f64,
+    /// 每个请求携带的数据字节数，加上 IPv4 与 ICMP 首部不超过 65535
+    #[arg(long, short, default_value_t = 56, value_parser = clap::value_parser!(u16).range(..=65507))]
+    pub size: u16,
+    /// 请求报文的生存期
+    #[arg(long, short, default_value_t = 64)]
+    pub ttl: u8,
+    /// 总运行时长上限，单位为秒
+    #[arg(long, short = 'w', value_parser = secsp)]
+    pub deadline: Option<f64>,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct TraceArgs {
+    /// 目的MAC地址（通常为网关），缺省时通过 ARP 解析
+    #[arg(value_parser = macp, long)]
+    pub dhost: Option<[u8; 6]>,
+    /// 目的IP地址
+    #[arg(value_parser = ipp, long, short)]
+    pub destip: [u8; 4],
+    /// 探测报文的协议。可选值有 ICMP（回显请求）、UDP（高端口）、TCP（SYN）
+    #[arg(value_parser = protocolp, long, short, default_value = "ICMP")]
+    pub protocol: Protocol,
+    /// 目的端口。UDP 缺省从 33434 起逐个递增，TCP 缺省为 80
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// 起始生存期
+    #[arg(long, short, default_value_t = 1)]
+    pub first_ttl: u8,
+    /// 最大跳数
+    #[arg(long, short, default_value_t = 30)]
+    pub max_hops: u8,
+    /// 每跳的探测次数
+    #[arg(long, short, default_value_t = 3)]
+    pub queries: usize,
+    /// 每个探测等待应答的时间，单位为秒
+    #[arg(long, short, default_value_t = 3.0, value_parser = secsp)]
+    pub wait: f64,
 }
 
 fn protocolp(inputs: &str) -> Result<Protocol, ParseIntError> {
@@ -57,25 +343,132 @@ fn protocolp(inputs: &str) -> Result<Protocol, ParseIntError> {
         "TCP" => Protocol::TCP,
         "UDP" => Protocol::UDP,
         "ICMP" => Protocol::ICMP,
-        _ => Protocol::Other(u8::from_str_radix(inputs, 10)?),
+        "ICMPv6" => Protocol::ICMPv6,
+        _ => Protocol::Other(inputs.parse()?),
     })
 }
 
-fn ipp(inputs: &str) -> Result<[u8; 4], ParseIntError> {
+fn pcapfmtp(inputs: &str) -> Result<PcapFormat, String> {
+    match inputs {
+        "pcap" => Ok(PcapFormat::Pcap),
+        "pcapng" => Ok(PcapFormat::Pcapng),
+        _ => Err(msg!("cli.unknown_format", value = inputs)),
+    }
+}
+
+/// 秒数，须为有限的非负数，且从现在起算的时刻不会溢出
+fn secsp(inputs: &str) -> Result<f64, String> {
+    let valid = |secs: &f64| {
+        Duration::try_from_secs_f64(*secs).is_ok_and(|d| Instant::now().checked_add(d).is_some())
+    };
+    inputs
+        .parse::<f64>()
+        .ok()
+        .filter(valid)
+        .ok_or_else(|| msg!("cli.bad_seconds", value = inputs))
+}
+
+fn encodingp(inputs: &str) -> Result<Encoding, String> {
+    match inputs {
+        "hex" => Ok(Encoding::Hex),
+        "base64" => Ok(Encoding::Base64),
+        "text" => Ok(Encoding::Text),
+        "escaped" => Ok(Encoding::Escaped),
+        "raw" => Ok(Encoding::Raw),
+        _ => Err(msg!("cli.unknown_encoding", value = inputs)),
+    }
+}
+
+fn langp(inputs: &str) -> Result<Lang, String> {
+    Lang::parse(inputs).ok_or_else(|| msg!("cli.unknown_lang", value = inputs))
+}
+
+fn outputp(inputs: &str) -> Result<OutputFormat, String> {
+    match inputs {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        "ndjson" => Ok(OutputFormat::Ndjson),
+        "csv" => Ok(OutputFormat::Csv),
+        _ => Err(msg!("cli.unknown_output", value = inputs)),
+    }
+}
+
+fn sectionp(inputs: &str) -> Result<Section, String> {
+    match inputs {
+        "link" | "ether" => Ok(Section::Link),
+        "network" | "ip" | "ipv6" => Ok(Section::Network),
+        "transport" | "tcp" | "udp" | "icmp" | "icmpv6" => Ok(Section::Transport),
+        "payload" | "data" => Ok(Section::Payload),
+        _ => Err(msg!("cli.unknown_section", value = inputs)),
+    }
+}
+
+fn sizep(inputs: &str) -> Result<u64, String> {
+    let (num, unit) = match inputs.char_indices().last() {
+        Some((idx, 'K' | 'k')) => (&inputs[..idx], 1 << 10),
+        Some((idx, 'M' | 'm')) => (&inputs[..idx], 1 << 20),
+        Some((idx, 'G' | 'g')) => (&inputs[..idx], 1 << 30),
+        _ => (inputs, 1),
+    };
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| msg!("cli.bad_size", value = inputs))
+}
+
+fn vlanp(inputs: &str) -> Result<VlanTag, String> {
+    let (vid, pcp) = match inputs.split_once(':') {
+        Some((vid, pcp)) => (vid, Some(pcp)),
+        None => (inputs, None),
+    };
+    let vid = vid
+        .parse::<u16>()
+        .ok()
+        .filter(|&vid| vid < 4096)
+        .ok_or_else(|| msg!("error.vlan_id", vid = vid))?;
+    let mut tag = VlanTag::new(vid);
+    if let Some(pcp) = pcp {
+        tag.pcp = pcp
+            .parse::<u8>()
+            .ok()
+            .filter(|&pcp| pcp < 8)
+            .ok_or_else(|| msg!("cli.bad_pcp", pcp = pcp))?;
+    }
+    Ok(tag)
+}
+
+fn ipp(inputs: &str) -> Result<[u8; 4], String> {
     if inputs == "localhost" {
         Ok([127, 0, 0, 1])
     } else {
-        ipparser::<4, 10>(inputs)
+        ipparser::<4, 10>(inputs, &['.'])
     }
 }
 
-fn ipparser<const S: usize, const R: u32>(inputs: &str) -> Result<[u8; S], ParseIntError> {
-    inputs
-        .split('.')
-        .enumerate()
-        .try_fold([0; S], |mut result, (idx, num)| {
-            let num = u8::from_str_radix(num, R)?;
-            result[idx] = num;
-            Ok(result)
-        })
+fn addrp(inputs: &str) -> Result<IpAddr, String> {
+    if inputs.contains(':') {
+        inputs
+            .parse::<Ipv6Addr>()
+            .map(IpAddr::from)
+            .map_err(|e| e.to_string())
+    } else {
+        ipp(inputs).map(IpAddr::from)
+    }
+}
+
+fn macp(inputs: &str) -> Result<[u8; 6], String> {
+    ipparser::<6, 16>(inputs, &[':', '-', '.'])
+}
+
+/// 解析以 `seps` 分隔的恰好 `S` 段地址，每段是一个 `R` 进制的字节
+fn ipparser<const S: usize, const R: u32>(inputs: &str, seps: &[char]) -> Result<[u8; S], String> {
+    let parts = inputs.split(seps).collect::<Vec<_>>();
+    if parts.len() != S {
+        return Err(msg!("cli.bad_address", value = inputs, parts = S));
+    }
+    let mut result = [0; S];
+    for (byte, part) in result.iter_mut().zip(parts) {
+        *byte = u8::from_str_radix(part, R).map_err(|e| e.to_string())?;
+    }
+    Ok(result)
 }
@@ -0,0 +1,225 @@
+//! 命令行参数的取值范围与解析
+
+use std::path::Path;
+
+use clap::Parser;
+
+use super::*;
+
+fn parse(args: &[&str]) -> Result<Args, clap::Error> {
+    Args::try_parse_from(["ipwrapper"].iter().chain(args))
+}
+
+#[test]
+fn address_parts() {
+    assert_eq!(ipp("192.0.2.1"), Ok([192, 0, 2, 1]));
+    assert_eq!(ipp("localhost"), Ok([127, 0, 0, 1]));
+    // 段数不符时报错而不是越界
+    assert!(ipp("192.0.2").is_err());
+    assert!(ipp("192.0.2.1.5").is_err());
+    assert!(ipp("192.0.2.256").is_err());
+    // 含冒号的按 IPv6 地址解析
+    assert_eq!(addrp("192.0.2.1"), Ok([192, 0, 2, 1].into()));
+    assert_eq!(addrp("fe80::1"), Ok("fe80::1".parse().unwrap()));
+    assert!(addrp("fe80::1::2").is_err());
+    let mac = [0x02, 0x00, 0x5e, 0x10, 0xab, 0xcd];
+    assert_eq!(macp("02:00:5e:10:ab:cd"), Ok(mac));
+    assert_eq!(macp("02-00-5e-10-ab-cd"), Ok(mac));
+    assert!(macp("02:00:5e:10:ab").is_err());
+    assert!(macp("02:00:5e:10:ab:cd:ef").is_err());
+}
+
+#[test]
+fn seconds_range() {
+    assert_eq!(secsp("0"), Ok(0.0));
+    assert_eq!(secsp("0.2"), Ok(0.2));
+    // 负数、非有限数以及加到当前时刻会溢出的秒数都应报错而不是在运行时崩溃
+    for bad in ["-1", "NaN", "inf", "1e300", "abc"] {
+        assert!(secsp(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn trace_wait_range() {
+    let trace = |wait: &str| match parse(&[
+        "trace",
+        "--dhost",
+        "02:00:00:00:00:01",
+        "-d",
+        "192.0.2.1",
+        &format!("--wait={wait}"),
+    ]) {
+        Ok(Args {
+            command: Command::Trace(args),
+            ..
+        }) => Ok(args.wait),
+        Ok(args) => panic!("{args:?}"),
+        Err(e) => Err(e.kind()),
+    };
+    assert_eq!(trace("0.5"), Ok(0.5));
+    // 等待时间会转换为 `Duration`，负数和非有限数在解析时报错
+    for bad in ["-1", "NaN", "inf"] {
+        assert_eq!(
+            trace(bad),
+            Err(clap::error::ErrorKind::ValueValidation),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn ping_size_range() {
+    let ping = |size: &str| match parse(&[
+        "ping",
+        "--dhost",
+        "02:00:00:00:00:01",
+        "-d",
+        "192.0.2.1",
+        "--size",
+        size,
+    ]) {
+        Ok(Args {
+            command: Command::Ping(args),
+            ..
+        }) => Ok(args.size),
+        Ok(args) => panic!("{args:?}"),
+        Err(e) => Err(e.kind()),
+    };
+    // 数据加上 20 字节 IPv4 首部和 8 字节 ICMP 首部不超过 65535
+    assert_eq!(ping("0"), Ok(0));
+    assert_eq!(ping("65507"), Ok(65507));
+    assert_eq!(ping("65508"), Err(clap::error::ErrorKind::ValueValidation));
+    assert_eq!(ping("-1"), Err(clap::error::ErrorKind::UnknownArgument));
+}
+
+#[test]
+fn global_interface() {
+    let interface = |args: &[&str]| parse(args).unwrap().interface;
+    // 全局选项可以写在子命令之前或之后
+    assert_eq!(interface(&["interfaces"]), None);
+    assert_eq!(interface(&["-i", "lo", "interfaces"]), Some("lo".into()));
+    assert_eq!(
+        interface(&["analyz", "--interface", "eth0"]),
+        Some("eth0".into())
+    );
+    // ping 的间隔只有长选项，`-i` 留给接口
+    let args = parse(&["ping", "-d", "192.0.2.1", "-i", "lo"]).unwrap();
+    assert_eq!(args.interface.as_deref(), Some("lo"));
+    let Command::Ping(ping) = args.command else {
+        panic!("{:?}", args.command);
+    };
+    assert_eq!(ping.interval, 1.0);
+}
+
+#[test]
+fn send_offset_range() {
+    let send =
+        |offset: &str| match parse(&["send", "-d", "192.0.2.1", "-p", "UDP", "--offset", offset]) {
+            Ok(Args {
+                command: Command::Send(args),
+                ..
+            }) => Ok(args.offset),
+            Ok(args) => panic!("{args:?}"),
+            Err(e) => Err(e.kind()),
+        };
+    // 片偏移只有 13 位
+    assert_eq!(send("0"), Ok(0));
+    assert_eq!(send("8191"), Ok(0x1fff));
+    assert_eq!(send("8192"), Err(clap::error::ErrorKind::ValueValidation));
+}
+
+#[test]
+fn vlan_tags() {
+    assert_eq!(vlanp("100"), Ok(VlanTag::new(100)));
+    let tag = vlanp("4095:7").unwrap();
+    assert_eq!((tag.vid, tag.pcp), (4095, 7));
+    for bad in ["4096", "-1", "100:8", "100:", "abc"] {
+        assert!(vlanp(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn analyz_limits() {
+    let analyz = |args: &[&str]| match parse(&[&["analyz"], args].concat()) {
+        Ok(Args {
+            command: Command::Analyz(args),
+            ..
+        }) => Ok((args.count, args.duration, args.interval, args.top)),
+        Ok(args) => panic!("{args:?}"),
+        Err(e) => Err(e.kind()),
+    };
+    assert_eq!(analyz(&[]), Ok((None, None, 1.0, 5)));
+    assert_eq!(
+        analyz(&[
+            "-c",
+            "10",
+            "--duration",
+            "2.5",
+            "--interval",
+            "0",
+            "--top",
+            "3"
+        ]),
+        Ok((Some(10), Some(2.5), 0.0, 3))
+    );
+    // 时长与间隔会转换为 `Duration`，负数和非有限数在解析时报错
+    for bad in ["-1", "NaN", "inf"] {
+        assert_eq!(
+            analyz(&[&format!("--duration={bad}")]),
+            Err(clap::error::ErrorKind::ValueValidation),
+            "{bad}"
+        );
+        assert_eq!(
+            analyz(&[&format!("--interval={bad}")]),
+            Err(clap::error::ErrorKind::ValueValidation),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn output_formats() {
+    assert_eq!(outputp("ndjson"), Ok(OutputFormat::Ndjson));
+    assert!(outputp("JSON").is_err());
+    let args = parse(&["filter", "-o", "csv", "tcp"]).unwrap();
+    let Command::Filter(filter) = args.command else {
+        panic!("{:?}", args.command);
+    };
+    assert_eq!(filter.output, OutputFormat::Csv);
+    let args = parse(&["analyz"]).unwrap();
+    let Command::Analyz(analyz) = args.command else {
+        panic!("{:?}", args.command);
+    };
+    assert_eq!(analyz.output, OutputFormat::Text);
+}
+
+#[test]
+fn log_rotation_args() {
+    assert_eq!(sizep("0"), Ok(0));
+    assert_eq!(sizep("512"), Ok(512));
+    assert_eq!(sizep("10M"), Ok(10 << 20));
+    assert_eq!(sizep("2g"), Ok(2 << 30));
+    for bad in ["", "M", "1.5M", "-1K", "1T", "99999999999G"] {
+        assert!(sizep(bad).is_err(), "{bad}");
+    }
+    let logging = |args: &[&str]| match parse(&[&["filter"], args].concat()) {
+        Ok(Args {
+            command: Command::Filter(args),
+            ..
+        }) => Ok(args.logging),
+        Ok(args) => panic!("{args:?}"),
+        Err(e) => Err(e.kind()),
+    };
+    let args = logging(&[]).unwrap();
+    assert_eq!(args.log, None);
+    assert_eq!((args.log_size, args.log_keep), (10 << 20, 10));
+    let args = logging(&["-l", "/tmp/ipw", "--log-interval", "60", "--log-gzip"]).unwrap();
+    assert_eq!(args.log.as_deref(), Some(Path::new("/tmp/ipw")));
+    assert_eq!(args.log_interval, Some(60.0));
+    assert!(args.log_gzip);
+    // 轮转间隔会转换为 `Duration`，负数在解析时报错
+    assert_eq!(
+        logging(&["--log-interval=-1"]).unwrap_err(),
+        clap::error::ErrorKind::ValueValidation
+    );
+}
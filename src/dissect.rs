@@ -0,0 +1,453 @@
+//! 分层显示：各层首部的字段树与 `xxd` 风格的十六进制转储。
+//!
+//! 字段树的每个节点记录它在所属缓冲区中的字节范围。`-v` 只显示各层，`-vv` 展开各层的
+//! 字段，`-vvv` 再在字段后附上原始字节；转储可以高亮某一层的字节。
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fmt::Display,
+    net::{Ipv4Addr, Ipv6Addr},
+    ops::Range,
+};
+
+use crate::{
+    head::{
+        checksum::Verdict, EtherHdr, Header, IPHdr, Icmpv6, Icmpv6Msg, Ipv6Hdr, Ping, TcpHdr,
+        UdpHdr, ICMP,
+    },
+    i18n::msg,
+    output::mac,
+};
+
+/// 转储中每行的字节数
+const ROW: usize = 16;
+/// `-vvv` 时字段后最多附上的字节数
+const RAW_MAX: usize = 16;
+/// 高亮的开始与结束（反显）
+const HL_ON: &str = "\x1b[7m";
+const HL_OFF: &str = "\x1b[0m";
+
+/// 报文中可以单独高亮的层
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// 以太网首部，包括 VLAN 标签与 LLC 首部
+    Link,
+    /// IPv4 或 IPv6 首部，包括选项与扩展首部
+    Network,
+    /// TCP、UDP、ICMP 或 ICMPv6 首部
+    Transport,
+    /// 传输层之上的数据，或未重组的分片数据
+    Payload,
+}
+
+/// 字段树的节点，`range` 为它在缓冲区中的字节范围
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub label: String,
+    pub range: Range<usize>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(label: impl Into<String>, range: Range<usize>) -> Self {
+        Self {
+            label: label.into(),
+            range,
+            children: vec![],
+        }
+    }
+
+    /// 追加一个字段。`at`、`len` 为字段相对节点起点的偏移与长度
+    pub fn field(self, name: &str, value: impl Display, at: usize, len: usize) -> Self {
+        let start = self.range.start + at;
+        let label = msg!("tree.field", name = name, value = value);
+        self.child(Node::new(label, start..start + len))
+    }
+
+    pub fn child(mut self, node: Node) -> Self {
+        self.children.push(node);
+        self
+    }
+
+    /// 依次为 `items` 追加子节点，长度由 `size` 给出，从相对节点起点的偏移 `at` 开始
+    fn items<T: Display>(mut self, items: &[T], size: fn(&T) -> usize, mut at: usize) -> Self {
+        for item in items {
+            let start = self.range.start + at;
+            self.children
+                .push(Node::new(item.to_string(), start..start + size(item)));
+            at += size(item);
+        }
+        self
+    }
+}
+
+/// 一个缓冲区及其中各层的字段树
+#[derive(Debug)]
+pub struct Tree<'a> {
+    data: &'a [u8],
+    layers: Vec<(Section, Node)>,
+}
+
+impl<'a> Tree<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            layers: vec![],
+        }
+    }
+
+    pub fn push(&mut self, section: Section, node: Node) {
+        self.layers.push((section, node));
+    }
+
+    /// 层 `section` 在缓冲区中的范围，缓冲区中没有该层时为 `None`
+    pub fn span(&self, section: Section) -> Option<Range<usize>> {
+        self.layers
+            .iter()
+            .filter(|(s, _)| *s == section)
+            .map(|(_, node)| node.range.clone())
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+    }
+
+    /// 按详细程度 `verbose` 显示的字段树：1 只显示各层，2 展开字段，3 附上原始字节
+    pub fn render(&self, verbose: u8) -> Render<'_> {
+        Render {
+            tree: self,
+            verbose,
+        }
+    }
+
+    /// 整个缓冲区的转储，高亮层 `section` 的字节
+    pub fn dump(&self, section: Option<Section>) -> HexDump<'a> {
+        HexDump::new(self.data).highlight(section.and_then(|s| self.span(s)))
+    }
+}
+
+/// [`Tree::render`] 的结果
+pub struct Render<'a> {
+    tree: &'a Tree<'a>,
+    verbose: u8,
+}
+
+impl Render<'_> {
+    fn node(&self, f: &mut std::fmt::Formatter<'_>, node: &Node, depth: usize) -> std::fmt::Result {
+        let Range { start, end } = node.range;
+        let range = msg!("tree.range", offset = start, len = end - start);
+        write!(
+            f,
+            "{:indent$}{}  {range}",
+            "",
+            node.label,
+            indent = depth * 4
+        )?;
+        if self.verbose >= 3 && depth > 0 {
+            let bytes = self.tree.data.get(start..end.min(self.tree.data.len()));
+            let bytes = bytes.unwrap_or_default();
+            let raw = bytes
+                .iter()
+                .take(RAW_MAX)
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>();
+            let more = if bytes.len() > RAW_MAX { " …" } else { "" };
+            write!(f, " = {}{more}", raw.join(" "))?;
+        }
+        if self.verbose >= 2 {
+            for child in &node.children {
+                writeln!(f)?;
+                self.node(f, child, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Render<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, (_, node)) in self.tree.layers.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            self.node(f, node, 0)?;
+        }
+        Ok(())
+    }
+}
+
+/// `xxd` 风格的十六进制与 ASCII 转储，每行 16 字节，`highlight` 范围内的字节反显
+#[derive(Debug, Clone)]
+pub struct HexDump<'a> {
+    data: &'a [u8],
+    highlight: Option<Range<usize>>,
+}
+
+impl<'a> HexDump<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            highlight: None,
+        }
+    }
+
+    pub fn highlight(self, highlight: Option<Range<usize>>) -> Self {
+        Self { highlight, ..self }
+    }
+
+    fn lit(&self, idx: usize) -> bool {
+        self.highlight.as_ref().is_some_and(|r| r.contains(&idx))
+    }
+
+    /// 输出一行中的字节，`cell` 输出单个字节，`gap` 为第 `i` 个字节之后的分隔
+    fn cells(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        offset: usize,
+        chunk: &[u8],
+        cell: fn(u8) -> String,
+        gap: fn(usize) -> &'static str,
+    ) -> std::fmt::Result {
+        for (i, &byte) in chunk.iter().enumerate() {
+            let idx = offset + i;
+            // 高亮覆盖同一范围内字节之间的分隔，但不延伸到范围之外
+            let on = self.lit(idx);
+            if on && (i == 0 || !self.lit(idx - 1)) {
+                write!(f, "{HL_ON}")?;
+            }
+            write!(f, "{}", cell(byte))?;
+            let next = i + 1 < chunk.len() && self.lit(idx + 1);
+            if on && !next {
+                write!(f, "{HL_OFF}")?;
+            }
+            if i + 1 < chunk.len() {
+                write!(f, "{}", gap(i))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for HexDump<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (row, chunk) in self.data.chunks(ROW).enumerate() {
+            let offset = row * ROW;
+            if row > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{offset:08x}: ")?;
+            let gap = |i: usize| if i % 2 == 1 { " " } else { "" };
+            self.cells(f, offset, chunk, |b| format!("{b:02x}"), gap)?;
+            // 不满一行时补齐十六进制区，使 ASCII 区对齐
+            let width = ROW * 2 + ROW / 2 - 1;
+            let used = chunk.len() * 2 + (chunk.len() - 1) / 2;
+            write!(f, "{:pad$}  ", "", pad = width - used)?;
+            let ascii = |b: u8| match b {
+                0x20..=0x7e => (b as char).to_string(),
+                _ => ".".to_string(),
+            };
+            self.cells(f, offset, chunk, ascii, |_| "")?;
+        }
+        Ok(())
+    }
+}
+
+/// 校验和字段的值
+fn checksum(chksum: u16, verdict: Verdict) -> String {
+    format!("{chksum:#06x} ({verdict})")
+}
+
+/// 以太网首部，`at` 为其在帧中的偏移
+pub fn ether(hdr: &EtherHdr, at: usize) -> Node {
+    let title = msg!("tree.ether", src = mac(&hdr.shost), dst = mac(&hdr.dhost));
+    let mut node = Node::new(title, at..at + hdr.size())
+        .field(msg!("field.dst"), mac(&hdr.dhost), 0, 6)
+        .field(msg!("field.src"), mac(&hdr.shost), 6, 6);
+    for (idx, tag) in hdr.vlans.iter().enumerate() {
+        let start = at + 12 + idx * 4;
+        node = node.child(Node::new(tag.to_string(), start..start + 4));
+    }
+    let etype = format!("{:?} ({:#06x})", hdr.etype, u16::from(hdr.etype));
+    let at = 12 + hdr.vlans.len() * 4;
+    node = node.field(msg!("field.etype"), etype, at, 2);
+    if let Some(llc) = hdr.llc {
+        let start = node.range.start + at + 2;
+        node = node.child(Node::new(llc.to_string(), start..start + llc.size()));
+    }
+    node
+}
+
+/// IPv4 首部，`at` 为其在帧中的偏移
+pub fn ipv4(hdr: &IPHdr, verdict: Verdict, at: usize) -> Node {
+    let (src, dst) = (Ipv4Addr::from(hdr.source), Ipv4Addr::from(hdr.destinaiton));
+    let len = hdr.ihl as usize;
+    let flags = format!("DF={} MF={}", hdr.flag.df as u8, hdr.flag.mf as u8);
+    let frag_offset = msg!("tree.bytes", len = hdr.offset as usize * 8);
+    let node = Node::new(msg!("tree.ipv4", src = src, dst = dst), at..at + len)
+        .field(msg!("field.version"), hdr.version, 0, 1)
+        .field(msg!("field.hdr_len"), msg!("tree.bytes", len = len), 0, 1)
+        .field(msg!("field.tos"), format!("{:#04x}", hdr.tos), 1, 1)
+        .field(msg!("field.total_len"), hdr.totlen, 2, 2)
+        .field(msg!("field.ident"), format!("{:#06x}", hdr.ident), 4, 2)
+        .field(msg!("field.flags"), flags, 6, 1)
+        .field(msg!("field.frag_offset"), frag_offset, 6, 2)
+        .field(msg!("field.ttl"), hdr.ttl, 8, 1)
+        .field(msg!("field.protocol"), format!("{:?}", hdr.protocol), 9, 1)
+        .field(msg!("field.checksum"), checksum(hdr.chksum, verdict), 10, 2)
+        .field(msg!("field.src"), src, 12, 4)
+        .field(msg!("field.dst"), dst, 16, 4);
+    if len <= 20 {
+        return node;
+    }
+    let options = Node::new(msg!("field.options"), at + 20..at + len).items(
+        &hdr.options,
+        |opt| opt.size(),
+        0,
+    );
+    node.child(options)
+}
+
+/// IPv6 首部及其扩展首部，`at` 为其在帧中的偏移
+pub fn ipv6(hdr: &Ipv6Hdr, at: usize) -> Node {
+    let (src, dst) = (Ipv6Addr::from(hdr.source), Ipv6Addr::from(hdr.destination));
+    let next = hdr
+        .exts
+        .first()
+        .map_or(u8::from(hdr.protocol), |ext| ext.code());
+    let range = at..at + 40 + hdr.ext_len();
+    Node::new(msg!("tree.ipv6", src = src, dst = dst), range)
+        .field(msg!("field.version"), hdr.version, 0, 1)
+        .field(msg!("field.tclass"), format!("{:#04x}", hdr.tclass), 0, 2)
+        .field(msg!("field.flow"), format!("{:#07x}", hdr.flow), 1, 3)
+        .field(msg!("field.payload_len"), hdr.plen, 4, 2)
+        .field(msg!("field.next_header"), next, 6, 1)
+        .field(msg!("field.hop_limit"), hdr.hlim, 7, 1)
+        .field(msg!("field.src"), src, 8, 16)
+        .field(msg!("field.dst"), dst, 24, 16)
+        .items(&hdr.exts, |ext| ext.size(), 40)
+}
+
+/// TCP 首部，`at` 为其在所属缓冲区中的偏移
+pub fn tcp(hdr: &TcpHdr, verdict: Verdict, at: usize) -> Node {
+    let len = hdr.doff as usize;
+    let title = msg!(
+        "tree.tcp",
+        sport = hdr.sport,
+        dport = hdr.dport,
+        flags = hdr.flag
+    );
+    let node = Node::new(title, at..at + len)
+        .field(msg!("field.sport"), hdr.sport, 0, 2)
+        .field(msg!("field.dport"), hdr.dport, 2, 2)
+        .field(msg!("field.seq"), hdr.seqnum, 4, 4)
+        .field(msg!("field.ack"), hdr.acknum, 8, 4)
+        .field(msg!("field.hdr_len"), msg!("tree.bytes", len = len), 12, 1)
+        .field(msg!("field.flags"), hdr.flag, 12, 2)
+        .field(msg!("field.window"), hdr.window, 14, 2)
+        .field(msg!("field.checksum"), checksum(hdr.chksum, verdict), 16, 2)
+        .field(msg!("field.urgent"), hdr.urgent, 18, 2);
+    if len <= 20 {
+        return node;
+    }
+    let options = Node::new(msg!("field.options"), at + 20..at + len).items(
+        &hdr.options,
+        |opt| opt.size(),
+        0,
+    );
+    node.child(options)
+}
+
+/// UDP 首部，`at` 为其在所属缓冲区中的偏移
+pub fn udp(hdr: &UdpHdr, verdict: Verdict, at: usize) -> Node {
+    let title = msg!("tree.udp", sport = hdr.sport, dport = hdr.dport);
+    Node::new(title, at..at + 8)
+        .field(msg!("field.sport"), hdr.sport, 0, 2)
+        .field(msg!("field.dport"), hdr.dport, 2, 2)
+        .field(msg!("field.length"), hdr.len, 4, 2)
+        .field(msg!("field.checksum"), checksum(hdr.chksum, verdict), 6, 2)
+}
+
+/// 回显请求或应答的标识与序号
+fn echo(node: Node, ping: &Ping) -> Node {
+    node.field(msg!("field.ident"), ping.ident, 4, 2)
+        .field(msg!("field.seq"), ping.seqnum, 6, 2)
+}
+
+/// ICMP 首部，`at` 为其在所属缓冲区中的偏移
+pub fn icmp(hdr: &ICMP, verdict: Verdict, at: usize) -> Node {
+    let len = hdr.clone().to_bytes().len();
+    let title = msg!("tree.icmp", description = hdr.typ_dsc());
+    let node = Node::new(title, at..at + len)
+        .field(msg!("field.type"), hdr.typ, 0, 1)
+        .field(msg!("field.code"), hdr.code, 1, 1)
+        .field(msg!("field.checksum"), checksum(hdr.chksum, verdict), 2, 2);
+    match &hdr.msg {
+        Some(ping) => echo(node, ping),
+        None => node,
+    }
+}
+
+/// ICMPv6 报文（包括邻居发现选项），`at` 为其在所属缓冲区中的偏移
+pub fn icmpv6(hdr: &Icmpv6, verdict: Verdict, at: usize) -> Node {
+    let len = hdr.clone().to_bytes().len();
+    let title = msg!("tree.icmpv6", description = hdr.typ_dsc());
+    let node = Node::new(title, at..at + len)
+        .field(msg!("field.type"), hdr.typ, 0, 1)
+        .field(msg!("field.code"), hdr.code, 1, 1)
+        .field(msg!("field.checksum"), checksum(hdr.chksum, verdict), 2, 2);
+    let options = |node: Node, at| {
+        let options = hdr.msg.as_ref().map_or(&[][..], |msg| msg.options());
+        node.items(options, |opt| opt.size(), at)
+    };
+    match &hdr.msg {
+        None => node,
+        Some(Icmpv6Msg::Echo(ping)) => echo(node, ping),
+        Some(Icmpv6Msg::RouterSolicit { .. }) => options(node, 8),
+        Some(Icmpv6Msg::RouterAdvert {
+            hop_limit,
+            managed,
+            other,
+            lifetime,
+            reachable,
+            retrans,
+            ..
+        }) => {
+            let flags = format!("M={} O={}", *managed as u8, *other as u8);
+            let node = node
+                .field(msg!("field.hop_limit"), hop_limit, 4, 1)
+                .field(msg!("field.flags"), flags, 5, 1)
+                .field(msg!("field.lifetime"), lifetime, 6, 2)
+                .field(msg!("field.reachable"), reachable, 8, 4)
+                .field(msg!("field.retrans"), retrans, 12, 4);
+            options(node, 16)
+        }
+        Some(Icmpv6Msg::NeighborSolicit { target, .. }) => {
+            let node = node.field(msg!("field.target"), Ipv6Addr::from(*target), 8, 16);
+            options(node, 24)
+        }
+        Some(Icmpv6Msg::NeighborAdvert {
+            router,
+            solicited,
+            override_,
+            target,
+            ..
+        }) => {
+            let flags = format!(
+                "R={} S={} O={}",
+                *router as u8, *solicited as u8, *override_ as u8
+            );
+            let target = Ipv6Addr::from(*target);
+            let node = node.field(msg!("field.flags"), flags, 4, 1).field(
+                msg!("field.target"),
+                target,
+                8,
+                16,
+            );
+            options(node, 24)
+        }
+    }
+}
+
+/// 传输层之上的 `len` 字节数据，`at` 为其在所属缓冲区中的偏移
+pub fn data(len: usize, at: usize) -> Node {
+    Node::new(msg!("tree.data", len = len), at..at + len)
+}
@@ -0,0 +1,210 @@
+//! 转储的行格式与高亮、截断的帧，以及各详细程度下的字段树
+
+use std::path::Path;
+
+use super::*;
+use crate::{pcap::PcapReader, source::PacketSource};
+
+/// 捕获文件中的 ICMP 回显请求
+fn echo_frame() -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/icmp-echo.pcap");
+    let mut reader = PcapReader::open(&path).unwrap();
+    reader.next_frame().unwrap().unwrap().data
+}
+
+/// 按 `filter -v` 的方式为帧 `full` 建立字段树，缓冲区为可能被截断的 `data`
+fn echo_tree<'a>(full: &[u8], data: &'a [u8]) -> Tree<'a> {
+    let ((ethdr, iphdr), rest) = <(EtherHdr, IPHdr)>::from_bytes(full).unwrap();
+    let (icmphdr, payload) = ICMP::from_bytes(rest).unwrap();
+    let mut tree = Tree::new(data);
+    tree.push(Section::Link, ether(&ethdr, 0));
+    tree.push(Section::Network, ipv4(&iphdr, Verdict::Valid, 14));
+    tree.push(Section::Transport, icmp(&icmphdr, Verdict::Valid, 34));
+    tree.push(Section::Payload, self::data(payload.len(), 42));
+    tree
+}
+
+#[test]
+fn hex_dump_layout() {
+    let data = b"ABCDEFGHIJKLMNOP\x00\x01\x7f ~";
+    let text = HexDump::new(data).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "00000000: 4142 4344 4546 4748 494a 4b4c 4d4e 4f50  ABCDEFGHIJKLMNOP",
+            &format!("00000010: {:39}  ... ~", "0001 7f20 7e"),
+        ]
+    );
+    // 不满一行时 ASCII 区与整行对齐
+    assert_eq!(lines[0].find("  A"), lines[1].find("  ."));
+
+    assert_eq!(HexDump::new(&[]).to_string(), "");
+    assert_eq!(
+        HexDump::new(&[0xff]).to_string(),
+        format!("00000000: {:39}  .", "ff")
+    );
+    let rows = HexDump::new(&[0; 33]).to_string();
+    let offsets = rows.lines().map(|line| &line[..9]).collect::<Vec<_>>();
+    assert_eq!(offsets, ["00000000:", "00000010:", "00000020:"]);
+}
+
+#[test]
+fn highlight_ranges() {
+    let dump = |range| HexDump::new(b"abcdef").highlight(Some(range)).to_string();
+    // 范围内字节之间的分隔也被高亮
+    assert_eq!(
+        dump(2..5),
+        format!(
+            "00000000: 6162 {HL_ON}6364 65{HL_OFF}66{}  ab{HL_ON}cde{HL_OFF}f",
+            " ".repeat(25)
+        )
+    );
+    assert_eq!(
+        dump(0..1),
+        format!(
+            "00000000: {HL_ON}61{HL_OFF}62 6364 6566{}  {HL_ON}a{HL_OFF}bcdef",
+            " ".repeat(25)
+        )
+    );
+    // 超出数据的部分被忽略，空范围不高亮
+    let text = dump(5..100);
+    assert!(text.contains(&format!(" 65{HL_ON}66{HL_OFF} ")), "{text:?}");
+    assert!(
+        text.ends_with(&format!("  abcde{HL_ON}f{HL_OFF}")),
+        "{text:?}"
+    );
+    assert_eq!(dump(3..3), HexDump::new(b"abcdef").to_string());
+
+    // 跨行的范围在每行各自开始和结束
+    let data = (0..20).map(|n| b'a' + n).collect::<Vec<_>>();
+    let text = HexDump::new(&data).highlight(Some(14..18)).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert!(lines[0].contains(" \x1b[7m6f70\x1b[0m  "), "{text:?}");
+    assert!(lines[0].ends_with("\x1b[7mop\x1b[0m"), "{text:?}");
+    assert!(
+        lines[1].starts_with("00000010: \x1b[7m7172\x1b[0m 7374"),
+        "{text:?}"
+    );
+
+    // 按层高亮时取该层的字节范围
+    let frame = echo_frame();
+    let tree = echo_tree(&frame, &frame);
+    assert_eq!(tree.span(Section::Network), Some(14..34));
+    assert_eq!(tree.span(Section::Payload), Some(42..98));
+    let text = tree.dump(Some(Section::Transport)).to_string();
+    assert_eq!(text.matches(HL_ON).count(), 2);
+    let lines = text.lines().collect::<Vec<_>>();
+    // 第 34 字节位于第 3 行第 3 个字节，ICMP 首部共 8 字节
+    let lit = format!("00000020: 0201 {HL_ON}0800 ");
+    assert!(lines[2].starts_with(&lit), "{text:?}");
+    assert!(lines[2].contains(&format!("0001{HL_OFF} 0001")), "{text:?}");
+    assert_eq!(
+        tree.dump(None).to_string(),
+        HexDump::new(&frame).to_string()
+    );
+}
+
+/// `--snaplen` 截断的帧：转储只有捕获到的字节，截断之后的字段不附原始字节
+#[test]
+fn snaplen_truncation() {
+    let frame = echo_frame();
+    // 截断在 ICMP 校验和的两个字节之间
+    let tree = echo_tree(&frame, &frame[..37]);
+    let dump = tree.dump(Some(Section::Payload)).to_string();
+    let lines = dump.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    let hex = format!("0201 0800 {:02x}", frame[36]);
+    assert!(lines[2].starts_with(&format!("00000020: {hex:39}  ")));
+    assert!(!dump.contains(HL_ON));
+    let dump = tree.dump(Some(Section::Transport)).to_string();
+    let last = dump.lines().last().unwrap();
+    let lit = format!("00000020: 0201 {HL_ON}0800 {:02x}{HL_OFF} ", frame[36]);
+    assert!(last.starts_with(&lit), "{dump:?}");
+
+    // 各字段的原始字节：`name` 为字段名，取最后一个同名字段
+    let text = tree.render(3).to_string();
+    let raw = |name: &str| {
+        let prefix = msg!("tree.field", name = name, value = "");
+        let line = text
+            .lines()
+            .rev()
+            .find(|line| line.trim_start().starts_with(&prefix))
+            .unwrap();
+        line.split_once(" = ").unwrap().1.to_string()
+    };
+    assert_eq!(raw(msg!("field.ttl")), "40");
+    assert_eq!(raw(msg!("field.type")), "08");
+    assert_eq!(raw(msg!("field.checksum")), format!("{:02x}", frame[36]));
+    assert_eq!(raw(msg!("field.ident")), "");
+    // 数据节点的范围仍按原始长度显示
+    let data = msg!("tree.data", len = 56);
+    let line = text.lines().find(|line| line.starts_with(&data)).unwrap();
+    let range = msg!("tree.range", offset = 42, len = 56);
+    assert_eq!(line, format!("{data}  {range}"));
+}
+
+#[test]
+fn verbose_levels() {
+    let frame = echo_frame();
+    let tree = echo_tree(&frame, &frame);
+
+    // -v：每层一行，不缩进
+    let text = tree.render(1).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines.iter().all(|line| !line.starts_with(' ')));
+    assert!(!text.contains(" = "));
+    let ipv4 = msg!("tree.ipv4", src = "192.0.2.2", dst = "192.0.2.1");
+    let range = msg!("tree.range", offset = 14, len = 20);
+    assert_eq!(lines[1], format!("{ipv4}  {range}"));
+
+    // -vv：展开字段，字段缩进 4 格
+    let text = tree.render(2).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines.iter().filter(|line| !line.starts_with(' ')).count(),
+        4
+    );
+    // 以太网 3 个、IPv4 12 个、ICMP 5 个字段
+    assert_eq!(lines.len(), 4 + 3 + 12 + 5);
+    assert!(lines.iter().all(|line| !line.starts_with("     ")));
+    assert!(!text.contains(" = "));
+    let ttl = msg!("tree.field", name = msg!("field.ttl"), value = 64);
+    let range = msg!("tree.range", offset = 22, len = 1);
+    assert!(lines.contains(&format!("    {ttl}  {range}").as_str()));
+
+    // -vvv：字段后附上原始字节，各层标题不附
+    let text = tree.render(3).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4 + 3 + 12 + 5);
+    assert!(lines.contains(&format!("    {ttl}  {range} = 40").as_str()));
+    assert!(lines
+        .iter()
+        .filter(|line| !line.starts_with(' '))
+        .all(|line| !line.contains(" = ")));
+    let dst = msg!(
+        "tree.field",
+        name = msg!("field.dst"),
+        value = "02:fc:00:00:00:05"
+    );
+    let range = msg!("tree.range", offset = 0, len = 6);
+    assert!(lines.contains(&format!("    {dst}  {range} = 02 fc 00 00 00 05").as_str()));
+}
+
+/// 嵌套的节点逐层缩进，超过 16 字节的原始字节被省略
+#[test]
+fn nested_nodes() {
+    let data = (0..40).collect::<Vec<u8>>();
+    let mut tree = Tree::new(&data);
+    let inner = Node::new("inner", 4..24).field("long", "x", 0, 20);
+    tree.push(Section::Payload, Node::new("outer", 0..40).child(inner));
+    let text = tree.render(3).to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert!(lines[1].starts_with("    inner  "));
+    assert!(lines[1].ends_with(" = 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f 10 11 12 13 …"));
+    let long = msg!("tree.field", name = "long", value = "x");
+    assert!(lines[2].starts_with(&format!("        {long}  ")));
+    assert_eq!(tree.span(Section::Payload), Some(0..40));
+    assert_eq!(tree.span(Section::Link), None);
+}
@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests;
+
+use std::fmt::Display;
+
+use crate::i18n::msg;
+
+/// 报文数据的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// 十六进制字符串，字节之间可以用空白、`:`、`-`、`,`、`.` 分隔
+    Hex,
+    /// 标准或 URL 安全字母表的 Base64，填充可省略
+    Base64,
+    /// 原样发送的 UTF-8 文本
+    #[default]
+    Text,
+    /// 支持 `\x00`、`\n`、`\t`、`\r`、`\0`、`\\` 转义的文本
+    Escaped,
+    /// 原样发送的二进制数据，通常配合文件使用
+    Raw,
+}
+
+/// 数据解码失败，`offset` 为出错位置在输入中的字节偏移
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = msg!("decode.error", offset = self.offset, reason = self.reason);
+        write!(f, "{text}")
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for std::io::Error {
+    fn from(e: DecodeError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
+
+fn error(offset: usize, reason: impl Into<String>) -> DecodeError {
+    DecodeError {
+        offset,
+        reason: reason.into(),
+    }
+}
+
+/// 描述输入中 `offset` 处的字符，用于错误信息
+fn describe(input: &[u8], offset: usize) -> String {
+    match input[offset..].utf8_chunks().next() {
+        Some(chunk) if !chunk.valid().is_empty() => {
+            format!("`{}`", chunk.valid().chars().next().unwrap().escape_debug())
+        }
+        _ => format!("0x{:02x}", input[offset]),
+    }
+}
+
+impl Encoding {
+    /// 按编码方式将输入解码为报文数据
+    pub fn decode(self, input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        match self {
+            Encoding::Hex => hex(input),
+            Encoding::Base64 => base64(input),
+            Encoding::Text => match std::str::from_utf8(input) {
+                Ok(text) => Ok(text.as_bytes().to_vec()),
+                Err(e) => Err(error(e.valid_up_to(), msg!("decode.utf8"))),
+            },
+            Encoding::Escaped => escaped(input),
+            Encoding::Raw => Ok(input.to_vec()),
+        }
+    }
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+/// 十六进制字节之间允许的分隔符
+fn is_separator(c: u8) -> bool {
+    c.is_ascii_whitespace() || b":-,.".contains(&c)
+}
+
+fn hex(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = vec![];
+    let mut idx = 0;
+    while idx < input.len() {
+        let c = input[idx];
+        if is_separator(c) {
+            idx += 1;
+            continue;
+        }
+        let Some(high) = hex_digit(c) else {
+            return Err(error(
+                idx,
+                msg!("decode.hex_digit", c = describe(input, idx)),
+            ));
+        };
+        let Some(low) = input.get(idx + 1).and_then(|&c| hex_digit(c)) else {
+            // 落单的数字指向其本身，字节中间的非法字符指向该字符
+            return Err(match input.get(idx + 1) {
+                Some(&c) if !is_separator(c) => error(
+                    idx + 1,
+                    msg!("decode.hex_digit", c = describe(input, idx + 1)),
+                ),
+                _ => error(idx, msg!("decode.hex_odd")),
+            });
+        };
+        bytes.push(high << 4 | low);
+        idx += 2;
+    }
+    Ok(bytes)
+}
+
+fn base64_digit(c: u8) -> Option<u32> {
+    Some(match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    } as u32)
+}
+
+fn base64(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = vec![];
+    let (mut acc, mut bits, mut digits) = (0u32, 0, 0usize);
+    let mut padding = None;
+    for (idx, &c) in input.iter().enumerate() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        if c == b'=' {
+            padding.get_or_insert(idx);
+            continue;
+        }
+        if padding.is_some() {
+            return Err(error(idx, msg!("decode.base64_padding")));
+        }
+        let Some(value) = base64_digit(c) else {
+            return Err(error(
+                idx,
+                msg!("decode.base64_char", c = describe(input, idx)),
+            ));
+        };
+        acc = acc << 6 | value;
+        bits += 6;
+        digits += 1;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if digits % 4 == 1 {
+        return Err(error(
+            padding.unwrap_or(input.len()),
+            msg!("decode.base64_length"),
+        ));
+    }
+    Ok(bytes)
+}
+
+fn escaped(input: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = vec![];
+    let mut idx = 0;
+    while idx < input.len() {
+        if input[idx] != b'\\' {
+            bytes.push(input[idx]);
+            idx += 1;
+            continue;
+        }
+        let Some(&c) = input.get(idx + 1) else {
+            return Err(error(idx, msg!("decode.escape_missing")));
+        };
+        let byte = match c {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'0' => 0,
+            b'\\' => b'\\',
+            b'x' => {
+                let digits = input
+                    .get(idx + 2..idx + 4)
+                    .and_then(|ds| hex_digit(ds[0]).zip(hex_digit(ds[1])));
+                let Some((high, low)) = digits else {
+                    return Err(error(idx, msg!("decode.escape_hex")));
+                };
+                idx += 2;
+                high << 4 | low
+            }
+            _ => {
+                return Err(error(
+                    idx,
+                    msg!("decode.escape_unknown", c = describe(input, idx + 1)),
+                ))
+            }
+        };
+        bytes.push(byte);
+        idx += 2;
+    }
+    Ok(bytes)
+}
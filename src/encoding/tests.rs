@@ -0,0 +1,80 @@
+//! 各编码方式的解码结果与出错位置
+
+use super::*;
+
+fn offset(encoding: Encoding, input: &str) -> usize {
+    encoding.decode(input.as_bytes()).unwrap_err().offset
+}
+
+#[test]
+fn hex_decode() {
+    let decode = |input: &str| Encoding::Hex.decode(input.as_bytes()).unwrap();
+    assert_eq!(decode("deadBEEF"), [0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(
+        decode("de:ad-be,ef.01 02\n\t03"),
+        [0xde, 0xad, 0xbe, 0xef, 1, 2, 3]
+    );
+    assert_eq!(decode(""), []);
+    assert_eq!(decode(" : "), []);
+
+    // 非十六进制字符指向该字符，奇数个数字指向落单的数字
+    assert_eq!(offset(Encoding::Hex, "0g"), 1);
+    assert_eq!(offset(Encoding::Hex, "00 zz"), 3);
+    assert_eq!(offset(Encoding::Hex, "00 1"), 3);
+    assert_eq!(offset(Encoding::Hex, "00 1 2"), 3);
+    let err = Encoding::Hex.decode("00é".as_bytes()).unwrap_err();
+    assert_eq!(err.offset, 2);
+    assert_eq!(err.reason, msg!("decode.hex_digit", c = "`é`"));
+    let err = Encoding::Hex.decode(b"00\xff").unwrap_err();
+    assert_eq!(err.reason, msg!("decode.hex_digit", c = "0xff"));
+}
+
+#[test]
+fn base64_decode() {
+    let decode = |input: &str| Encoding::Base64.decode(input.as_bytes()).unwrap();
+    assert_eq!(decode("aGVsbG8="), b"hello");
+    assert_eq!(decode("aGVsbG8"), b"hello");
+    assert_eq!(decode("aGVsbG8h"), b"hello!");
+    assert_eq!(decode("QQ=="), b"A");
+    assert_eq!(decode("QQ"), b"A");
+    assert_eq!(decode("QUI="), b"AB");
+    assert_eq!(decode("aGVs\nbG8=\n"), b"hello");
+    assert_eq!(decode(""), b"");
+    // 标准与 URL 安全字母表
+    assert_eq!(decode("+/8="), [0xfb, 0xff]);
+    assert_eq!(decode("-_8"), [0xfb, 0xff]);
+
+    // 填充之后的数字指向该数字，多余的单个数字指向填充或输入末尾
+    assert_eq!(offset(Encoding::Base64, "QQ=A"), 3);
+    assert_eq!(offset(Encoding::Base64, "QQ== QQ=="), 5);
+    assert_eq!(offset(Encoding::Base64, "QUJDR"), 5);
+    assert_eq!(offset(Encoding::Base64, "QUJDR==="), 5);
+    assert_eq!(offset(Encoding::Base64, "Q"), 1);
+    assert_eq!(offset(Encoding::Base64, "QUJD*A=="), 4);
+}
+
+#[test]
+fn escaped_decode() {
+    let decode = |input: &str| Encoding::Escaped.decode(input.as_bytes()).unwrap();
+    assert_eq!(decode(r"a\nb\tc\rd\0e\\f"), b"a\nb\tc\rd\0e\\f");
+    assert_eq!(decode(r"\x00\xff\x7F"), [0, 0xff, 0x7f]);
+    assert_eq!(decode("中"), "中".as_bytes());
+
+    // 出错位置均指向反斜杠
+    assert_eq!(offset(Encoding::Escaped, r"ab\"), 2);
+    assert_eq!(offset(Encoding::Escaped, r"ab\x1"), 2);
+    assert_eq!(offset(Encoding::Escaped, r"ab\x1g"), 2);
+    assert_eq!(offset(Encoding::Escaped, r"\x00\q"), 4);
+    let err = Encoding::Escaped.decode(br"\q").unwrap_err();
+    assert_eq!(err.reason, msg!("decode.escape_unknown", c = "`q`"));
+}
+
+#[test]
+fn text_decode() {
+    assert_eq!(
+        Encoding::Text.decode("héllo".as_bytes()).unwrap(),
+        "héllo".as_bytes()
+    );
+    assert_eq!(Encoding::Text.decode(b"ab\xffcd").unwrap_err().offset, 2);
+    assert_eq!(Encoding::Raw.decode(b"ab\xffcd").unwrap(), b"ab\xffcd");
+}
@@ -0,0 +1,827 @@
+//! 过滤表达式：语法与 tcpdump 相近，解析为语法树后对解码出的各层首部求值。
+//!
+//! ```text
+//! expr    := and ("or" and)*
+//! and     := unary ("and" unary)*
+//! unary   := "not" unary | "(" expr ")" | primitive
+//! primitive := [src|dst] host ADDR | [src|dst] net ADDR[/LEN] | [src|dst] port N
+//!            | ether [src|dst] [host] MAC | vlan [ID] | proto NAME|N
+//!            | ip | ip6 | tcp | udp | icmp | icmp6
+//!            | FIELD [& MASK] (== | != | < | <= | > | >=) N
+//! ```
+//!
+//! `and`、`or`、`not` 也可以写作 `&&`、`||`、`!`，`and` 的优先级高于 `or`。
+
+pub mod bpf;
+#[cfg(test)]
+mod tests;
+
+use std::{fmt::Display, net::IpAddr};
+
+use crate::{
+    head::{EtherHdr, Icmpv6, NetHdr, Protocol, TcpHdr, UdpHdr, ICMP},
+    i18n::msg,
+};
+
+/// 表达式解析失败，`offset` 为出错位置在输入中的字符偏移
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    pub input: String,
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = msg!("expr.error", offset = self.offset + 1, reason = self.reason);
+        writeln!(f, "{text}")?;
+        writeln!(f, "  {}", self.input)?;
+        write!(f, "  {}^", " ".repeat(self.offset))
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// 地址、端口等匹配的方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dir {
+    Src,
+    Dst,
+    /// 源或目的
+    Any,
+}
+
+impl Display for Dir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dir::Src => write!(f, "src "),
+            Dir::Dst => write!(f, "dst "),
+            Dir::Any => Ok(()),
+        }
+    }
+}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    fn apply(self, lhs: u32, rhs: u32) -> bool {
+        match self {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl Display for CmpOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        };
+        write!(f, "{op}")
+    }
+}
+
+/// 可以比较的首部字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// 生存期，IPv6 中为跳数限制
+    Ttl,
+    /// 服务类型，IPv6 中为流量类别
+    Tos,
+    /// IP 报文总长度
+    Len,
+    IpId,
+    /// 片偏移，单位为 8 字节
+    IpOff,
+    IpProto,
+    IcmpType,
+    IcmpCode,
+    Icmp6Type,
+    Icmp6Code,
+    TcpSport,
+    TcpDport,
+    TcpFlags,
+    TcpWin,
+    UdpSport,
+    UdpDport,
+    UdpLen,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 17] = [
+        ("ttl", Field::Ttl),
+        ("tos", Field::Tos),
+        ("len", Field::Len),
+        ("ip.id", Field::IpId),
+        ("ip.off", Field::IpOff),
+        ("ip.proto", Field::IpProto),
+        ("icmp.type", Field::IcmpType),
+        ("icmp.code", Field::IcmpCode),
+        ("icmp6.type", Field::Icmp6Type),
+        ("icmp6.code", Field::Icmp6Code),
+        ("tcp.sport", Field::TcpSport),
+        ("tcp.dport", Field::TcpDport),
+        ("tcp.flags", Field::TcpFlags),
+        ("tcp.win", Field::TcpWin),
+        ("udp.sport", Field::UdpSport),
+        ("udp.dport", Field::UdpDport),
+        ("udp.len", Field::UdpLen),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Field::ALL
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, field)| field)
+    }
+
+    pub fn name(self) -> &'static str {
+        Field::ALL.iter().find(|&&(_, f)| f == self).unwrap().0
+    }
+
+    /// 报文中该字段的值，报文没有该字段时为 `None`
+    fn value(self, pkt: &Packet) -> Option<u32> {
+        let v4 = match pkt.net {
+            NetHdr::V4(hdr) => Some(hdr),
+            NetHdr::V6(_) => None,
+        };
+        Some(match self {
+            Field::Ttl => match pkt.net {
+                NetHdr::V4(hdr) => hdr.ttl as u32,
+                NetHdr::V6(hdr) => hdr.hlim as u32,
+            },
+            Field::Tos => match pkt.net {
+                NetHdr::V4(hdr) => hdr.tos as u32,
+                NetHdr::V6(hdr) => hdr.tclass as u32,
+            },
+            Field::Len => match pkt.net {
+                NetHdr::V4(hdr) => hdr.totlen as u32,
+                NetHdr::V6(hdr) => 40 + hdr.plen as u32,
+            },
+            Field::IpId => v4?.ident as u32,
+            Field::IpOff => v4?.offset as u32,
+            Field::IpProto => u8::from(pkt.net.protocol()) as u32,
+            Field::IcmpType => pkt.icmp?.typ as u32,
+            Field::IcmpCode => pkt.icmp?.code as u32,
+            Field::Icmp6Type => pkt.icmpv6?.typ as u32,
+            Field::Icmp6Code => pkt.icmpv6?.code as u32,
+            Field::TcpSport => pkt.tcp?.sport as u32,
+            Field::TcpDport => pkt.tcp?.dport as u32,
+            Field::TcpFlags => pkt.tcp?.flag.bits() as u32,
+            Field::TcpWin => pkt.tcp?.window as u32,
+            Field::UdpSport => pkt.udp?.sport as u32,
+            Field::UdpDport => pkt.udp?.dport as u32,
+            Field::UdpLen => pkt.udp?.len as u32,
+        })
+    }
+}
+
+/// 按协议匹配
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proto {
+    Ip,
+    Ip6,
+    /// 网络层之上的协议
+    Upper(Protocol),
+}
+
+impl Display for Proto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Proto::Ip => write!(f, "ip"),
+            Proto::Ip6 => write!(f, "ip6"),
+            Proto::Upper(Protocol::TCP) => write!(f, "tcp"),
+            Proto::Upper(Protocol::UDP) => write!(f, "udp"),
+            Proto::Upper(Protocol::ICMP) => write!(f, "icmp"),
+            Proto::Upper(Protocol::ICMPv6) => write!(f, "icmp6"),
+            Proto::Upper(Protocol::Other(p)) => write!(f, "proto {p}"),
+        }
+    }
+}
+
+/// 表达式中不可再分的条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Primitive {
+    Host(Dir, IpAddr),
+    /// 网络地址及前缀长度
+    Net(Dir, IpAddr, u8),
+    /// TCP 或 UDP 端口
+    Port(Dir, u16),
+    Ether(Dir, [u8; 6]),
+    /// 带有此 VLAN ID 标签（任意一层）的帧，`None` 表示带有任意 VLAN 标签
+    Vlan(Option<u16>),
+    Proto(Proto),
+    /// 字段与掩码按位与之后同常量比较
+    Cmp {
+        field: Field,
+        mask: Option<u32>,
+        op: CmpOp,
+        value: u32,
+    },
+}
+
+impl Primitive {
+    fn matches(&self, pkt: &Packet) -> bool {
+        let either = |dir: Dir, src: bool, dst: bool| match dir {
+            Dir::Src => src,
+            Dir::Dst => dst,
+            Dir::Any => src || dst,
+        };
+        match *self {
+            Primitive::Host(dir, addr) => {
+                either(dir, pkt.net.source() == addr, pkt.net.destination() == addr)
+            }
+            Primitive::Net(dir, net, len) => either(
+                dir,
+                in_net(pkt.net.source(), net, len),
+                in_net(pkt.net.destination(), net, len),
+            ),
+            Primitive::Port(dir, port) => {
+                let ports = match (pkt.tcp, pkt.udp) {
+                    (Some(tcp), _) => (tcp.sport, tcp.dport),
+                    (_, Some(udp)) => (udp.sport, udp.dport),
+                    _ => return false,
+                };
+                either(dir, ports.0 == port, ports.1 == port)
+            }
+            Primitive::Ether(dir, mac) => {
+                either(dir, pkt.ether.shost == mac, pkt.ether.dhost == mac)
+            }
+            Primitive::Vlan(None) => !pkt.ether.vlans.is_empty(),
+            Primitive::Vlan(Some(vid)) => pkt.ether.vlans.iter().any(|tag| tag.vid == vid),
+            Primitive::Proto(Proto::Ip) => matches!(pkt.net, NetHdr::V4(_)),
+            Primitive::Proto(Proto::Ip6) => matches!(pkt.net, NetHdr::V6(_)),
+            Primitive::Proto(Proto::Upper(protocol)) => pkt.net.protocol() == protocol,
+            Primitive::Cmp {
+                field,
+                mask,
+                op,
+                value,
+            } => field
+                .value(pkt)
+                .is_some_and(|v| op.apply(v & mask.unwrap_or(u32::MAX), value)),
+        }
+    }
+}
+
+/// 地址 `addr` 是否属于前缀为 `len` 的网络 `net`
+fn in_net(addr: IpAddr, net: IpAddr, len: u8) -> bool {
+    match (addr, net) {
+        (IpAddr::V4(addr), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+            u32::from(addr) & mask == u32::from(net)
+        }
+        (IpAddr::V6(addr), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+            u128::from(addr) & mask == u128::from(net)
+        }
+        _ => false,
+    }
+}
+
+fn mac_str(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|c| format!("{:02x}", c))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Primitive::Host(dir, addr) => write!(f, "{dir}host {addr}"),
+            Primitive::Net(dir, net, len) => write!(f, "{dir}net {net}/{len}"),
+            Primitive::Port(dir, port) => write!(f, "{dir}port {port}"),
+            Primitive::Ether(dir, mac) => write!(f, "ether {dir}host {}", mac_str(mac)),
+            Primitive::Vlan(None) => write!(f, "vlan"),
+            Primitive::Vlan(Some(vid)) => write!(f, "vlan {vid}"),
+            Primitive::Proto(proto) => write!(f, "{proto}"),
+            Primitive::Cmp {
+                field,
+                mask,
+                op,
+                value,
+            } => {
+                write!(f, "{}", field.name())?;
+                if let Some(mask) = mask {
+                    write!(f, " & {mask:#x}")?;
+                }
+                write!(f, " {op} {value}")
+            }
+        }
+    }
+}
+
+/// 过滤表达式的语法树
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Prim(Primitive),
+}
+
+impl Expr {
+    /// 解析过滤表达式
+    pub fn parse(input: &str) -> Result<Self, FilterError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser {
+            input,
+            tokens,
+            pos: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some((Token::RParen, offset)) => Err(parser.error(offset, msg!("expr.extra_paren"))),
+            Some((token, offset)) => {
+                Err(parser.error(offset, msg!("expr.expect_op", token = token)))
+            }
+        }
+    }
+
+    pub fn and(self, other: Expr) -> Self {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Self {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+
+    /// 报文是否满足表达式
+    pub fn matches(&self, pkt: &Packet) -> bool {
+        match self {
+            Expr::And(lhs, rhs) => lhs.matches(pkt) && rhs.matches(pkt),
+            Expr::Or(lhs, rhs) => lhs.matches(pkt) || rhs.matches(pkt),
+            Expr::Not(expr) => !expr.matches(pkt),
+            Expr::Prim(prim) => prim.matches(pkt),
+        }
+    }
+}
+
+impl From<Primitive> for Expr {
+    fn from(prim: Primitive) -> Self {
+        Expr::Prim(prim)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Or(lhs, rhs) => write!(f, "{lhs} or {rhs}"),
+            Expr::And(lhs, rhs) => {
+                for (idx, side) in [lhs, rhs].into_iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, " and ")?;
+                    }
+                    match **side {
+                        Expr::Or(..) => write!(f, "({side})")?,
+                        _ => write!(f, "{side}")?,
+                    }
+                }
+                Ok(())
+            }
+            Expr::Not(expr) => match **expr {
+                Expr::And(..) | Expr::Or(..) => write!(f, "not ({expr})"),
+                _ => write!(f, "not {expr}"),
+            },
+            Expr::Prim(prim) => write!(f, "{prim}"),
+        }
+    }
+}
+
+/// 求值时使用的已解码报文
+#[derive(Debug, Clone, Copy)]
+pub struct Packet<'a> {
+    pub ether: &'a EtherHdr,
+    pub net: &'a NetHdr,
+    pub tcp: Option<&'a TcpHdr>,
+    pub udp: Option<&'a UdpHdr>,
+    pub icmp: Option<&'a ICMP>,
+    pub icmpv6: Option<&'a Icmpv6>,
+}
+
+impl<'a> Packet<'a> {
+    /// 只有链路层与网络层首部的报文
+    pub fn new(ether: &'a EtherHdr, net: &'a NetHdr) -> Self {
+        Packet {
+            ether,
+            net,
+            tcp: None,
+            udp: None,
+            icmp: None,
+            icmpv6: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Amp,
+    Cmp(CmpOp),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "`{word}`"),
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::And => write!(f, "`and`"),
+            Token::Or => write!(f, "`or`"),
+            Token::Not => write!(f, "`not`"),
+            Token::Amp => write!(f, "`&`"),
+            Token::Cmp(op) => write!(f, "`{op}`"),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '/' | '_')
+}
+
+/// 将输入切分为词法单元及其字符偏移
+fn lex(input: &str) -> Result<Vec<(Token, usize)>, FilterError> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let start = idx;
+        let next = chars.get(idx + 1).copied();
+        let (token, len) = match (chars[idx], next) {
+            (c, _) if c.is_whitespace() => {
+                idx += 1;
+                continue;
+            }
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('&', Some('&')) => (Token::And, 2),
+            ('&', _) => (Token::Amp, 1),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('!', Some('=')) => (Token::Cmp(CmpOp::Ne), 2),
+            ('!', _) => (Token::Not, 1),
+            ('=', Some('=')) => (Token::Cmp(CmpOp::Eq), 2),
+            ('=', _) => (Token::Cmp(CmpOp::Eq), 1),
+            ('<', Some('=')) => (Token::Cmp(CmpOp::Le), 2),
+            ('<', _) => (Token::Cmp(CmpOp::Lt), 1),
+            ('>', Some('=')) => (Token::Cmp(CmpOp::Ge), 2),
+            ('>', _) => (Token::Cmp(CmpOp::Gt), 1),
+            (c, _) if is_word_char(c) => {
+                let len = chars[idx..]
+                    .iter()
+                    .take_while(|&&c| is_word_char(c))
+                    .count();
+                let word = chars[idx..idx + len].iter().collect::<String>();
+                let token = match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                (token, len)
+            }
+            (c, _) => {
+                return Err(FilterError {
+                    input: input.to_string(),
+                    offset: start,
+                    reason: msg!("expr.bad_char", c = c.escape_debug()),
+                })
+            }
+        };
+        tokens.push((token, start));
+        idx += len;
+    }
+    Ok(tokens)
+}
+
+/// 递归下降解析器
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, offset: usize, reason: impl Into<String>) -> FilterError {
+        FilterError {
+            input: self.input.to_string(),
+            offset,
+            reason: reason.into(),
+        }
+    }
+
+    /// 输入末尾的偏移，用于表达式不完整的错误
+    fn end(&self) -> usize {
+        self.input.chars().count()
+    }
+
+    fn peek(&self) -> Option<(Token, usize)> {
+        self.tokens.get(self.pos).cloned()
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Word(word), _)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn bump(&mut self) -> Option<(Token, usize)> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    /// 下一个单元应为单词，`what` 描述期望的内容
+    fn word(&mut self, what: &str) -> Result<(String, usize), FilterError> {
+        match self.bump() {
+            Some((Token::Word(word), offset)) => Ok((word, offset)),
+            Some((token, offset)) => {
+                Err(self.error(offset, msg!("expr.expect", what = what, token = token)))
+            }
+            None => Err(self.error(self.end(), msg!("expr.missing", what = what))),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.and()?;
+        while let Some((Token::Or, _)) = self.peek() {
+            self.pos += 1;
+            lhs = lhs.or(self.and()?);
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut lhs = self.unary()?;
+        while let Some((Token::And, _)) = self.peek() {
+            self.pos += 1;
+            lhs = lhs.and(self.unary()?);
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        match self.peek() {
+            Some((Token::Not, _)) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some((Token::LParen, open)) => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                match self.bump() {
+                    Some((Token::RParen, _)) => Ok(expr),
+                    Some((token, offset)) => Err(self.error(
+                        offset,
+                        msg!("expr.expect_rparen", token = token, open = open + 1),
+                    )),
+                    None => Err(self.error(open, msg!("expr.unclosed"))),
+                }
+            }
+            Some((Token::Word(_), _)) => self.primitive().map(Expr::Prim),
+            Some((token, offset)) => Err(self.error(
+                offset,
+                msg!(
+                    "expr.expect",
+                    what = msg!("expr.what.primitive"),
+                    token = token
+                ),
+            )),
+            None => Err(self.error(
+                self.end(),
+                msg!("expr.missing", what = msg!("expr.what.primitive")),
+            )),
+        }
+    }
+
+    fn primitive(&mut self) -> Result<Primitive, FilterError> {
+        let (word, offset) = self.word(msg!("expr.what.primitive"))?;
+        let dir = match word.as_str() {
+            "src" => Some(Dir::Src),
+            "dst" => Some(Dir::Dst),
+            _ => None,
+        };
+        if let Some(dir) = dir {
+            let (word, offset) = self.word(msg!("expr.what.after_dir"))?;
+            return match word.as_str() {
+                "host" => self.host(dir),
+                "net" => self.net(dir),
+                "port" => self.port(dir),
+                // `src ADDR` 是 `src host ADDR` 的简写
+                _ => self
+                    .addr(&word, offset)
+                    .map(|addr| Primitive::Host(dir, addr)),
+            };
+        }
+        match word.as_str() {
+            "host" => self.host(Dir::Any),
+            "net" => self.net(Dir::Any),
+            "port" => self.port(Dir::Any),
+            "ether" => self.ether(),
+            "vlan" => Ok(Primitive::Vlan(self.vlan()?)),
+            "proto" => {
+                let (word, offset) = self.word(msg!("expr.what.protocol"))?;
+                let proto = proto_name(&word)
+                    .or_else(|| {
+                        number(&word)
+                            .and_then(|p| u8::try_from(p).ok())
+                            .map(|p| Proto::Upper(Protocol::from(p)))
+                    })
+                    .ok_or_else(|| {
+                        self.error(offset, msg!("expr.unknown_protocol", word = word))
+                    })?;
+                Ok(Primitive::Proto(proto))
+            }
+            _ => {
+                if let Some(proto) = proto_name(&word) {
+                    return Ok(Primitive::Proto(proto));
+                }
+                match Field::from_name(&word) {
+                    Some(field) => self.compare(field),
+                    None if matches!(self.peek(), Some((Token::Cmp(_) | Token::Amp, _)))
+                        || word.contains('.') =>
+                    {
+                        let names = Field::ALL.map(|(name, _)| name).join("、");
+                        Err(self.error(
+                            offset,
+                            msg!("expr.unknown_field", word = word, names = names),
+                        ))
+                    }
+                    None => Err(self.error(offset, msg!("expr.unknown_primitive", word = word))),
+                }
+            }
+        }
+    }
+
+    fn addr(&self, word: &str, offset: usize) -> Result<IpAddr, FilterError> {
+        word.parse::<IpAddr>()
+            .map_err(|_| self.error(offset, msg!("expr.bad_addr", word = word)))
+    }
+
+    fn host(&mut self, dir: Dir) -> Result<Primitive, FilterError> {
+        let (word, offset) = self.word(msg!("expr.what.addr"))?;
+        Ok(Primitive::Host(dir, self.addr(&word, offset)?))
+    }
+
+    fn net(&mut self, dir: Dir) -> Result<Primitive, FilterError> {
+        let (word, offset) = self.word(msg!("expr.what.net"))?;
+        let (addr, len) = match word.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (word.as_str(), None),
+        };
+        let net = self.addr(addr, offset)?;
+        let max = match net {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let len = match len {
+            Some(len) => len
+                .parse::<u8>()
+                .ok()
+                .filter(|&len| len <= max)
+                .ok_or_else(|| {
+                    self.error(
+                        offset + addr.chars().count() + 1,
+                        msg!("expr.bad_prefix", len = len, max = max),
+                    )
+                })?,
+            None => max,
+        };
+        if !in_net(net, net, len) {
+            return Err(self.error(offset, msg!("expr.host_bits", word = word)));
+        }
+        Ok(Primitive::Net(dir, net, len))
+    }
+
+    fn port(&mut self, dir: Dir) -> Result<Primitive, FilterError> {
+        let (word, offset) = self.word(msg!("expr.what.port"))?;
+        let port = number(&word)
+            .and_then(|port| u16::try_from(port).ok())
+            .ok_or_else(|| self.error(offset, msg!("expr.bad_port", word = word)))?;
+        Ok(Primitive::Port(dir, port))
+    }
+
+    fn ether(&mut self) -> Result<Primitive, FilterError> {
+        let dir = match self.peek_word() {
+            Some("src") => Dir::Src,
+            Some("dst") => Dir::Dst,
+            _ => Dir::Any,
+        };
+        if dir != Dir::Any {
+            self.pos += 1;
+        }
+        if self.peek_word() == Some("host") {
+            self.pos += 1;
+        }
+        let (word, offset) = self.word(msg!("expr.what.mac"))?;
+        let mac =
+            mac(&word).ok_or_else(|| self.error(offset, msg!("expr.bad_mac", word = word)))?;
+        Ok(Primitive::Ether(dir, mac))
+    }
+
+    /// `vlan` 之后可选的 VLAN ID
+    fn vlan(&mut self) -> Result<Option<u16>, FilterError> {
+        let Some(word) = self.peek_word() else {
+            return Ok(None);
+        };
+        if !word.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let (word, offset) = self.word("VLAN ID")?;
+        number(&word)
+            .and_then(|vid| u16::try_from(vid).ok())
+            .filter(|&vid| vid < 4096)
+            .map(Some)
+            .ok_or_else(|| self.error(offset, msg!("error.vlan_id", vid = word)))
+    }
+
+    /// 字段名之后的 `[& MASK] OP VALUE`
+    fn compare(&mut self, field: Field) -> Result<Primitive, FilterError> {
+        let mask = match self.peek() {
+            Some((Token::Amp, _)) => {
+                self.pos += 1;
+                let (word, offset) = self.word(msg!("expr.what.mask"))?;
+                Some(
+                    number(&word)
+                        .ok_or_else(|| self.error(offset, msg!("expr.bad_mask", word = word)))?,
+                )
+            }
+            _ => None,
+        };
+        let op = match self.bump() {
+            Some((Token::Cmp(op), _)) => op,
+            Some((token, offset)) => {
+                return Err(self.error(
+                    offset,
+                    msg!("expr.expect_cmp", field = field.name(), token = token),
+                ))
+            }
+            None => {
+                return Err(self.error(self.end(), msg!("expr.missing_cmp", field = field.name())))
+            }
+        };
+        let (word, offset) = self.word(msg!("expr.what.integer"))?;
+        let value = number(&word)
+            .ok_or_else(|| self.error(offset, msg!("expr.bad_integer", word = word)))?;
+        Ok(Primitive::Cmp {
+            field,
+            mask,
+            op,
+            value,
+        })
+    }
+}
+
+fn proto_name(name: &str) -> Option<Proto> {
+    Some(match name {
+        "ip" => Proto::Ip,
+        "ip6" => Proto::Ip6,
+        "tcp" => Proto::Upper(Protocol::TCP),
+        "udp" => Proto::Upper(Protocol::UDP),
+        "icmp" => Proto::Upper(Protocol::ICMP),
+        "icmp6" => Proto::Upper(Protocol::ICMPv6),
+        _ => return None,
+    })
+}
+
+/// 十进制或以 `0x` 开头的十六进制整数
+fn number(word: &str) -> Option<u32> {
+    match word.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => word.parse().ok(),
+    }
+}
+
+/// 以 `:` 分隔的 MAC 地址
+fn mac(word: &str) -> Option<[u8; 6]> {
+    let bytes = word
+        .split(':')
+        .map(|b| u8::from_str_radix(b, 16).ok().filter(|_| b.len() <= 2))
+        .collect::<Option<Vec<_>>>()?;
+    bytes.try_into().ok()
+}
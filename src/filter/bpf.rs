@@ -0,0 +1,636 @@
+//! 将过滤表达式编译为经典 BPF 程序，在内核中丢弃不匹配的帧。
+//!
+//! BPF 无法精确判断的情况（帧中仍带有 VLAN 标签、802.3 帧、IPv6 扩展首部、IP 分片的传输层字段）
+//! 记为“不确定”：按所在位置的极性放行或丢弃，使程序接受的帧总是用户态求值结果的超集，
+//! 再由用户态求值得出最终结果。
+
+#[cfg(test)]
+mod tests;
+
+use std::{fmt::Display, net::IpAddr};
+
+use libc::{
+    sock_filter, BPF_ABS, BPF_ADD, BPF_ALU, BPF_AND, BPF_B, BPF_H, BPF_IND, BPF_JA, BPF_JEQ,
+    BPF_JGE, BPF_JGT, BPF_JMP, BPF_JSET, BPF_K, BPF_LD, BPF_LDX, BPF_MSH, BPF_RET, BPF_RSH, BPF_W,
+    SKF_AD_OFF, SKF_AD_VLAN_TAG, SKF_AD_VLAN_TAG_PRESENT,
+};
+
+use crate::head::{NH_DEST_OPTS, NH_FRAGMENT, NH_HOP_BY_HOP, NH_ROUTING, TPID_CTAG, TPID_STAG};
+
+use super::{CmpOp, Dir, Expr, Field, Primitive, Proto};
+
+/// 接受时保留的字节数，与 tcpdump 相同
+const ACCEPT_LEN: u32 = 262144;
+/// 网络层首部在帧中的偏移
+const NET: u32 = 14;
+/// IPv6 固定首部之后的上层数据在帧中的偏移
+const UPPER6: u32 = NET + 40;
+
+const ETHERTYPE_IP: u32 = 0x0800;
+const ETHERTYPE_IPV6: u32 = 0x86DD;
+/// 小于等于此值的类型字段是 802.3 帧的长度
+const ETHER_MAX_LEN: u32 = 0x05DC;
+
+/// 跳转目标，放置后才知道位置
+type Label = usize;
+
+/// 尚未确定跳转偏移的指令
+enum Op {
+    Stmt(u32, u32),
+    Jump {
+        code: u32,
+        k: u32,
+        jt: Label,
+        jf: Label,
+    },
+    Ja(Label),
+}
+
+/// 字段所在的位置
+#[derive(Clone, Copy)]
+enum At {
+    /// 距网络层首部的偏移
+    Net(u32),
+    /// 距传输层首部的偏移，只在上层协议为其中之一且不是分片时存在
+    Upper(&'static [u8], u32),
+    /// 上层协议号，IPv6 中遇到扩展首部时不确定
+    Proto,
+}
+
+/// 如何取得一个字段的值：位置、宽度以及取出后依次进行的运算
+#[derive(Clone, Copy)]
+struct Load {
+    at: At,
+    size: u32,
+    ops: &'static [(u32, u32)],
+}
+
+impl Load {
+    const fn new(at: At, size: u32) -> Self {
+        Load { at, size, ops: &[] }
+    }
+
+    const fn ops(self, ops: &'static [(u32, u32)]) -> Self {
+        Load { ops, ..self }
+    }
+}
+
+const TCP: &[u8] = &[6];
+const UDP: &[u8] = &[17];
+const TCP_UDP: &[u8] = &[6, 17];
+const ICMP: &[u8] = &[1];
+const ICMPV6: &[u8] = &[58];
+
+/// 字段在 IPv4 与 IPv6 报文中的取法，报文没有该字段时为 `None`
+fn field_loads(field: Field) -> (Option<Load>, Option<Load>) {
+    let upper = |protos, off, size| Some(Load::new(At::Upper(protos, off), size));
+    match field {
+        Field::Ttl => (
+            Some(Load::new(At::Net(8), BPF_B)),
+            Some(Load::new(At::Net(7), BPF_B)),
+        ),
+        Field::Tos => (
+            Some(Load::new(At::Net(1), BPF_B)),
+            // 流量类别跨越前两个字节的中间 8 位
+            Some(Load::new(At::Net(0), BPF_H).ops(&[(BPF_RSH, 4), (BPF_AND, 0xff)])),
+        ),
+        Field::Len => (
+            Some(Load::new(At::Net(2), BPF_H)),
+            Some(Load::new(At::Net(4), BPF_H).ops(&[(BPF_ADD, 40)])),
+        ),
+        Field::IpId => (Some(Load::new(At::Net(4), BPF_H)), None),
+        Field::IpOff => (
+            Some(Load::new(At::Net(6), BPF_H).ops(&[(BPF_AND, 0x1fff)])),
+            None,
+        ),
+        Field::IpProto => (
+            Some(Load::new(At::Proto, BPF_B)),
+            Some(Load::new(At::Proto, BPF_B)),
+        ),
+        Field::IcmpType => (upper(ICMP, 0, BPF_B), None),
+        Field::IcmpCode => (upper(ICMP, 1, BPF_B), None),
+        Field::Icmp6Type => (None, upper(ICMPV6, 0, BPF_B)),
+        Field::Icmp6Code => (None, upper(ICMPV6, 1, BPF_B)),
+        Field::TcpSport => (upper(TCP, 0, BPF_H), upper(TCP, 0, BPF_H)),
+        Field::TcpDport => (upper(TCP, 2, BPF_H), upper(TCP, 2, BPF_H)),
+        Field::TcpFlags => {
+            let load = Load::new(At::Upper(TCP, 12), BPF_H).ops(&[(BPF_AND, 0x1ff)]);
+            (Some(load), Some(load))
+        }
+        Field::TcpWin => (upper(TCP, 14, BPF_H), upper(TCP, 14, BPF_H)),
+        Field::UdpSport => (upper(UDP, 0, BPF_H), upper(UDP, 0, BPF_H)),
+        Field::UdpDport => (upper(UDP, 2, BPF_H), upper(UDP, 2, BPF_H)),
+        Field::UdpLen => (upper(UDP, 4, BPF_H), upper(UDP, 4, BPF_H)),
+    }
+}
+
+/// 一次比较：在帧中 `off` 处取 `size` 宽的值，与掩码按位与后应等于 `value`
+#[derive(Clone, Copy)]
+struct Word {
+    size: u32,
+    off: u32,
+    value: u32,
+    mask: u32,
+}
+
+/// 地址 `addr` 的前 `len` 位按 32 位字拆分，`off` 为地址在帧中的偏移
+fn addr_words(addr: IpAddr, len: u8, off: u32) -> Vec<Word> {
+    let bytes = match addr {
+        IpAddr::V4(addr) => addr.octets().to_vec(),
+        IpAddr::V6(addr) => addr.octets().to_vec(),
+    };
+    bytes
+        .chunks(4)
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let bits = (len as u32).saturating_sub(idx as u32 * 32).min(32);
+            Word {
+                size: BPF_W,
+                off: off + idx as u32 * 4,
+                value: u32::from_be_bytes(chunk.try_into().unwrap()),
+                mask: u32::MAX.checked_shl(32 - bits).unwrap_or(0),
+            }
+        })
+        .filter(|word| word.mask != 0)
+        .collect()
+}
+
+/// 跳转目标：真、假，以及无法判断时的去向
+#[derive(Clone, Copy)]
+struct Targets {
+    t: Label,
+    f: Label,
+    unknown: Label,
+}
+
+#[derive(Default)]
+struct Compiler {
+    ops: Vec<Op>,
+    /// 各标号放置的位置
+    labels: Vec<Option<usize>>,
+}
+
+impl Compiler {
+    fn label(&mut self) -> Label {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: Label) {
+        self.labels[label] = Some(self.ops.len());
+    }
+
+    fn stmt(&mut self, code: u32, k: u32) {
+        self.ops.push(Op::Stmt(code, k));
+    }
+
+    fn jump(&mut self, code: u32, k: u32, jt: Label, jf: Label) {
+        self.ops.push(Op::Jump { code, k, jt, jf });
+    }
+
+    /// 紧接着的下一条指令
+    fn next(&mut self) -> Label {
+        let label = self.label();
+        self.labels[label] = Some(self.ops.len() + 1);
+        label
+    }
+
+    fn goto(&mut self, label: Label) {
+        self.ops.push(Op::Ja(label));
+    }
+
+    fn load(&mut self, size: u32, off: u32) {
+        self.stmt(BPF_LD | size | BPF_ABS, off);
+    }
+
+    fn expr(&mut self, expr: &Expr, upper: bool, t: Label, f: Label) {
+        match expr {
+            Expr::And(lhs, rhs) => {
+                let mid = self.label();
+                self.expr(lhs, upper, mid, f);
+                self.place(mid);
+                self.expr(rhs, upper, t, f);
+            }
+            Expr::Or(lhs, rhs) => {
+                let mid = self.label();
+                self.expr(lhs, upper, t, mid);
+                self.place(mid);
+                self.expr(rhs, upper, t, f);
+            }
+            Expr::Not(expr) => self.expr(expr, !upper, f, t),
+            Expr::Prim(prim) => {
+                let unknown = if upper { t } else { f };
+                self.primitive(prim, Targets { t, f, unknown });
+            }
+        }
+    }
+
+    /// 按类型字段分别处理 IPv4 与 IPv6，其他类型为假，无法判断网络层的帧为不确定
+    fn dispatch(&mut self, tg: Targets, v4: impl FnOnce(&mut Self), v6: impl FnOnce(&mut Self)) {
+        let (l4, l6) = (self.label(), self.label());
+        self.load(BPF_H, 12);
+        let next = self.next();
+        self.jump(BPF_JMP | BPF_JEQ | BPF_K, ETHERTYPE_IP, l4, next);
+        let next = self.next();
+        self.jump(BPF_JMP | BPF_JEQ | BPF_K, ETHERTYPE_IPV6, l6, next);
+        // 未被内核剥离的 VLAN 标签使网络层的偏移不固定
+        for tpid in [TPID_CTAG, TPID_STAG] {
+            let next = self.next();
+            self.jump(BPF_JMP | BPF_JEQ | BPF_K, tpid as u32, tg.unknown, next);
+        }
+        self.jump(BPF_JMP | BPF_JGT | BPF_K, ETHER_MAX_LEN, tg.f, tg.unknown);
+        self.place(l4);
+        v4(self);
+        self.place(l6);
+        v6(self);
+    }
+
+    /// 检查上层协议为 `protos` 之一。IPv4 分片不能判断传输层字段，之后 X 为 IPv4 首部长度
+    fn upper4(&mut self, protos: &[u8], tg: Targets) {
+        let ok = self.label();
+        self.load(BPF_B, NET + 9);
+        for &proto in protos {
+            let next = self.next();
+            self.jump(BPF_JMP | BPF_JEQ | BPF_K, proto as u32, ok, next);
+        }
+        self.goto(tg.f);
+        self.place(ok);
+        self.load(BPF_H, NET + 6);
+        let next = self.next();
+        self.jump(BPF_JMP | BPF_JSET | BPF_K, 0x3fff, tg.unknown, next);
+        self.stmt(BPF_LDX | BPF_B | BPF_MSH, NET);
+    }
+
+    /// 检查 IPv6 的下一首部为 `protos` 之一，遇到扩展首部时不确定
+    fn upper6(&mut self, protos: &[u8], tg: Targets) {
+        let ok = self.label();
+        self.load(BPF_B, NET + 6);
+        for &proto in protos {
+            let next = self.next();
+            self.jump(BPF_JMP | BPF_JEQ | BPF_K, proto as u32, ok, next);
+        }
+        self.ext6(tg);
+        self.goto(tg.f);
+        self.place(ok);
+    }
+
+    /// A 中的下一首部为扩展首部时不确定
+    fn ext6(&mut self, tg: Targets) {
+        for ext in [NH_HOP_BY_HOP, NH_ROUTING, NH_FRAGMENT, NH_DEST_OPTS] {
+            let next = self.next();
+            self.jump(BPF_JMP | BPF_JEQ | BPF_K, ext as u32, tg.unknown, next);
+        }
+    }
+
+    /// 依次比较 `words`，全部相等时转到 `t`
+    fn words(&mut self, words: &[Word], t: Label, f: Label) {
+        for (idx, word) in words.iter().enumerate() {
+            self.load(word.size, word.off);
+            let full = match word.size {
+                BPF_W => u32::MAX,
+                BPF_H => 0xffff,
+                _ => 0xff,
+            };
+            if word.mask != full {
+                self.stmt(BPF_ALU | BPF_AND | BPF_K, word.mask);
+            }
+            let jt = match idx + 1 == words.len() {
+                true => t,
+                false => self.next(),
+            };
+            self.jump(BPF_JMP | BPF_JEQ | BPF_K, word.value & word.mask, jt, f);
+        }
+    }
+
+    /// 按方向比较源、目的两组 `words`
+    fn either(&mut self, dir: Dir, src: &[Word], dst: &[Word], t: Label, f: Label) {
+        match dir {
+            Dir::Src => self.words(src, t, f),
+            Dir::Dst => self.words(dst, t, f),
+            Dir::Any => {
+                let next = self.label();
+                self.words(src, t, next);
+                self.place(next);
+                self.words(dst, t, f);
+            }
+        }
+    }
+
+    /// 取出字段的值放在 A 中。字段不存在时为假
+    fn field(&mut self, load: Option<Load>, v6: bool, tg: Targets) {
+        let Some(load) = load else {
+            self.goto(tg.f);
+            return;
+        };
+        match (load.at, v6) {
+            (At::Net(off), _) => self.load(load.size, NET + off),
+            (At::Upper(protos, off), false) => {
+                self.upper4(protos, tg);
+                self.stmt(BPF_LD | load.size | BPF_IND, NET + off);
+            }
+            (At::Upper(protos, off), true) => {
+                self.upper6(protos, tg);
+                self.load(load.size, UPPER6 + off);
+            }
+            (At::Proto, false) => self.load(BPF_B, NET + 9),
+            (At::Proto, true) => {
+                self.load(BPF_B, NET + 6);
+                self.ext6(tg);
+            }
+        }
+        for &(op, k) in load.ops {
+            self.stmt(BPF_ALU | op | BPF_K, k);
+        }
+    }
+
+    /// A 与掩码按位与后同 `value` 比较
+    fn compare(&mut self, mask: Option<u32>, op: CmpOp, value: u32, tg: Targets) {
+        if let Some(mask) = mask {
+            self.stmt(BPF_ALU | BPF_AND | BPF_K, mask);
+        }
+        let (code, jt, jf) = match op {
+            CmpOp::Eq => (BPF_JEQ, tg.t, tg.f),
+            CmpOp::Ne => (BPF_JEQ, tg.f, tg.t),
+            CmpOp::Gt => (BPF_JGT, tg.t, tg.f),
+            CmpOp::Ge => (BPF_JGE, tg.t, tg.f),
+            CmpOp::Lt => (BPF_JGE, tg.f, tg.t),
+            CmpOp::Le => (BPF_JGT, tg.f, tg.t),
+        };
+        self.jump(BPF_JMP | code | BPF_K, value, jt, jf);
+    }
+
+    fn primitive(&mut self, prim: &Primitive, tg: Targets) {
+        match *prim {
+            Primitive::Host(dir, addr) => self.net(dir, addr, 128, tg),
+            Primitive::Net(dir, addr, len) => self.net(dir, addr, len, tg),
+            Primitive::Port(dir, port) => {
+                let word = |off| Word {
+                    size: BPF_H,
+                    off,
+                    value: port as u32,
+                    mask: 0xffff,
+                };
+                self.dispatch(
+                    tg,
+                    |c| {
+                        c.upper4(TCP_UDP, tg);
+                        // 端口按 X 间接寻址
+                        c.either_ind(dir, port, tg);
+                    },
+                    |c| {
+                        c.upper6(TCP_UDP, tg);
+                        c.either(dir, &[word(UPPER6)], &[word(UPPER6 + 2)], tg.t, tg.f);
+                    },
+                );
+            }
+            Primitive::Ether(dir, mac) => {
+                let words = |off| {
+                    [
+                        Word {
+                            size: BPF_H,
+                            off,
+                            value: u16::from_be_bytes([mac[0], mac[1]]) as u32,
+                            mask: 0xffff,
+                        },
+                        Word {
+                            size: BPF_W,
+                            off: off + 2,
+                            value: u32::from_be_bytes([mac[2], mac[3], mac[4], mac[5]]),
+                            mask: u32::MAX,
+                        },
+                    ]
+                };
+                self.either(dir, &words(6), &words(0), tg.t, tg.f);
+            }
+            Primitive::Vlan(vid) => {
+                let in_frame = self.label();
+                self.load(BPF_W, (SKF_AD_OFF + SKF_AD_VLAN_TAG_PRESENT) as u32);
+                match vid {
+                    Some(vid) => {
+                        let next = self.next();
+                        self.jump(BPF_JMP | BPF_JEQ | BPF_K, 0, in_frame, next);
+                        self.load(BPF_W, (SKF_AD_OFF + SKF_AD_VLAN_TAG) as u32);
+                        self.stmt(BPF_ALU | BPF_AND | BPF_K, 0xfff);
+                        self.jump(BPF_JMP | BPF_JEQ | BPF_K, vid as u32, tg.t, in_frame);
+                    }
+                    None => self.jump(BPF_JMP | BPF_JEQ | BPF_K, 0, in_frame, tg.t),
+                }
+                // 内层标签仍留在帧中，无法确定其中是否有该 VLAN ID
+                self.place(in_frame);
+                let found = match vid {
+                    Some(_) => tg.unknown,
+                    None => tg.t,
+                };
+                self.load(BPF_H, 12);
+                let next = self.next();
+                self.jump(BPF_JMP | BPF_JEQ | BPF_K, TPID_CTAG as u32, found, next);
+                self.jump(BPF_JMP | BPF_JEQ | BPF_K, TPID_STAG as u32, found, tg.f);
+            }
+            Primitive::Proto(Proto::Ip) => self.dispatch(tg, |c| c.goto(tg.t), |c| c.goto(tg.f)),
+            Primitive::Proto(Proto::Ip6) => self.dispatch(tg, |c| c.goto(tg.f), |c| c.goto(tg.t)),
+            Primitive::Proto(Proto::Upper(protocol)) => {
+                let proto = u8::from(protocol) as u32;
+                self.dispatch(
+                    tg,
+                    |c| {
+                        c.load(BPF_B, NET + 9);
+                        c.jump(BPF_JMP | BPF_JEQ | BPF_K, proto, tg.t, tg.f);
+                    },
+                    |c| {
+                        c.load(BPF_B, NET + 6);
+                        let next = c.next();
+                        c.jump(BPF_JMP | BPF_JEQ | BPF_K, proto, tg.t, next);
+                        c.ext6(tg);
+                        c.goto(tg.f);
+                    },
+                )
+            }
+            Primitive::Cmp {
+                field,
+                mask,
+                op,
+                value,
+            } => {
+                let (v4, v6) = field_loads(field);
+                self.dispatch(
+                    tg,
+                    |c| {
+                        c.field(v4, false, tg);
+                        if v4.is_some() {
+                            c.compare(mask, op, value, tg);
+                        }
+                    },
+                    |c| {
+                        c.field(v6, true, tg);
+                        if v6.is_some() {
+                            c.compare(mask, op, value, tg);
+                        }
+                    },
+                );
+            }
+        }
+    }
+
+    /// 地址 `addr` 的前 `len` 位与源或目的地址比较
+    fn net(&mut self, dir: Dir, addr: IpAddr, len: u8, tg: Targets) {
+        match addr {
+            IpAddr::V4(_) => self.dispatch(
+                tg,
+                |c| {
+                    let src = addr_words(addr, len, NET + 12);
+                    let dst = addr_words(addr, len, NET + 16);
+                    c.either(dir, &src, &dst, tg.t, tg.f);
+                },
+                |c| c.goto(tg.f),
+            ),
+            IpAddr::V6(_) => self.dispatch(
+                tg,
+                |c| c.goto(tg.f),
+                |c| {
+                    let src = addr_words(addr, len, NET + 8);
+                    let dst = addr_words(addr, len, NET + 24);
+                    c.either(dir, &src, &dst, tg.t, tg.f);
+                },
+            ),
+        }
+    }
+
+    /// IPv4 中按 X 间接取源、目的端口比较
+    fn either_ind(&mut self, dir: Dir, port: u16, tg: Targets) {
+        let offs = match dir {
+            Dir::Src => &[0][..],
+            Dir::Dst => &[2],
+            Dir::Any => &[0, 2],
+        };
+        for (idx, off) in offs.iter().enumerate() {
+            self.stmt(BPF_LD | BPF_H | BPF_IND, NET + off);
+            let jf = match idx + 1 == offs.len() {
+                true => tg.f,
+                false => self.next(),
+            };
+            self.jump(BPF_JMP | BPF_JEQ | BPF_K, port as u32, tg.t, jf);
+        }
+    }
+
+    /// 确定各跳转的偏移。条件跳转只能向后跳 255 条指令，超出时返回 `None`
+    fn finish(self) -> Option<Program> {
+        let offset = |pc: usize, label: Label| {
+            let target = self.labels[label].expect("跳转到未放置的标号");
+            target.checked_sub(pc + 1)
+        };
+        let insns = self
+            .ops
+            .iter()
+            .enumerate()
+            .map(|(pc, op)| {
+                Some(match *op {
+                    Op::Stmt(code, k) => insn(code, k, 0, 0),
+                    Op::Jump { code, k, jt, jf } => {
+                        let jt = u8::try_from(offset(pc, jt)?).ok()?;
+                        let jf = u8::try_from(offset(pc, jf)?).ok()?;
+                        insn(code, k, jt, jf)
+                    }
+                    Op::Ja(label) => insn(BPF_JMP | BPF_JA, offset(pc, label)? as u32, 0, 0),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        (insns.len() <= libc::BPF_MAXINSNS as usize).then_some(Program(insns))
+    }
+}
+
+fn insn(code: u32, k: u32, jt: u8, jf: u8) -> sock_filter {
+    sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    }
+}
+
+/// 编译好的 BPF 程序
+pub struct Program(Vec<sock_filter>);
+
+impl Program {
+    pub fn insns(&self) -> &[sock_filter] {
+        &self.0
+    }
+}
+
+/// 将过滤表达式编译为 BPF 程序，没有表达式时接受所有帧。程序过长时返回 `None`
+pub fn compile(expr: Option<&Expr>) -> Option<Program> {
+    let mut c = Compiler::default();
+    let (accept, reject) = (c.label(), c.label());
+    if let Some(expr) = expr {
+        c.expr(expr, true, accept, reject);
+    }
+    c.place(accept);
+    c.stmt(BPF_RET | BPF_K, ACCEPT_LEN);
+    c.place(reject);
+    c.stmt(BPF_RET | BPF_K, 0);
+    c.finish()
+}
+
+/// 辅助数据的名称
+fn ancillary(k: u32) -> Option<&'static str> {
+    match (k as i32).checked_sub(SKF_AD_OFF)? {
+        SKF_AD_VLAN_TAG => Some("vlan_tci"),
+        SKF_AD_VLAN_TAG_PRESENT => Some("vlan_avail"),
+        _ => None,
+    }
+}
+
+/// 按 `tcpdump -d` 的格式输出
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (pc, insn) in self.0.iter().enumerate() {
+            let code = insn.code as u32;
+            let k = insn.k;
+            let size = match code & 0x18 {
+                BPF_H => "h",
+                BPF_B => "b",
+                _ => "",
+            };
+            let (op, operand) = match code & 0x07 {
+                BPF_LD => match (code & 0xe0, ancillary(k)) {
+                    (BPF_ABS, Some(name)) => (format!("ld{size}"), name.to_string()),
+                    (BPF_ABS, None) => (format!("ld{size}"), format!("[{k}]")),
+                    (BPF_IND, _) => (format!("ld{size}"), format!("[x + {k}]")),
+                    _ => ("ld".to_string(), format!("#{k:#x}")),
+                },
+                BPF_LDX => ("ldxb".to_string(), format!("4*([{k}]&0xf)")),
+                BPF_ALU => {
+                    let op = match code & 0xf0 {
+                        BPF_ADD => "add",
+                        BPF_AND => "and",
+                        BPF_RSH => "rsh",
+                        _ => "alu",
+                    };
+                    match code & 0xf0 {
+                        BPF_AND => (op.to_string(), format!("#{k:#x}")),
+                        _ => (op.to_string(), format!("#{k}")),
+                    }
+                }
+                BPF_JMP if code & 0xf0 == BPF_JA => {
+                    ("ja".to_string(), format!("{}", pc + 1 + k as usize))
+                }
+                BPF_JMP => {
+                    let op = match code & 0xf0 {
+                        BPF_JEQ => "jeq",
+                        BPF_JGT => "jgt",
+                        BPF_JGE => "jge",
+                        _ => "jset",
+                    };
+                    let jt = pc + 1 + insn.jt as usize;
+                    let jf = pc + 1 + insn.jf as usize;
+                    let operand = format!("#{k:#x}");
+                    writeln!(f, "({pc:03}) {op:<8} {operand:<16} jt {jt}\tjf {jf}")?;
+                    continue;
+                }
+                BPF_RET => ("ret".to_string(), format!("#{k}")),
+                _ => ("unimp".to_string(), format!("{code:#x}")),
+            };
+            writeln!(f, "({pc:03}) {op:<8} {operand}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,505 @@
+//! 用一个简单的 BPF 解释器运行编译结果，与用户态求值比较
+
+use std::path::Path;
+
+use super::*;
+use crate::{
+    filter::Packet,
+    head::{
+        ArpHdr, EtherHdr, EtherKind, ExtHdr, Header, IPHdr, Icmpv6, Ipv6Hdr, NetHdr, Protocol,
+        TcpFlag, TcpHdr, UdpHdr, VlanTag, ICMP,
+    },
+    pcap::PcapReader,
+    source::PacketSource,
+};
+
+/// 按内核的语义运行程序，`vlan` 为内核剥离的外层 VLAN 标签。越界读取时丢弃帧
+fn run(prog: &Program, frame: &[u8], vlan: Option<VlanTag>) -> u32 {
+    let (mut a, mut x, mut pc) = (0u32, 0u32, 0);
+    let load = |off: u32, size: u32| -> Option<u32> {
+        let len = match size {
+            BPF_W => 4,
+            BPF_H => 2,
+            _ => 1,
+        };
+        let bytes = frame.get(off as usize..off as usize + len)?;
+        Some(bytes.iter().fold(0, |acc, &b| acc << 8 | b as u32))
+    };
+    loop {
+        let insn = prog.insns()[pc];
+        let (code, k) = (insn.code as u32, insn.k);
+        pc += 1;
+        match code & 0x07 {
+            BPF_LD => {
+                let size = code & 0x18;
+                a = match code & 0xe0 {
+                    BPF_ABS if (k as i32) < 0 => match (k as i32) - SKF_AD_OFF {
+                        SKF_AD_VLAN_TAG => vlan.map_or(0, |tag| tag.tci() as u32),
+                        SKF_AD_VLAN_TAG_PRESENT => vlan.is_some() as u32,
+                        ad => panic!("辅助数据 {ad}"),
+                    },
+                    BPF_ABS => match load(k, size) {
+                        Some(value) => value,
+                        None => return 0,
+                    },
+                    BPF_IND => match load(x.wrapping_add(k), size) {
+                        Some(value) => value,
+                        None => return 0,
+                    },
+                    mode => panic!("取数方式 {mode:#x}"),
+                };
+            }
+            BPF_LDX if code & 0xe0 == BPF_MSH => match load(k, BPF_B) {
+                Some(value) => x = 4 * (value & 0xf),
+                None => return 0,
+            },
+            BPF_ALU => match code & 0xf0 {
+                BPF_ADD => a = a.wrapping_add(k),
+                BPF_AND => a &= k,
+                BPF_RSH => a >>= k,
+                op => panic!("运算 {op:#x}"),
+            },
+            BPF_JMP if code & 0xf0 == BPF_JA => pc += k as usize,
+            BPF_JMP => {
+                let taken = match code & 0xf0 {
+                    BPF_JEQ => a == k,
+                    BPF_JGT => a > k,
+                    BPF_JGE => a >= k,
+                    BPF_JSET => a & k != 0,
+                    op => panic!("跳转 {op:#x}"),
+                };
+                pc += if taken { insn.jt } else { insn.jf } as usize;
+            }
+            BPF_RET => return k,
+            class => panic!("指令类别 {class:#x}"),
+        }
+    }
+}
+
+/// 测试用的帧：内核交给程序的字节与剥离的标签，以及用户态看到的完整帧
+struct Case {
+    name: String,
+    wire: Vec<u8>,
+    vlan: Option<VlanTag>,
+    frame: Vec<u8>,
+    /// 程序能够精确判断，接受与否应与用户态求值一致
+    exact: bool,
+    /// 分片所属数据报重组后的传输层首部
+    reassembled: Option<UdpHdr>,
+}
+
+impl Case {
+    fn new(name: &str, frame: Vec<u8>, exact: bool) -> Self {
+        Case {
+            name: name.to_string(),
+            wire: frame.clone(),
+            vlan: None,
+            frame,
+            exact,
+            reassembled: None,
+        }
+    }
+
+    /// 内核剥离外层 VLAN 标签后的帧
+    fn stripped(name: &str, frame: Vec<u8>, exact: bool) -> Self {
+        let mut wire = frame.clone();
+        let tci = u16::from_be_bytes([wire[14], wire[15]]);
+        let tpid = u16::from_be_bytes([wire[12], wire[13]]);
+        wire.drain(12..16);
+        Case {
+            wire,
+            vlan: Some(VlanTag::from_tci(tpid, tci)),
+            ..Case::new(name, frame, exact)
+        }
+    }
+}
+
+/// 解码后的各层首部，对应 [`Packet`] 中借用的部分
+struct Decoded {
+    ether: EtherHdr,
+    net: NetHdr,
+    tcp: Option<TcpHdr>,
+    udp: Option<UdpHdr>,
+    icmp: Option<ICMP>,
+    icmpv6: Option<Icmpv6>,
+}
+
+impl Decoded {
+    fn new(frame: &[u8]) -> Option<Self> {
+        let (ether, rest) = EtherHdr::from_bytes(frame).unwrap();
+        let (net, rest) = match ether.etype {
+            EtherKind::IP => {
+                let (hdr, rest) = IPHdr::from_bytes(rest).unwrap();
+                (NetHdr::V4(hdr), rest)
+            }
+            EtherKind::IPv6 => {
+                let (hdr, rest) = Ipv6Hdr::from_bytes(rest).unwrap();
+                (NetHdr::V6(hdr), rest)
+            }
+            _ => return None,
+        };
+        let mut decoded = Decoded {
+            ether,
+            net,
+            tcp: None,
+            udp: None,
+            icmp: None,
+            icmpv6: None,
+        };
+        let fragment = match &decoded.net {
+            NetHdr::V4(hdr) => hdr.is_fragment(),
+            NetHdr::V6(hdr) => hdr.is_fragment(),
+        };
+        if !fragment {
+            match decoded.net.protocol() {
+                Protocol::TCP => decoded.tcp = Some(TcpHdr::from_bytes(rest).unwrap().0),
+                Protocol::UDP => decoded.udp = Some(UdpHdr::from_bytes(rest).unwrap().0),
+                Protocol::ICMP => decoded.icmp = Some(ICMP::from_bytes(rest).unwrap().0),
+                Protocol::ICMPv6 => decoded.icmpv6 = Some(Icmpv6::from_bytes(rest).unwrap().0),
+                Protocol::Other(_) => {}
+            }
+        }
+        Some(decoded)
+    }
+
+    fn packet(&self) -> Packet<'_> {
+        Packet {
+            tcp: self.tcp.as_ref(),
+            udp: self.udp.as_ref(),
+            icmp: self.icmp.as_ref(),
+            icmpv6: self.icmpv6.as_ref(),
+            ..Packet::new(&self.ether, &self.net)
+        }
+    }
+}
+
+fn fixture(name: &str) -> Vec<Vec<u8>> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name);
+    let mut reader = PcapReader::open(&path).unwrap();
+    let mut frames = vec![];
+    while let Some(frame) = reader.next_frame().unwrap() {
+        frames.push(frame.data);
+    }
+    frames
+}
+
+const MAC: [u8; 6] = [2, 0, 0, 0, 0, 1];
+
+/// 带选项（首部长 24 字节）的 TCP SYN，用于检查按 X 间接寻址
+fn tcp_frame(vlans: Vec<VlanTag>) -> Vec<u8> {
+    let tcp = TcpHdr::new(1000, 80).flag(TcpFlag {
+        syn: true,
+        ..Default::default()
+    });
+    let ip = IPHdr::new(7)
+        .source([10, 0, 0, 1])
+        .destination([10, 1, 2, 3])
+        .protocol(Protocol::TCP)
+        .tos(0xb8)
+        .router_alert()
+        .payload_len(20)
+        .checksum();
+    assert_eq!(ip.ihl, 24);
+    let ether = EtherHdr::new([0xff; 6], MAC, EtherKind::IP).vlans(vlans);
+    [ether.to_bytes(), ip.to_bytes(), tcp.to_bytes()].concat()
+}
+
+/// 逐跳选项之后的 UDP
+fn ext_frame() -> Vec<u8> {
+    let mut source = [0; 16];
+    source[..2].copy_from_slice(&[0xfd, 0]);
+    let mut destination = source;
+    (source[15], destination[15]) = (1, 2);
+    let data = b"hi";
+    let mut ip = Ipv6Hdr::new(source, destination, Protocol::UDP);
+    ip.exts.push(ExtHdr::HopByHop(vec![1, 4, 0, 0, 0, 0]));
+    let ip = ip.payload_len(10);
+    let udp = UdpHdr::new(7, 9).checksum(&ip, data);
+    let ether = EtherHdr::new([0xff; 6], MAC, EtherKind::IPv6);
+    [
+        ether.to_bytes(),
+        ip.to_bytes(),
+        udp.to_bytes(),
+        data.to_vec(),
+    ]
+    .concat()
+}
+
+fn cases() -> Vec<Case> {
+    let mut cases = vec![
+        Case::new("tcp", tcp_frame(vec![]), true),
+        Case::stripped("tcp vlan 300", tcp_frame(vec![VlanTag::new(300)]), true),
+        // 内层标签留在帧中
+        Case::stripped(
+            "tcp qinq",
+            tcp_frame(vec![VlanTag::new(300), VlanTag::new(400)]),
+            false,
+        ),
+        Case::new(
+            "tcp in-frame vlan",
+            tcp_frame(vec![VlanTag::new(300)]),
+            false,
+        ),
+        Case::new("ipv6 hop-by-hop", ext_frame(), false),
+    ];
+    for (idx, frame) in fixture("icmp-echo.pcap").into_iter().enumerate() {
+        cases.push(Case::new(&format!("icmp-echo {idx}"), frame, true));
+    }
+    for (idx, frame) in fixture("ipv6-nd.pcap").into_iter().enumerate() {
+        cases.push(Case::new(&format!("ipv6-nd {idx}"), frame, true));
+    }
+    let qinq = fixture("qinq.pcapng").remove(0);
+    cases.push(Case::new("qinq", qinq.clone(), false));
+    cases.push(Case::stripped("qinq stripped", qinq, false));
+    let reassembled = UdpHdr {
+        sport: 7,
+        dport: 9,
+        len: 48,
+        chksum: 0,
+    };
+    for (idx, frame) in fixture("ipv4-frag.pcap").into_iter().enumerate() {
+        cases.push(Case {
+            reassembled: Some(reassembled.clone()),
+            ..Case::new(&format!("ipv4-frag {idx}"), frame, false)
+        });
+    }
+    cases
+}
+
+const EXPRS: &[&str] = &[
+    "ip",
+    "ip6",
+    "tcp",
+    "udp",
+    "icmp",
+    "icmp6",
+    "proto 17",
+    "not udp",
+    "host 10.1.2.3",
+    "src host 10.0.0.1",
+    "dst 10.0.0.1",
+    "net 10.0.0.0/8",
+    "dst net 10.1.0.0/16",
+    "not net 10.0.0.0/8",
+    "net 192.0.2.0/25",
+    "host fd00::2",
+    "src net fd00::/16",
+    "dst net ff02::1:ff00:0/104",
+    "port 80",
+    "src port 1000",
+    "dst port 9",
+    "port 7 or port 9",
+    "not port 80",
+    "not dst port 9",
+    "ether src 02:00:00:00:00:01",
+    "ether dst ff:ff:ff:ff:ff:ff",
+    "ether host 02:fc:00:00:00:01",
+    "vlan",
+    "not vlan",
+    "vlan 100",
+    "vlan 200",
+    "vlan 300",
+    "vlan 400",
+    "not vlan 200",
+    "vlan 300 and tcp",
+    "ttl > 60",
+    "ttl == 255",
+    "tos & 0xfc == 0xb8",
+    "len >= 60",
+    "len < 60",
+    "ip.id == 7",
+    "ip.off != 0",
+    "ip.proto == 6",
+    "not ip.proto == 17",
+    "icmp.type == 8",
+    "icmp.code != 0",
+    "icmp6.type == 135",
+    "icmp6.code == 0",
+    "tcp.flags & 0x2 != 0",
+    "not tcp.flags & 0x2 != 0",
+    "tcp.flags & 0x12 == 0x12",
+    "tcp.sport == 1000 and tcp.dport == 80",
+    "tcp.win < 1000",
+    "udp.sport < 10",
+    "udp.dport >= 9 and udp.dport <= 9",
+    "not udp.dport == 9",
+    "udp.len > 10",
+    "udp or tcp and port 80",
+    "not (udp and port 9)",
+    "not (not tcp or port 443)",
+    "ip6 and not udp.dport == 9",
+    "(vlan or ip6) and not (icmp6 or port 9)",
+];
+
+/// 程序接受的帧总是用户态求值结果的超集，能精确判断时两者一致
+#[test]
+fn matches_userspace() {
+    for input in EXPRS {
+        let expr = Expr::parse(input).unwrap();
+        let prog = compile(Some(&expr)).unwrap();
+        for case in cases() {
+            let decoded = Decoded::new(&case.frame).unwrap();
+            let mut expected = expr.matches(&decoded.packet());
+            // 分片的传输层首部在重组完成后才能得到
+            if let Some(udp) = &case.reassembled {
+                let pkt = Packet {
+                    udp: Some(udp),
+                    ..decoded.packet()
+                };
+                expected |= expr.matches(&pkt);
+            }
+            let accepted = run(&prog, &case.wire, case.vlan) != 0;
+            let name = &case.name;
+            if case.exact {
+                assert_eq!(accepted, expected, "`{input}` 对 {name}\n{prog}");
+            } else {
+                assert!(accepted || !expected, "`{input}` 丢弃了 {name}\n{prog}");
+            }
+        }
+    }
+}
+
+/// 不确定的结果按所在位置的极性处理，取反后同样放行
+#[test]
+fn unknown_polarity() {
+    let accepts = |input: &str, case: &Case| {
+        let prog = compile(Some(&Expr::parse(input).unwrap())).unwrap();
+        run(&prog, &case.wire, case.vlan) != 0
+    };
+    let ext = Case::new("ipv6 hop-by-hop", ext_frame(), false);
+    for input in ["udp", "not udp", "port 9", "not port 9", "udp.dport == 9"] {
+        assert!(accepts(input, &ext), "{input}");
+    }
+    // 网络层字段不受扩展首部影响
+    assert!(!accepts("not ip6", &ext));
+    assert!(accepts("ip6 and src net fd00::/16", &ext));
+    assert!(!accepts("ip6 and not src net fd00::/16", &ext));
+
+    // 双重否定恢复原来的极性；与确定为假的条件相与时仍然丢弃
+    let qinq = Case::new(
+        "tcp in-frame vlan",
+        tcp_frame(vec![VlanTag::new(300)]),
+        false,
+    );
+    for input in ["tcp", "not tcp", "not not tcp", "vlan 300", "not vlan 300"] {
+        assert!(accepts(input, &qinq), "{input}");
+    }
+    assert!(!accepts("not vlan", &qinq));
+    assert!(!accepts("tcp and ether src 02:00:00:00:00:02", &qinq));
+    assert!(!accepts("not (tcp or ether src 02:00:00:00:00:01)", &qinq));
+}
+
+/// 片偏移或 MF 不为 0 的 IPv4 报文不能判断传输层字段，DF 不影响判断
+#[test]
+fn fragment_jset() {
+    let prog = compile(Some(&Expr::parse("dst port 9").unwrap())).unwrap();
+    let text = prog.to_string();
+    assert!(text.contains("jset     #0x3fff"), "{text}");
+    let not = compile(Some(&Expr::parse("not dst port 9").unwrap())).unwrap();
+
+    let frames = fixture("ipv4-frag.pcap");
+    for frame in &frames {
+        assert_ne!(run(&prog, frame, None), 0);
+        assert_ne!(run(&not, frame, None), 0);
+    }
+
+    // 只置 DF 的完整数据报按端口判断
+    let mut whole = frames[0].clone();
+    let data = b"hello fragmented world, 40 bytes long...";
+    let ip = IPHdr::new(0)
+        .source([127, 0, 0, 1])
+        .destination([127, 0, 0, 1])
+        .protocol(Protocol::UDP)
+        .payload_len(48)
+        .checksum();
+    let udp = UdpHdr::new(7, 9).checksum(&ip, data);
+    whole.truncate(14);
+    whole.extend(ip.clone().to_bytes());
+    whole.extend(udp.to_bytes());
+    whole.extend(data);
+    assert!(ip.flag.df);
+    assert_ne!(run(&prog, &whole, None), 0);
+    assert_eq!(run(&not, &whole, None), 0);
+    let other = compile(Some(&Expr::parse("dst port 10").unwrap())).unwrap();
+    assert_eq!(run(&other, &whole, None), 0);
+    // 只置 MF 的首片同样不确定
+    whole[14 + 6] |= 0x20;
+    assert_ne!(run(&other, &whole, None), 0);
+}
+
+/// VLAN 标签：内核剥离的外层标签从辅助数据读取，留在帧中的标签使网络层偏移不确定
+#[test]
+fn vlan_offsets() {
+    let run_expr = |input: &str, case: &Case| {
+        let prog = compile(Some(&Expr::parse(input).unwrap())).unwrap();
+        run(&prog, &case.wire, case.vlan)
+    };
+    let stripped = Case::stripped("tcp vlan 300", tcp_frame(vec![VlanTag::new(300)]), true);
+    assert_eq!(stripped.wire, tcp_frame(vec![]));
+    assert_ne!(
+        run_expr("vlan 300 and port 80 and host 10.1.2.3", &stripped),
+        0
+    );
+    assert_eq!(run_expr("vlan 301", &stripped), 0);
+
+    let qinq = fixture("qinq.pcapng").remove(0);
+    let stripped = Case::stripped("qinq stripped", qinq.clone(), false);
+    assert_eq!(stripped.vlan.unwrap().vid, 100);
+    assert_eq!(stripped.vlan.unwrap().pcp, 5);
+    assert_ne!(run_expr("vlan 100", &stripped), 0);
+    // 内层标签在帧中，VLAN ID 不确定
+    assert_ne!(run_expr("vlan 200", &stripped), 0);
+    assert_ne!(run_expr("vlan 999", &stripped), 0);
+    assert_ne!(run_expr("not vlan 999", &stripped), 0);
+    // 外层标签已确定匹配
+    assert_eq!(run_expr("not vlan 100", &stripped), 0);
+    assert_eq!(run_expr("not vlan", &stripped), 0);
+
+    // 未剥离时只要类型字段是标签协议标识即为带有 VLAN 标签
+    let unstripped = Case::new("qinq", qinq, false);
+    assert_ne!(run_expr("vlan", &unstripped), 0);
+    assert_eq!(run_expr("not vlan", &unstripped), 0);
+    assert_ne!(run_expr("udp and dst port 9", &unstripped), 0);
+    assert_ne!(run_expr("not udp", &unstripped), 0);
+}
+
+/// IPv6 扩展首部之后的上层协议不确定，其他类型的帧为假
+#[test]
+fn ipv6_ext_and_other_types() {
+    let frame = ext_frame();
+    let decoded = Decoded::new(&frame).unwrap();
+    let NetHdr::V6(ip) = &decoded.net else {
+        panic!("{:?}", decoded.net);
+    };
+    assert_eq!(ip.exts.len(), 1);
+    assert_eq!(decoded.udp.as_ref().unwrap().dport, 9);
+    for input in ["ip.proto == 17", "icmp6", "tcp.dport == 9"] {
+        let prog = compile(Some(&Expr::parse(input).unwrap())).unwrap();
+        assert_ne!(run(&prog, &frame, None), 0, "{input}");
+    }
+    // 固定首部中的字段仍可精确判断
+    for (input, expected) in [
+        ("ttl == 64", true),
+        ("len == 58", true),
+        ("len == 50", false),
+    ] {
+        let prog = compile(Some(&Expr::parse(input).unwrap())).unwrap();
+        assert_eq!(run(&prog, &frame, None) != 0, expected, "{input}");
+    }
+
+    let arp = ArpHdr::request(MAC, [10, 0, 0, 1], [10, 0, 0, 2]);
+    let ether = EtherHdr::new([0xff; 6], MAC, EtherKind::ARP);
+    let frame = (ether, arp).to_bytes();
+    for (input, expected) in [
+        ("ip or ip6", false),
+        ("not ip", true),
+        ("port 80", false),
+        ("ttl > 0", false),
+        ("ether src 02:00:00:00:00:01", true),
+    ] {
+        let prog = compile(Some(&Expr::parse(input).unwrap())).unwrap();
+        assert_eq!(run(&prog, &frame, None) != 0, expected, "{input}");
+    }
+}
@@ -0,0 +1,275 @@
+//! 表达式的解析、求值与错误位置
+
+use std::path::Path;
+
+use super::*;
+use crate::{
+    frag::{Reassembler, FRAG_TIMEOUT},
+    head::{EtherKind, Header, IPHdr, Ipv6Hdr, TcpFlag, VlanTag},
+    pcap::PcapReader,
+    source::PacketSource,
+};
+
+fn parse(input: &str) -> Expr {
+    Expr::parse(input).unwrap_or_else(|e| panic!("{e}"))
+}
+
+fn prim(input: &str) -> Expr {
+    match parse(input) {
+        expr @ Expr::Prim(_) => expr,
+        expr => panic!("{expr:?}"),
+    }
+}
+
+fn ether() -> EtherHdr {
+    EtherHdr::new([0xff; 6], [2, 0, 0, 0, 0, 1], EtherKind::IP)
+}
+
+fn ipv4(source: [u8; 4], destination: [u8; 4], protocol: Protocol) -> NetHdr {
+    let hdr = IPHdr::new(7)
+        .source(source)
+        .destination(destination)
+        .protocol(protocol);
+    NetHdr::V4(hdr)
+}
+
+fn syn() -> TcpFlag {
+    TcpFlag {
+        syn: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    let (a, b, c) = (prim("tcp"), prim("udp"), prim("port 53"));
+    assert_eq!(
+        parse("tcp or udp and port 53"),
+        a.clone().or(b.clone().and(c.clone()))
+    );
+    assert_eq!(
+        parse("tcp and udp or port 53"),
+        a.clone().and(b.clone()).or(c.clone())
+    );
+    assert_eq!(
+        parse("tcp || udp && port 53"),
+        parse("tcp or udp and port 53")
+    );
+    // 同级运算左结合
+    assert_eq!(
+        parse("tcp or udp or port 53"),
+        a.clone().or(b.clone()).or(c.clone())
+    );
+
+    // 按优先级求值：UDP 报文满足 `tcp and port 53` 之外的 `udp`
+    let (ethdr, net) = (ether(), ipv4([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP));
+    let udp = UdpHdr::new(1000, 2000);
+    let pkt = Packet {
+        udp: Some(&udp),
+        ..Packet::new(&ethdr, &net)
+    };
+    assert!(parse("udp or tcp and port 53").matches(&pkt));
+    assert!(!parse("(udp or tcp) and port 53").matches(&pkt));
+}
+
+#[test]
+fn not_and_parentheses() {
+    let (a, b) = (prim("tcp"), prim("udp"));
+    let not = |expr: Expr| Expr::Not(Box::new(expr));
+    assert_eq!(parse("not tcp and udp"), not(a.clone()).and(b.clone()));
+    assert_eq!(parse("not (tcp and udp)"), not(a.clone().and(b.clone())));
+    assert_eq!(
+        parse("!tcp or !!udp"),
+        not(a.clone()).or(not(not(b.clone())))
+    );
+    assert_eq!(parse("((tcp))"), a);
+    assert_eq!(
+        parse("tcp and (udp or icmp)"),
+        a.clone().and(b.clone().or(prim("icmp")))
+    );
+
+    // 显示结果只在需要时加括号，再次解析得到同一语法树
+    for input in [
+        "not (tcp or udp) and port 80",
+        "tcp and (udp or icmp)",
+        "not not ip6",
+        "(tcp or udp) and not (src host 10.0.0.1 and dst port 22)",
+    ] {
+        let expr = parse(input);
+        assert_eq!(parse(&expr.to_string()), expr, "{input}");
+    }
+    assert_eq!(parse("((tcp)) and (udp)").to_string(), "tcp and udp");
+
+    let (ethdr, net) = (ether(), ipv4([10, 0, 0, 1], [10, 0, 0, 2], Protocol::ICMP));
+    let pkt = Packet::new(&ethdr, &net);
+    assert!(parse("not (tcp or udp)").matches(&pkt));
+    assert!(!parse("not tcp and udp").matches(&pkt));
+    assert!(parse("!(ip6) && !(tcp || udp)").matches(&pkt));
+}
+
+#[test]
+fn masked_comparison() {
+    assert_eq!(
+        parse("tcp.flags & 0x2 != 0"),
+        Expr::Prim(Primitive::Cmp {
+            field: Field::TcpFlags,
+            mask: Some(2),
+            op: CmpOp::Ne,
+            value: 0,
+        })
+    );
+    assert_eq!(
+        parse("tcp.flags&0x12==0x12").to_string(),
+        "tcp.flags & 0x12 == 18"
+    );
+
+    let (ethdr, net) = (ether(), ipv4([10, 0, 0, 1], [10, 0, 0, 2], Protocol::TCP));
+    let tcp = |flag| TcpHdr::new(1000, 80).flag(flag);
+    let (syn, synack) = (tcp(syn()), tcp(TcpFlag { ack: true, ..syn() }));
+    let ack = tcp(TcpFlag {
+        ack: true,
+        ..Default::default()
+    });
+    let matches = |input: &str, hdr: &TcpHdr| {
+        let pkt = Packet {
+            tcp: Some(hdr),
+            ..Packet::new(&ethdr, &net)
+        };
+        parse(input).matches(&pkt)
+    };
+    assert!(matches("tcp.flags & 0x2 != 0", &syn));
+    assert!(matches("tcp.flags & 0x2 != 0", &synack));
+    assert!(!matches("tcp.flags & 0x2 != 0", &ack));
+    assert!(matches("tcp.flags & 0x12 == 0x12", &synack));
+    assert!(!matches("tcp.flags & 0x12 == 0x12", &syn));
+    // 不带掩码时比较整个字段
+    assert!(matches("tcp.flags == 2", &syn));
+    assert!(!matches("tcp.flags == 2", &synack));
+    assert!(matches("tcp.flags & 0xff > 0x10", &synack));
+
+    // 没有该字段的报文不满足比较，取反后满足
+    let pkt = Packet::new(&ethdr, &net);
+    assert!(!parse("tcp.flags & 0x2 != 0").matches(&pkt));
+    assert!(!parse("tcp.flags & 0x2 == 0").matches(&pkt));
+    assert!(parse("not tcp.flags & 0x2 != 0").matches(&pkt));
+}
+
+#[test]
+fn net_prefix() {
+    assert_eq!(
+        parse("src net 10.1.0.0/16"),
+        Expr::Prim(Primitive::Net(Dir::Src, [10, 1, 0, 0].into(), 16))
+    );
+    // 不带前缀长度时为单个地址
+    assert_eq!(parse("net 10.1.2.3").to_string(), "net 10.1.2.3/32");
+
+    let (ethdr, net) = (
+        ether(),
+        ipv4([10, 1, 2, 3], [192, 168, 7, 9], Protocol::UDP),
+    );
+    let pkt = Packet::new(&ethdr, &net);
+    for (input, expected) in [
+        ("net 10.1.0.0/16", true),
+        ("src net 10.1.0.0/16", true),
+        ("dst net 10.1.0.0/16", false),
+        ("dst net 192.168.6.0/23", true),
+        ("dst net 192.168.4.0/23", false),
+        ("net 10.1.2.3/32", true),
+        ("net 10.1.2.2/31", true),
+        ("net 10.0.0.0/8 and net 192.168.0.0/16", true),
+        ("net 0.0.0.0/0", true),
+        ("net 11.0.0.0/8", false),
+        ("net fd00::/8", false),
+    ] {
+        assert_eq!(parse(input).matches(&pkt), expected, "{input}");
+    }
+
+    let mut addr = [0; 16];
+    addr[..2].copy_from_slice(&[0xfd, 0x12]);
+    addr[15] = 1;
+    let net = NetHdr::V6(Ipv6Hdr::new(addr, [0xff; 16], Protocol::UDP));
+    let pkt = Packet::new(&ethdr, &net);
+    assert!(parse("src net fd12::/16").matches(&pkt));
+    assert!(!parse("src net fd00::/16").matches(&pkt));
+    assert!(parse("src net fd00::/8").matches(&pkt));
+    assert!(!parse("net 10.0.0.0/8").matches(&pkt));
+}
+
+/// 端口取自重组后的传输层首部，而不是各个分片
+#[test]
+fn port_on_reassembled_datagram() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/ipv4-frag.pcap");
+    let mut reader = PcapReader::open(&path).unwrap();
+    let expr = parse("udp and dst port 9 and not src port 9");
+    let mut reasm = Reassembler::new(FRAG_TIMEOUT);
+    let mut matched = vec![];
+    while let Some(frame) = reader.next_frame().unwrap() {
+        let ((ethdr, iphdr), rest) = <(EtherHdr, IPHdr)>::from_bytes(&frame.data).unwrap();
+        let reassembled = reasm.push(&iphdr, rest, frame.ts);
+        let net = NetHdr::V4(iphdr);
+        let udp = reassembled.map(|(_, data)| UdpHdr::from_bytes(&data).unwrap().0);
+        let pkt = Packet {
+            udp: udp.as_ref(),
+            ..Packet::new(&ethdr, &net)
+        };
+        // 分片本身满足 `udp`，但没有端口
+        assert!(parse("udp").matches(&pkt));
+        assert_eq!(parse("port 7").matches(&pkt), udp.is_some());
+        matched.push(expr.matches(&pkt));
+    }
+    assert_eq!(matched, [false, false, true]);
+}
+
+#[test]
+fn vlan_and_ether() {
+    let ethdr = ether().vlans(vec![VlanTag::new(100), VlanTag::new(200)]);
+    let net = ipv4([10, 0, 0, 1], [10, 0, 0, 2], Protocol::UDP);
+    let pkt = Packet::new(&ethdr, &net);
+    assert!(parse("vlan").matches(&pkt));
+    assert!(parse("vlan 200 and vlan 100").matches(&pkt));
+    assert!(!parse("vlan 300").matches(&pkt));
+    assert!(parse("ether src 02:00:00:00:00:01").matches(&pkt));
+    assert!(parse("ether dst host ff:ff:ff:ff:ff:ff").matches(&pkt));
+    assert!(!parse("ether dst 02:00:00:00:00:01").matches(&pkt));
+    assert!(!parse("vlan").matches(&Packet::new(&ether(), &net)));
+}
+
+#[test]
+fn error_offsets() {
+    for (input, offset) in [
+        ("", 0),
+        ("tcp and", 7),
+        ("tcp and or udp", 8),
+        ("tcp udp", 4),
+        ("tcp)", 3),
+        ("(tcp or udp", 0),
+        ("(tcp or udp udp)", 12),
+        ("host 10.0.0", 5),
+        ("src 10.0.0.1 and dst foo", 21),
+        ("net 10.0.0.0/33", 13),
+        ("net 10.0.0.1/8", 4),
+        ("port 65536", 5),
+        ("ether 02:00:00:00:00", 6),
+        ("vlan 4096", 5),
+        ("ttl", 3),
+        ("ttl 5", 4),
+        ("ttl > x", 6),
+        ("tcp.flags & y != 0", 12),
+        ("tcp.flag == 1", 0),
+        ("proto 256", 6),
+        ("frob", 0),
+        ("tcp and $", 8),
+        ("tcp ~ udp", 4),
+    ] {
+        let err = Expr::parse(input).unwrap_err();
+        assert_eq!(err.offset, offset, "{input}");
+    }
+
+    // 偏移按字符计算，与插入符的位置一致
+    let input = "tcp\u{3000}and\u{3000}port x";
+    let err = Expr::parse(input).unwrap_err();
+    assert_eq!(err.offset, 13);
+    let caret = err.to_string().lines().last().unwrap().to_string();
+    assert_eq!(caret, format!("  {}^", " ".repeat(13)));
+    assert_eq!(err.reason, msg!("expr.bad_port", word = "x"));
+}
@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::head::{IPHdr, Protocol};
+
+/// 分片集合的缺省超时时间，与 Linux 的 `ipfrag_time` 一致
+pub const FRAG_TIMEOUT: Duration = Duration::from_secs(30);
+/// IPv4 报文的最大长度
+const MAX_DATAGRAM: usize = 65535;
+
+/// 将首部为 `hdr`、数据为 `payload` 的数据报切分为每片至多 `size` 字节数据的分片。
+/// `size` 会向下取整为 8 的倍数；片偏移从 `hdr.offset` 起算，最后一片保留原有的 MF 标志。
+/// 第一片之后的分片只携带需要复制的选项。调用者须先以 [`fits`] 检查数据能否放下。
+pub fn fragment(hdr: &IPHdr, payload: &[u8], size: usize) -> Vec<(IPHdr, Vec<u8>)> {
+    let size = (size / 8 * 8).max(8);
+    let count = payload.len().div_ceil(size).max(1);
+    (0..count)
+        .map(|idx| {
+            let start = idx * size;
+            let chunk = &payload[start..payload.len().min(start + size)];
+            let last = idx + 1 == count;
+            let frag = match idx {
+                0 => hdr.clone(),
+                _ => hdr.clone().copied_options(),
+            };
+            let frag = frag
+                .flag(hdr.flag.df, !last || hdr.flag.mf)
+                .offset(hdr.offset + (start / 8) as u16)
+                .payload_len(chunk.len() as u16)
+                .checksum();
+            (frag, chunk.to_vec())
+        })
+        .collect()
+}
+
+/// 从 `hdr.offset` 起能否放下 `len` 字节数据：重组后的数据报不超过 65535 字节，
+/// 因而每个分片的片偏移都能用 13 位表示
+pub fn fits(hdr: &IPHdr, len: usize) -> bool {
+    hdr.offset as usize * 8 + hdr.ihl as usize + len <= MAX_DATAGRAM
+}
+
+/// 区分同一数据报各分片的键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FragKey {
+    pub source: [u8; 4],
+    pub destination: [u8; 4],
+    pub protocol: Protocol,
+    pub ident: u16,
+}
+
+impl From<&IPHdr> for FragKey {
+    fn from(hdr: &IPHdr) -> Self {
+        Self {
+            source: hdr.source,
+            destination: hdr.destinaiton,
+            protocol: hdr.protocol,
+            ident: hdr.ident,
+        }
+    }
+}
+
+/// 正在重组的一个数据报
+#[derive(Debug)]
+struct FragSet {
+    /// 片偏移为 0 的分片的首部
+    first: Option<IPHdr>,
+    data: Vec<u8>,
+    /// 已收到的数据区间，按起点排序且互不相交
+    filled: Vec<(usize, usize)>,
+    /// 最后一片到达后得知的数据总长
+    total: Option<usize>,
+    /// 收到首个分片的时间
+    since: SystemTime,
+}
+
+impl FragSet {
+    /// 写入 `[start, start + data.len())` 中尚未收到的部分，先到的数据优先。
+    /// 返回是否与已有数据重叠。
+    fn insert(&mut self, start: usize, data: &[u8]) -> bool {
+        let end = start + data.len();
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        let mut overlap = false;
+        let mut cur = start;
+        for &(s, e) in &self.filled {
+            if e <= cur || s >= end {
+                continue;
+            }
+            overlap = true;
+            if s > cur {
+                self.data[cur..s].copy_from_slice(&data[cur - start..s - start]);
+            }
+            cur = cur.max(e);
+        }
+        if cur < end {
+            self.data[cur..end].copy_from_slice(&data[cur - start..]);
+        }
+
+        self.filled.push((start, end));
+        self.filled.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = vec![];
+        for &(s, e) in &self.filled {
+            match merged.last_mut() {
+                Some(last) if s <= last.1 => last.1 = last.1.max(e),
+                _ => merged.push((s, e)),
+            }
+        }
+        self.filled = merged;
+        overlap
+    }
+
+    fn is_complete(&self) -> bool {
+        self.first.is_some() && self.total.is_some_and(|total| self.filled == [(0, total)])
+    }
+}
+
+/// 按 (源地址, 目的地址, 协议, 标识) 缓存分片并重组 IPv4 数据报
+#[derive(Debug)]
+pub struct Reassembler {
+    sets: HashMap<FragKey, FragSet>,
+    timeout: Duration,
+    /// 因超时而丢弃的分片集合数
+    pub expired: usize,
+    /// 与已收到数据重叠的分片数
+    pub overlaps: usize,
+}
+
+impl Reassembler {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            sets: HashMap::new(),
+            timeout,
+            expired: 0,
+            overlaps: 0,
+        }
+    }
+
+    /// 丢弃在 `now` 之前已超时的分片集合
+    fn expire(&mut self, now: SystemTime) {
+        let timeout = self.timeout;
+        let before = self.sets.len();
+        self.sets.retain(|_, set| {
+            now.duration_since(set.since)
+                .map_or(true, |elapsed| elapsed < timeout)
+        });
+        self.expired += before - self.sets.len();
+    }
+
+    /// 加入一个在 `ts` 时刻收到的分片，`payload` 为 IP 首部之后的数据。
+    /// 数据报的所有分片到齐时返回重组后的首部和数据。
+    pub fn push(
+        &mut self,
+        hdr: &IPHdr,
+        payload: &[u8],
+        ts: SystemTime,
+    ) -> Option<(IPHdr, Vec<u8>)> {
+        self.expire(ts);
+
+        // 去掉链路层可能附加的填充
+        let len = (hdr.totlen as usize)
+            .saturating_sub(hdr.ihl as usize)
+            .min(payload.len());
+        let payload = &payload[..len];
+        let start = hdr.offset as usize * 8;
+        let end = start + payload.len();
+        if end + hdr.ihl as usize > MAX_DATAGRAM || (hdr.flag.mf && !len.is_multiple_of(8)) {
+            return None;
+        }
+
+        let key = FragKey::from(hdr);
+        let set = self.sets.entry(key).or_insert_with(|| FragSet {
+            first: None,
+            data: vec![],
+            filled: vec![],
+            total: None,
+            since: ts,
+        });
+        if start == 0 {
+            set.first.get_or_insert_with(|| hdr.clone());
+        }
+        if !hdr.flag.mf {
+            set.total.get_or_insert(end);
+        }
+        if set.insert(start, payload) {
+            self.overlaps += 1;
+        }
+        if !set.is_complete() {
+            return None;
+        }
+
+        let set = self.sets.remove(&key)?;
+        let total = set.total?;
+        let mut data = set.data;
+        data.truncate(total);
+        let first = set.first?;
+        // 增量更新首片的校验和，首片校验和错误时重组结果同样错误
+        let hdr = first
+            .clone()
+            .flag(first.flag.df, false)
+            .offset(0)
+            .payload_len(total as u16)
+            .update_checksum(&first);
+        Some((hdr, data))
+    }
+}
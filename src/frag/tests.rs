@@ -0,0 +1,189 @@
+//! 分片与重组：乱序、重叠、缺片、超时，以及捕获文件中的分片
+
+use std::{path::Path, time::UNIX_EPOCH};
+
+use super::*;
+use crate::{
+    head::{EtherHdr, Header, UdpHdr},
+    pcap::PcapReader,
+    source::PacketSource,
+};
+
+fn datagram(ident: u16, len: usize) -> (IPHdr, Vec<u8>) {
+    let payload = (0..len).map(|n| (n * 7) as u8).collect::<Vec<_>>();
+    let hdr = IPHdr::new(ident)
+        .source([10, 0, 0, 1])
+        .destination([10, 0, 0, 2])
+        .protocol(Protocol::UDP)
+        .flag(false, false)
+        .router_alert()
+        .record_route(2)
+        .payload_len(len as u16)
+        .checksum();
+    (hdr, payload)
+}
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_792_224_900 + secs)
+}
+
+/// 检查重组结果与原数据报一致
+fn check(original: &IPHdr, payload: &[u8], (hdr, data): (IPHdr, Vec<u8>)) {
+    assert_eq!(data, payload);
+    assert_eq!((hdr.flag.mf, hdr.offset), (false, 0));
+    assert_eq!(hdr.options, original.options);
+    assert_eq!(hdr.totlen as usize, hdr.ihl as usize + payload.len());
+    assert!(hdr.verify());
+}
+
+#[test]
+fn fragment_round_trip() {
+    let (hdr, payload) = datagram(1, 1000);
+    // 片长向下取整为 8 的倍数
+    let frags = fragment(&hdr, &payload, 203);
+    assert_eq!(frags.len(), 5);
+    for (idx, (frag, chunk)) in frags.iter().enumerate() {
+        assert_eq!(frag.offset as usize, idx * 25);
+        assert_eq!(frag.flag.mf, idx < 4);
+        assert_eq!(frag.totlen as usize, frag.ihl as usize + chunk.len());
+        assert!(frag.verify());
+        // 第一片之后只保留路由器警告选项
+        let options = if idx == 0 { 2 } else { 1 };
+        assert_eq!(frag.options.len(), options);
+    }
+    assert_eq!(frags[4].1.len(), 200);
+
+    let mut reasm = Reassembler::new(FRAG_TIMEOUT);
+    let mut result = None;
+    for (frag, chunk) in &frags {
+        assert!(result.is_none());
+        result = reasm.push(frag, chunk, at(0));
+    }
+    check(&hdr, &payload, result.unwrap());
+    assert_eq!((reasm.overlaps, reasm.expired), (0, 0));
+    assert!(reasm.sets.is_empty());
+}
+
+#[test]
+fn offset_limit() {
+    // 首部 36 字节，从片偏移 8180 起最多还能放下 65535 - 36 - 65440 = 59 字节
+    let (hdr, payload) = datagram(5, 59);
+    let hdr = hdr.offset(8180);
+    assert!(fits(&hdr, payload.len()));
+    assert!(!fits(&hdr, payload.len() + 1));
+    assert!(!fits(&hdr.clone().offset(8190), 44));
+    for (frag, _) in fragment(&hdr, &payload, 16) {
+        assert!(frag.offset <= 0x1fff);
+        let (parsed, _) = IPHdr::from_bytes(&frag.clone().to_bytes()).unwrap();
+        // 片偏移没有溢出到标志位
+        assert_eq!((parsed.offset, parsed.flag), (frag.offset, frag.flag));
+    }
+}
+
+#[test]
+fn out_of_order() {
+    let (hdr, payload) = datagram(2, 1000);
+    let frags = fragment(&hdr, &payload, 96);
+    // 最后一片最先到达，首片最后到达
+    let mut order = (0..frags.len()).rev().collect::<Vec<_>>();
+    order.swap(1, 5);
+    let mut reasm = Reassembler::new(FRAG_TIMEOUT);
+    for (n, &idx) in order.iter().enumerate() {
+        let (frag, chunk) = &frags[idx];
+        let result = reasm.push(frag, chunk, at(n as u64));
+        if n + 1 < order.len() {
+            assert!(result.is_none());
+        } else {
+            check(&hdr, &payload, result.unwrap());
+        }
+    }
+}
+
+#[test]
+fn overlap_and_duplicate() {
+    let (hdr, payload) = datagram(3, 64);
+    let frags = fragment(&hdr, &payload, 32);
+    let mut reasm = Reassembler::new(FRAG_TIMEOUT);
+    assert!(reasm.push(&frags[0].0, &frags[0].1, at(0)).is_none());
+    // 重复的首片不改变已收到的数据
+    let mut dup = frags[0].1.clone();
+    dup.fill(0xee);
+    assert!(reasm.push(&frags[0].0, &dup, at(0)).is_none());
+    assert_eq!(reasm.overlaps, 1);
+
+    // 与首片后半部分重叠的分片，只取尚未收到的部分
+    let middle = frags[0]
+        .0
+        .clone()
+        .copied_options()
+        .offset(2)
+        .payload_len(24)
+        .checksum();
+    let mut chunk = vec![0xdd; 16];
+    chunk.extend(&payload[32..40]);
+    assert!(reasm.push(&middle, &chunk, at(0)).is_none());
+    assert_eq!(reasm.overlaps, 2);
+
+    let result = reasm.push(&frags[1].0, &frags[1].1, at(0)).unwrap();
+    check(&hdr, &payload, result);
+    assert_eq!(reasm.overlaps, 3);
+}
+
+#[test]
+fn missing_last_fragment() {
+    let (hdr, payload) = datagram(4, 100);
+    let frags = fragment(&hdr, &payload, 24);
+    let mut reasm = Reassembler::new(FRAG_TIMEOUT);
+    for (frag, chunk) in &frags[..frags.len() - 1] {
+        assert!(reasm.push(frag, chunk, at(0)).is_none());
+        // 重复收到也不会凑成完整的数据报
+        assert!(reasm.push(frag, chunk, at(0)).is_none());
+    }
+    let set = &reasm.sets[&FragKey::from(&hdr)];
+    assert_eq!(set.filled, [(0, 96)]);
+    assert!(set.total.is_none());
+
+    let (last, chunk) = frags.last().unwrap();
+    check(&hdr, &payload, reasm.push(last, chunk, at(1)).unwrap());
+}
+
+#[test]
+fn timeout_expiry() {
+    let (hdr, payload) = datagram(5, 64);
+    let frags = fragment(&hdr, &payload, 32);
+    let mut reasm = Reassembler::new(Duration::from_secs(5));
+    assert!(reasm.push(&frags[0].0, &frags[0].1, at(0)).is_none());
+
+    // 其他数据报的分片在超时之后到达，触发清理
+    let (other, data) = datagram(6, 64);
+    let others = fragment(&other, &data, 32);
+    assert!(reasm.push(&others[0].0, &others[0].1, at(5)).is_none());
+    assert_eq!(reasm.expired, 1);
+
+    // 首片已丢弃，剩余分片无法完成重组
+    assert!(reasm.push(&frags[1].0, &frags[1].1, at(5)).is_none());
+    // 未超时的集合照常完成
+    let result = reasm.push(&others[1].0, &others[1].1, at(9)).unwrap();
+    check(&other, &data, result);
+    assert_eq!(reasm.expired, 1);
+}
+
+#[test]
+fn reassemble_capture() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/ipv4-frag.pcap");
+    let mut reader = PcapReader::open(&path).unwrap();
+    let mut reasm = Reassembler::new(FRAG_TIMEOUT);
+    let mut result = None;
+    while let Some(frame) = reader.next_frame().unwrap() {
+        let ((_, iphdr), rest) = <(EtherHdr, IPHdr)>::from_bytes(&frame.data).unwrap();
+        assert!(result.is_none());
+        result = reasm.push(&iphdr, rest, frame.ts);
+    }
+    let (iphdr, data) = result.unwrap();
+    assert!(iphdr.verify());
+    assert_eq!(iphdr.totlen, 68);
+    let (udp, payload) = UdpHdr::from_bytes(&data).unwrap();
+    assert_eq!((udp.sport, udp.dport, udp.len), (7, 9, 48));
+    assert_eq!(payload, b"hello fragmented world, 40 bytes long...");
+    assert!(udp.verify(&iphdr, payload));
+}
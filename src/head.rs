@@ -1,22 +1,46 @@
+mod arp;
+pub mod checksum;
+mod error;
 mod ether;
 mod ip;
 mod icmp;
+mod icmpv6;
+mod ipv6;
+mod net;
+mod tcp;
+#[cfg(test)]
+mod tests;
+mod udp;
 
+pub use arp::*;
+pub use error::*;
 pub use ether::*;
 pub use ip::*;
 pub use icmp::*;
+pub use icmpv6::*;
+pub use ipv6::*;
+pub use net::*;
+pub use tcp::*;
+pub use udp::*;
+
+/// 为传输层校验和提供伪首部的网络层首部
+pub trait PseudoHeader {
+    /// 上层数据长度为 `len` 字节时的伪首部
+    fn pseudo_header(&self, len: usize) -> Vec<u8>;
+}
 
 pub trait Header: Sized {
-    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]);
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError>;
     fn to_bytes(self) -> Vec<u8>;
 }
 
 impl<H0: Header, H1: Header> Header for (H0, H1)
 {
-    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]) {
-        let (h0, bytes) = H0::from_bytes(bytes);
-        let (h1, rest) = H1::from_bytes(bytes);
-        ((h0, h1), rest)
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (h0, rest) = H0::from_bytes(bytes)?;
+        let consumed = bytes.len() - rest.len();
+        let (h1, rest) = H1::from_bytes(rest).map_err(|e| e.shift(consumed))?;
+        Ok(((h0, h1), rest))
     }
     fn to_bytes(self) -> Vec<u8> {
         let (h0, h1) = self;
@@ -0,0 +1,112 @@
+use super::{take, EtherKind, Header, Layer, ParseError};
+
+/// ARP 操作类型：请求
+pub const ARP_REQUEST: u16 = 1;
+/// ARP 操作类型：应答
+pub const ARP_REPLY: u16 = 2;
+
+/// 以太网上承载 IPv4 地址解析的 ARP 报文
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArpHdr {
+    /// 硬件类型，以太网为 1。
+    pub htype: u16,
+    /// 要映射的协议地址类型，IPv4 为 0x0800。
+    pub ptype: EtherKind,
+    /// 硬件地址长度，以太网为 6。
+    pub hlen: u8,
+    /// 协议地址长度，IPv4 为 4。
+    pub plen: u8,
+    /// 操作类型。1 为请求，2 为应答。
+    pub oper: u16,
+    /// 发送方硬件地址
+    pub sha: [u8; 6],
+    /// 发送方协议地址
+    pub spa: [u8; 4],
+    /// 目标硬件地址，请求中全为 0
+    pub tha: [u8; 6],
+    /// 目标协议地址
+    pub tpa: [u8; 4],
+}
+
+impl ArpHdr {
+    /// 构造询问 `tpa` 的硬件地址的请求
+    pub fn request(sha: [u8; 6], spa: [u8; 4], tpa: [u8; 4]) -> Self {
+        Self {
+            htype: 1,
+            ptype: EtherKind::IP,
+            hlen: 6,
+            plen: 4,
+            oper: ARP_REQUEST,
+            sha,
+            spa,
+            tha: [0; 6],
+            tpa,
+        }
+    }
+
+    /// 构造对请求 `req` 的应答，`sha` 为本机硬件地址
+    #[allow(dead_code)]
+    pub fn reply(req: &ArpHdr, sha: [u8; 6]) -> Self {
+        Self {
+            oper: ARP_REPLY,
+            sha,
+            spa: req.tpa,
+            tha: req.sha,
+            tpa: req.spa,
+            ..req.clone()
+        }
+    }
+}
+
+impl Header for ArpHdr {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, rest) = take(bytes, 28, Layer::ARP)?;
+        let htype = u16::from_be_bytes([hdr[0], hdr[1]]);
+        if htype != 1 {
+            return Err(ParseError::UnknownType {
+                layer: Layer::ARP,
+                offset: 0,
+                value: htype as u32,
+            });
+        }
+        let (ptype, _) = EtherKind::from_bytes(&hdr[2..4])?;
+        let (hlen, plen) = (hdr[4], hdr[5]);
+        if (hlen, plen) != (6, 4) {
+            return Err(ParseError::BadLength {
+                layer: Layer::ARP,
+                offset: 4,
+                len: if hlen != 6 { hlen } else { plen } as usize,
+            });
+        }
+        let oper = u16::from_be_bytes([hdr[6], hdr[7]]);
+
+        Ok((
+            ArpHdr {
+                htype,
+                ptype,
+                hlen,
+                plen,
+                oper,
+                sha: hdr[8..14].try_into().unwrap(),
+                spa: hdr[14..18].try_into().unwrap(),
+                tha: hdr[18..24].try_into().unwrap(),
+                tpa: hdr[24..28].try_into().unwrap(),
+            },
+            rest,
+        ))
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(self.htype.to_be_bytes());
+        bytes.extend(self.ptype.to_bytes());
+        bytes.push(self.hlen);
+        bytes.push(self.plen);
+        bytes.extend(self.oper.to_be_bytes());
+        bytes.extend_from_slice(&self.sha);
+        bytes.extend_from_slice(&self.spa);
+        bytes.extend_from_slice(&self.tha);
+        bytes.extend_from_slice(&self.tpa);
+        bytes
+    }
+}
@@ -0,0 +1,95 @@
+//! 互联网校验和（RFC 1071）及其增量更新（RFC 1624）
+
+use std::fmt::Display;
+
+use crate::i18n::msg;
+
+/// 按 16 位大端字求反码和，长度为奇数时末尾补 0
+pub fn sum(bytes: &[u8]) -> u16 {
+    let sum = bytes
+        .chunks(2)
+        .map(|bs| (bs[0] as u64) << 8 | bs.get(1).copied().unwrap_or(0) as u64)
+        .sum::<u64>();
+    fold(sum)
+}
+
+/// 将进位折叠回低 16 位
+fn fold(mut sum: u64) -> u16 {
+    while sum >> 16 != 0 {
+        sum = (sum >> 16) + (sum & 0xffff);
+    }
+    sum as u16
+}
+
+/// 数据的校验和，计算前校验和字段应置 0
+pub fn checksum(bytes: &[u8]) -> u16 {
+    !sum(bytes)
+}
+
+/// 包括校验和字段在内的数据是否校验正确
+pub fn verify(bytes: &[u8]) -> bool {
+    sum(bytes) == 0xffff
+}
+
+/// 将数据中的一个 16 位字从 `old` 改为 `new` 后，由原校验和 `chksum` 增量计算新的校验和。
+/// 按 RFC 1624 的公式 HC' = ~(~HC + ~m + m') 计算，原校验和错误时结果仍然错误。
+pub fn update(chksum: u16, old: u16, new: u16) -> u16 {
+    !fold(!chksum as u64 + !old as u64 + new as u64)
+}
+
+/// 将数据从 `old` 改为等长的 `new` 后增量计算新的校验和，只处理有变化的字
+pub fn update_bytes(chksum: u16, old: &[u8], new: &[u8]) -> u16 {
+    let word = |bs: &[u8]| (bs[0] as u16) << 8 | bs.get(1).copied().unwrap_or(0) as u16;
+    old.chunks(2)
+        .zip(new.chunks(2))
+        .filter(|(a, b)| a != b)
+        .fold(chksum, |chksum, (a, b)| update(chksum, word(a), word(b)))
+}
+
+/// 接收到的报文中校验和的检验结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Valid,
+    /// 校验和错误，`expected` 为按报文内容计算出的值
+    Invalid {
+        expected: u16,
+    },
+    /// 本机发出的报文由网卡计算校验和，捕获时尚未填入
+    Offloaded,
+    /// 校验和为 0，表示发送方没有计算（仅 IPv4 中的 UDP）
+    Absent,
+    /// 报文被截断，无法检验
+    Truncated,
+}
+
+impl Verdict {
+    /// 由检验结果得出结论。`offloaded` 表示该帧的传输层校验和交由网卡计算，
+    /// `expected` 只在校验和错误时调用。
+    pub fn new(valid: bool, offloaded: bool, expected: impl FnOnce() -> u16) -> Self {
+        match (valid, offloaded) {
+            (true, _) => Verdict::Valid,
+            (false, true) => Verdict::Offloaded,
+            (false, false) => Verdict::Invalid {
+                expected: expected(),
+            },
+        }
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        matches!(self, Verdict::Invalid { .. })
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Valid => write!(f, "{}", msg!("checksum.valid")),
+            Verdict::Invalid { expected } => {
+                write!(f, "{}", msg!("checksum.invalid", expected = expected))
+            }
+            Verdict::Offloaded => write!(f, "{}", msg!("checksum.offloaded")),
+            Verdict::Absent => write!(f, "{}", msg!("checksum.absent")),
+            Verdict::Truncated => write!(f, "{}", msg!("checksum.truncated")),
+        }
+    }
+}
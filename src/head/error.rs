@@ -0,0 +1,185 @@
+use std::fmt::Display;
+
+use crate::i18n::msg;
+
+/// 解析出错的协议层
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Layer {
+    Ether,
+    ARP,
+    IP,
+    IPv6,
+    ICMP,
+    ICMPv6,
+    UDP,
+    TCP,
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Layer::Ether => "Ethernet",
+            Layer::ARP => "ARP",
+            Layer::IP => "IPv4",
+            Layer::IPv6 => "IPv6",
+            Layer::ICMP => "ICMP",
+            Layer::ICMPv6 => "ICMPv6",
+            Layer::UDP => "UDP",
+            Layer::TCP => "TCP",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// 首部解析错误。`offset` 为出错位置相对于报文起始的字节偏移。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// 剩余字节不足以容纳该层首部
+    Truncated {
+        layer: Layer,
+        offset: usize,
+        need: usize,
+        have: usize,
+    },
+    /// 版本号不符
+    BadVersion {
+        layer: Layer,
+        offset: usize,
+        version: u8,
+    },
+    /// 首部长度字段非法
+    BadIhl {
+        layer: Layer,
+        offset: usize,
+        ihl: u8,
+    },
+    /// 长度字段与首部不一致
+    BadLength {
+        layer: Layer,
+        offset: usize,
+        len: usize,
+    },
+    /// 无法识别的类型字段
+    UnknownType {
+        layer: Layer,
+        offset: usize,
+        value: u32,
+    },
+}
+
+impl ParseError {
+    #[allow(dead_code)]
+    pub fn layer(&self) -> Layer {
+        match *self {
+            ParseError::Truncated { layer, .. }
+            | ParseError::BadVersion { layer, .. }
+            | ParseError::BadIhl { layer, .. }
+            | ParseError::BadLength { layer, .. }
+            | ParseError::UnknownType { layer, .. } => layer,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn offset(&self) -> usize {
+        match *self {
+            ParseError::Truncated { offset, .. }
+            | ParseError::BadVersion { offset, .. }
+            | ParseError::BadIhl { offset, .. }
+            | ParseError::BadLength { offset, .. }
+            | ParseError::UnknownType { offset, .. } => offset,
+        }
+    }
+
+    /// 将偏移量后移 `n` 字节，用于外层首部向上传递内层错误
+    pub fn shift(mut self, n: usize) -> Self {
+        match &mut self {
+            ParseError::Truncated { offset, .. }
+            | ParseError::BadVersion { offset, .. }
+            | ParseError::BadIhl { offset, .. }
+            | ParseError::BadLength { offset, .. }
+            | ParseError::UnknownType { offset, .. } => *offset += n,
+        }
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Truncated {
+                layer,
+                offset,
+                need,
+                have,
+            } => write!(
+                f,
+                "{}",
+                msg!(
+                    "error.truncated",
+                    layer = layer,
+                    offset = offset,
+                    need = need,
+                    have = have
+                )
+            ),
+            ParseError::BadVersion {
+                layer,
+                offset,
+                version,
+            } => write!(
+                f,
+                "{}",
+                msg!(
+                    "error.bad_version",
+                    layer = layer,
+                    offset = offset,
+                    version = version
+                )
+            ),
+            ParseError::BadIhl { layer, offset, ihl } => {
+                let text = msg!("error.bad_ihl", layer = layer, offset = offset, ihl = ihl);
+                write!(f, "{text}")
+            }
+            ParseError::BadLength { layer, offset, len } => {
+                let text = msg!(
+                    "error.bad_length",
+                    layer = layer,
+                    offset = offset,
+                    len = len
+                );
+                write!(f, "{text}")
+            }
+            ParseError::UnknownType {
+                layer,
+                offset,
+                value,
+            } => {
+                let value = format!("{value:#x}");
+                let text = msg!(
+                    "error.unknown_type",
+                    layer = layer,
+                    offset = offset,
+                    value = value
+                );
+                write!(f, "{text}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 从 `bytes` 头部切出 `n` 字节，不足时返回 [`ParseError::Truncated`]
+pub fn take(bytes: &[u8], n: usize, layer: Layer) -> Result<(&[u8], &[u8]), ParseError> {
+    if bytes.len() < n {
+        Err(ParseError::Truncated {
+            layer,
+            offset: 0,
+            need: n,
+            have: bytes.len(),
+        })
+    } else {
+        Ok(bytes.split_at(n))
+    }
+}
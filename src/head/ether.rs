@@ -1,11 +1,44 @@
 use std::fmt::Display;
 
-use super::Header;
+use super::{take, Header, Layer, ParseError};
+use crate::i18n::msg;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// 类型字段小于该值时为 802.3 帧的长度字段
+const ETHER_TYPE_MIN: u16 = 0x0600;
+
+/// 以太网帧的类型字段
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum EtherKind {
     IP,
     ARP,
+    /// 网络唤醒魔术包
+    WakeOnLan,
+    /// 逆地址解析
+    RARP,
+    /// 802.1Q VLAN 标签
+    Vlan,
+    IPv6,
+    /// MPLS 单播
+    MPLS,
+    /// MPLS 组播
+    MPLSMulticast,
+    /// PPPoE 发现阶段
+    PPPoEDiscovery,
+    /// PPPoE 会话阶段
+    PPPoESession,
+    /// 802.1X 认证
+    EAPOL,
+    /// 802.1ad 服务 VLAN 标签
+    QinQ,
+    /// 链路层发现协议
+    LLDP,
+    /// 802.1AE 链路加密
+    MACsec,
+    /// 精确时间协议
+    PTP,
+    /// 802.3 帧的长度字段（小于 0x0600），其后为 LLC 首部
+    Length(u16),
     Other(u16),
 }
 
@@ -14,55 +47,326 @@ impl EtherKind {
         match org {
             0x0800 => EtherKind::IP,
             0x0806 => EtherKind::ARP,
+            0x0842 => EtherKind::WakeOnLan,
+            0x8035 => EtherKind::RARP,
+            TPID_CTAG => EtherKind::Vlan,
+            0x86DD => EtherKind::IPv6,
+            0x8847 => EtherKind::MPLS,
+            0x8848 => EtherKind::MPLSMulticast,
+            0x8863 => EtherKind::PPPoEDiscovery,
+            0x8864 => EtherKind::PPPoESession,
+            0x888E => EtherKind::EAPOL,
+            TPID_STAG => EtherKind::QinQ,
+            0x88CC => EtherKind::LLDP,
+            0x88E5 => EtherKind::MACsec,
+            0x88F7 => EtherKind::PTP,
+            len if len < ETHER_TYPE_MIN => EtherKind::Length(len),
             org => EtherKind::Other(org),
         }
     }
 }
 
+impl From<EtherKind> for u16 {
+    fn from(etype: EtherKind) -> Self {
+        match etype {
+            EtherKind::IP => 0x0800,
+            EtherKind::ARP => 0x0806,
+            EtherKind::WakeOnLan => 0x0842,
+            EtherKind::RARP => 0x8035,
+            EtherKind::Vlan => TPID_CTAG,
+            EtherKind::IPv6 => 0x86DD,
+            EtherKind::MPLS => 0x8847,
+            EtherKind::MPLSMulticast => 0x8848,
+            EtherKind::PPPoEDiscovery => 0x8863,
+            EtherKind::PPPoESession => 0x8864,
+            EtherKind::EAPOL => 0x888E,
+            EtherKind::QinQ => TPID_STAG,
+            EtherKind::LLDP => 0x88CC,
+            EtherKind::MACsec => 0x88E5,
+            EtherKind::PTP => 0x88F7,
+            EtherKind::Length(org) | EtherKind::Other(org) => org,
+        }
+    }
+}
+
 impl Header for EtherKind {
-    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]) {
-        let (b, rest) = bytes.split_at(2);
-        let org = ((b[0] as u16) << 8) + (b[1] as u16);
-        (Self::new(org), rest)
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (b, rest) = take(bytes, 2, Layer::Ether)?;
+        Ok((Self::new(u16::from_be_bytes([b[0], b[1]])), rest))
+    }
+    fn to_bytes(self) -> Vec<u8> {
+        u16::from(self).to_be_bytes().to_vec()
+    }
+}
+
+/// SNAP 扩展：组织标识与该组织定义的协议号。组织标识为 0 时协议号即以太网类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snap {
+    pub oui: [u8; 3],
+    pub pid: u16,
+}
+
+/// IEEE 802.2 LLC 首部，出现在以长度字段代替类型字段的 802.3 帧中
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlcHdr {
+    /// 目的服务访问点
+    pub dsap: u8,
+    /// 源服务访问点
+    pub ssap: u8,
+    /// 控制字段，低字节在前。最低两位为 11 的 U 帧占 8 位，I 帧和 S 帧占 16 位。
+    pub control: u16,
+    /// DSAP、SSAP 均为 0xAA 且控制字段为 UI 帧时紧随其后的 SNAP 扩展
+    pub snap: Option<Snap>,
+}
+
+impl LlcHdr {
+    /// 控制字段是否只占一个字节
+    fn is_unnumbered(control: u8) -> bool {
+        control & 0x03 == 0x03
+    }
+
+    /// 首部长度，包括 SNAP 扩展
+    pub fn size(&self) -> usize {
+        let control = if Self::is_unnumbered(self.control as u8) {
+            1
+        } else {
+            2
+        };
+        2 + control + self.snap.map_or(0, |_| 5)
+    }
+}
+
+impl Header for LlcHdr {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, rest) = take(bytes, 3, Layer::Ether)?;
+        let (dsap, ssap) = (hdr[0], hdr[1]);
+        let (control, rest) = if Self::is_unnumbered(hdr[2]) {
+            (hdr[2] as u16, rest)
+        } else {
+            let (hi, rest) = take(rest, 1, Layer::Ether).map_err(|e| e.shift(3))?;
+            (u16::from_le_bytes([hdr[2], hi[0]]), rest)
+        };
+        let (snap, rest) = if (dsap, ssap, control) == (0xAA, 0xAA, 0x03) {
+            let offset = bytes.len() - rest.len();
+            let (ext, rest) = take(rest, 5, Layer::Ether).map_err(|e| e.shift(offset))?;
+            let snap = Snap {
+                oui: ext[..3].try_into().unwrap(),
+                pid: u16::from_be_bytes([ext[3], ext[4]]),
+            };
+            (Some(snap), rest)
+        } else {
+            (None, rest)
+        };
+        Ok((
+            Self {
+                dsap,
+                ssap,
+                control,
+                snap,
+            },
+            rest,
+        ))
     }
     fn to_bytes(self) -> Vec<u8> {
-        match self {
-            EtherKind::IP => [0x08, 0x00],
-            EtherKind::ARP => [0x08, 0x06],
-            EtherKind::Other(org) => org.to_le_bytes(),
+        let mut bytes = vec![self.dsap, self.ssap];
+        let control = self.control.to_le_bytes();
+        if Self::is_unnumbered(control[0]) {
+            bytes.push(control[0]);
+        } else {
+            bytes.extend(control);
+        }
+        if let Some(snap) = self.snap {
+            bytes.extend(snap.oui);
+            bytes.extend(snap.pid.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+impl Display for LlcHdr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = msg!(
+            "ether.llc",
+            dsap = format!("{:#04x}", self.dsap),
+            ssap = format!("{:#04x}", self.ssap),
+            control = format!("{:#x}", self.control)
+        );
+        write!(f, "{text}")?;
+        if let Some(snap) = self.snap {
+            let oui = snap.oui.map(|c| format!("{:02x}", c)).join(":");
+            let pid = format!("{:#06x}", snap.pid);
+            write!(f, "{}", msg!("ether.snap", oui = oui, pid = pid))?;
+        }
+        Ok(())
+    }
+}
+
+/// 802.1Q 客户 VLAN 标签的 TPID
+pub const TPID_CTAG: u16 = 0x8100;
+/// 802.1ad 服务 VLAN 标签（QinQ 外层）的 TPID
+pub const TPID_STAG: u16 = 0x88A8;
+
+/// 802.1Q VLAN 标签
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VlanTag {
+    /// 标签协议标识，占 16 位，为 [`TPID_CTAG`] 或 [`TPID_STAG`]。
+    pub tpid: u16,
+    /// 优先级，占 3 位。
+    pub pcp: u8,
+    /// 可丢弃标志，占 1 位。
+    pub dei: bool,
+    /// VLAN ID，占 12 位。
+    pub vid: u16,
+}
+
+impl VlanTag {
+    pub fn new(vid: u16) -> Self {
+        Self {
+            tpid: TPID_CTAG,
+            pcp: 0,
+            dei: false,
+            vid: vid & 0x0fff,
+        }
+    }
+
+    /// 由标签协议标识与 16 位标签控制信息构造
+    pub fn from_tci(tpid: u16, tci: u16) -> Self {
+        Self {
+            tpid,
+            pcp: (tci >> 13) as u8,
+            dei: tci & 0x1000 != 0,
+            vid: tci & 0x0fff,
+        }
+    }
+
+    /// 16 位标签控制信息
+    pub fn tci(&self) -> u16 {
+        (self.pcp as u16 & 0x07) << 13 | (self.dei as u16) << 12 | (self.vid & 0x0fff)
+    }
+
+    /// `tpid` 是否为 VLAN 标签的协议标识
+    pub fn is_tpid(tpid: u16) -> bool {
+        matches!(tpid, TPID_CTAG | TPID_STAG)
+    }
+}
+
+impl Display for VlanTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = msg!(
+            "ether.vlan",
+            vid = self.vid,
+            tpid = format!("{:#06x}", self.tpid),
+            pcp = self.pcp
+        );
+        write!(f, "{text}")?;
+        if self.dei {
+            write!(f, "{}", msg!("ether.vlan_dei"))?;
         }
-        .to_vec()
+        write!(f, ")")
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EtherHdr {
     pub dhost: [u8; 6],
     pub shost: [u8; 6],
+    /// 按由外到内的顺序排列的 VLAN 标签
+    pub vlans: Vec<VlanTag>,
     pub etype: EtherKind,
+    /// 802.3 帧（类型字段为 [`EtherKind::Length`]）的 LLC 首部
+    pub llc: Option<LlcHdr>,
+}
+
+impl EtherHdr {
+    pub fn new(dhost: [u8; 6], shost: [u8; 6], etype: EtherKind) -> Self {
+        Self {
+            dhost,
+            shost,
+            vlans: vec![],
+            etype,
+            llc: None,
+        }
+    }
+
+    pub fn vlans(self, vlans: Vec<VlanTag>) -> Self {
+        Self { vlans, ..self }
+    }
+
+    /// 首部长度，包括 VLAN 标签与 LLC 首部
+    pub fn size(&self) -> usize {
+        14 + self.vlans.len() * 4 + self.llc.map_or(0, |llc| llc.size())
+    }
+
+    /// 上层协议的类型：Ethernet II 帧的类型字段，或 802.3 帧中以 SNAP 扩展封装的以太网类型
+    pub fn ethertype(&self) -> EtherKind {
+        match self.llc {
+            Some(LlcHdr {
+                snap:
+                    Some(Snap {
+                        oui: [0, 0, 0],
+                        pid,
+                    }),
+                ..
+            }) => EtherKind::new(pid),
+            _ => self.etype,
+        }
+    }
 }
 
 impl Header for EtherHdr {
-    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]) {
-        let (dhost, bytes) = bytes.split_at(6);
-        let (shost, bytes) = bytes.split_at(6);
-        let (etype, rest) = EtherKind::from_bytes(bytes);
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, mut rest) = take(bytes, 12, Layer::Ether)?;
+        let dhost = hdr[..6].try_into().unwrap();
+        let shost = hdr[6..].try_into().unwrap();
 
-        let dhost = dhost.try_into().unwrap();
-        let shost = shost.try_into().unwrap();
+        // 标签可以层层叠加，直到遇到真正的类型字段
+        let mut vlans = vec![];
+        loop {
+            let offset = bytes.len() - rest.len();
+            let (tpid, tail) = take(rest, 2, Layer::Ether).map_err(|e| e.shift(offset))?;
+            let tpid = u16::from_be_bytes([tpid[0], tpid[1]]);
+            if !VlanTag::is_tpid(tpid) {
+                break;
+            }
+            let (tci, tail) = take(tail, 2, Layer::Ether).map_err(|e| e.shift(offset + 2))?;
+            vlans.push(VlanTag::from_tci(
+                tpid,
+                u16::from_be_bytes([tci[0], tci[1]]),
+            ));
+            rest = tail;
+        }
+        let offset = bytes.len() - rest.len();
+        let (etype, rest) = EtherKind::from_bytes(rest).map_err(|e| e.shift(offset))?;
+        let (llc, rest) = match etype {
+            EtherKind::Length(_) => {
+                let (llc, rest) = LlcHdr::from_bytes(rest).map_err(|e| e.shift(offset + 2))?;
+                (Some(llc), rest)
+            }
+            _ => (None, rest),
+        };
 
-        (
+        Ok((
             Self {
                 dhost,
                 shost,
+                vlans,
                 etype,
+                llc,
             },
             rest,
-        )
+        ))
     }
     fn to_bytes(self) -> Vec<u8> {
         let etype = self.etype.to_bytes();
         let mut bytes = [self.dhost, self.shost].concat().to_vec();
+        for tag in &self.vlans {
+            bytes.extend(tag.tpid.to_be_bytes());
+            bytes.extend(tag.tci().to_be_bytes());
+        }
         bytes.extend(etype);
+        if let Some(llc) = self.llc {
+            bytes.extend(llc.to_bytes());
+        }
         bytes
     }
 }
@@ -81,6 +385,13 @@ impl Display for EtherHdr {
             .map(|&c| format!("{:02x}", c))
             .collect::<Vec<String>>()
             .join(":");
-        write!(f, "type: {:?} => MAC:{} >> {}", self.etype, shost, dhost)
+        write!(f, "type: {:?} => MAC:{} >> {}", self.etype, shost, dhost)?;
+        for tag in &self.vlans {
+            write!(f, ", {tag}")?;
+        }
+        if let Some(llc) = &self.llc {
+            write!(f, ", {llc}")?;
+        }
+        Ok(())
     }
 }
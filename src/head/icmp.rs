@@ -1,4 +1,5 @@
-use super::Header;
+use super::{checksum, take, Header, IPHdr, Layer, ParseError};
+use crate::i18n::{self, msg};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Ping {
@@ -7,6 +8,7 @@ pub struct Ping {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct ICMP {
     pub typ: u8,
     pub code: u8,
@@ -46,80 +48,65 @@ impl ICMP {
         }
     }
 
-    pub fn checksum(mut self, data:&[u8]) -> Self {
+    pub fn checksum(mut self, data: &[u8]) -> Self {
         self.chksum = 0;
         let mut bytes = self.clone().to_bytes();
         bytes.extend_from_slice(data);
+        self.chksum = checksum::checksum(&bytes);
+        self
+    }
 
-        let mut sum: u32 = bytes
-            .chunks(2)
-            .map(|bs| (bs[0] as u32) << 8 | bs[1] as u32)
-            .sum();
-
-        while sum >> 16 != 0 {
-            sum = (sum >> 16) + (sum & 0xffff);
-        }
-
-        self.chksum = !sum as u16;
+    /// 报文首部与数据 `data` 的校验和是否正确
+    pub fn verify(&self, data: &[u8]) -> bool {
+        let mut bytes = self.clone().to_bytes();
+        bytes.extend_from_slice(data);
+        checksum::verify(&bytes)
+    }
 
-        self
+    /// 是否为差错报告报文
+    pub fn is_error(&self) -> bool {
+        matches!(self.typ, 3 | 4 | 5 | 11 | 12)
     }
 
+    /// 解析差错报告报文中引用的原始 IP 首部，返回该首部及其后的数据（至少 8 字节）。
+    /// `rest` 为 [`ICMP::from_bytes`] 返回的剩余字节。
+    pub fn quoted<'a>(&self, rest: &'a [u8]) -> Result<(IPHdr, &'a [u8]), ParseError> {
+        if !self.is_error() {
+            return Err(ParseError::UnknownType {
+                layer: Layer::ICMP,
+                offset: 0,
+                value: self.typ as u32,
+            });
+        }
+        // 类型和代码之后的 4 字节为未用字段、指针或网关地址
+        let (_, quoted) = take(rest, 4, Layer::ICMP).map_err(|e| e.shift(4))?;
+        IPHdr::from_bytes(quoted).map_err(|e| e.shift(8))
+    }
 
+    /// 类型与代码的说明，取自消息目录中的 `icmp.<类型>.<代码>`
     pub fn typ_dsc(&self) -> String {
-        match (self.typ, self.code) {
-            (0, 0) => "回显应答（ping应答）",
-            (8, 0) => "请求回显（ping请求）",
-            (9, 0) => "路由器通告",
-            (10, 0) => "路由器请求",
-            (13, 0) => "时间戳请求（目前已不使用）",
-            (14, 0) => "时间戳应答（目前已不使用）",
-            (15, 0) => "信息请求（目前已不使用）",
-            (16, 0) => "信息应答（目前已不使用）",
-            (17, 0) => "地址掩码请求",
-            (18, 0) => "地址掩码应答",
-            (3, 0) => "网络不可达",
-            (3, 1) => "主机不可达",
-            (3, 2) => "协议不可达",
-            (3, 3) => "端口不可达",
-            (3, 6) => "目的网络不认识",
-            (3, 7) => "目的主机不认识",
-            (3, 9) => "目的网络被强制禁止",
-            (3, 10) => "目的主机被强制隔离",
-            (3, 11) => "由于TOS,网络不可达",
-            (3, 12) => "由于TOS,主机不可达",
-            (3, 13) => "由于过滤，通信被强制禁止",
-            (4, 0) => "源端被关闭",
-            (5, 0) => "对网络重定向",
-            (5, 1) => "对主机重定向",
-            (5, 2) => "对服务类型和网络重定向",
-            (5, 3) => "对服务类型和主机重定向",
-            (11, 0) => "传输期间生存时间为0",
-            (11, 1) => "在数据报组装期间生存时间为0",
-            (12, 0) => "坏的IP首部",
-            (12, 1) => "缺少必须的选项",
-            _ => "未定义",
-        }
-        .to_string()
+        i18n::get(&format!("icmp.{}.{}", self.typ, self.code))
+            .unwrap_or(msg!("icmp.undefined"))
+            .to_string()
     }
 }
 
 impl Header for ICMP {
-    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]) {
-        let (hdr, bytes) = bytes.split_at(4);
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, bytes) = take(bytes, 4, Layer::ICMP)?;
         let typ = hdr[0];
         let code = hdr[1];
-        let chksum = u16::from_be_bytes(hdr[2..4].try_into().unwrap());
+        let chksum = u16::from_be_bytes([hdr[2], hdr[3]]);
         let (msg, rest) = if matches!(typ, 0 | 8 | 9 | 10 | 13 | 14 | 15 | 16 | 17 | 18) {
-            let (dada, rest) = bytes.split_at(4);
-            let ident = u16::from_be_bytes(dada[0..2].try_into().unwrap());
-            let seqnum = u16::from_be_bytes(dada[2..4].try_into().unwrap());
+            let (dada, rest) = take(bytes, 4, Layer::ICMP).map_err(|e| e.shift(4))?;
+            let ident = u16::from_be_bytes([dada[0], dada[1]]);
+            let seqnum = u16::from_be_bytes([dada[2], dada[3]]);
             (Some(Ping { ident, seqnum }), rest)
         } else {
             (None, bytes)
         };
 
-        (
+        Ok((
             ICMP {
                 typ,
                 code,
@@ -127,7 +114,7 @@ impl Header for ICMP {
                 msg,
             },
             rest,
-        )
+        ))
     }
 
     fn to_bytes(self) -> Vec<u8> {
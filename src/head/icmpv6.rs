@@ -0,0 +1,431 @@
+use std::{fmt::Display, net::Ipv6Addr};
+
+use super::{checksum, take, Header, Ipv6Hdr, Layer, ParseError, Ping, PseudoHeader};
+use crate::i18n::{self, msg};
+
+/// 回显请求
+pub const ICMPV6_ECHO_REQUEST: u8 = 128;
+/// 回显应答
+pub const ICMPV6_ECHO_REPLY: u8 = 129;
+/// 路由器请求
+pub const ND_ROUTER_SOLICIT: u8 = 133;
+/// 路由器通告
+pub const ND_ROUTER_ADVERT: u8 = 134;
+/// 邻居请求
+pub const ND_NEIGHBOR_SOLICIT: u8 = 135;
+/// 邻居通告
+pub const ND_NEIGHBOR_ADVERT: u8 = 136;
+
+/// 邻居发现报文携带的选项（RFC 4861 第 4.6 节）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NdOption {
+    /// 源链路层地址
+    SourceLinkAddr([u8; 6]),
+    /// 目标链路层地址
+    TargetLinkAddr([u8; 6]),
+    /// 前缀信息。`onlink` 表示前缀在链路上，`autonomous` 表示可用于无状态地址自动配置；
+    /// `reserved1` 为标志字节中其余的 6 位，`reserved2` 为首选生存期之后的 4 个保留字节。
+    PrefixInfo {
+        prefix_len: u8,
+        onlink: bool,
+        autonomous: bool,
+        reserved1: u8,
+        valid: u32,
+        preferred: u32,
+        reserved2: u32,
+        prefix: [u8; 16],
+    },
+    /// 链路 MTU，`reserved` 为类型和长度之后的 2 个保留字节
+    Mtu { reserved: u16, mtu: u32 },
+    /// 无法识别的选项，`data` 不含类型和长度字段
+    Unknown { kind: u8, data: Vec<u8> },
+}
+
+impl NdOption {
+    /// 解析完整的选项区域，`at` 为选项区在报文中的偏移
+    fn parse_all(mut bytes: &[u8], at: usize) -> Result<Vec<NdOption>, ParseError> {
+        let total = bytes.len();
+        let mut options = vec![];
+        while let Some(&kind) = bytes.first() {
+            let at = at + total - bytes.len();
+            // 长度以 8 字节为单位，为 0 的选项是非法的
+            let len = match bytes.get(1) {
+                Some(&len) if len > 0 && len as usize * 8 <= bytes.len() => len as usize * 8,
+                _ => {
+                    return Err(ParseError::BadLength {
+                        layer: Layer::ICMPv6,
+                        offset: at + 1,
+                        len: bytes.get(1).copied().unwrap_or(0) as usize * 8,
+                    })
+                }
+            };
+            let (opt, rest) = bytes.split_at(len);
+            let data = &opt[2..];
+            options.push(match kind {
+                1 if len == 8 => NdOption::SourceLinkAddr(data[..6].try_into().unwrap()),
+                2 if len == 8 => NdOption::TargetLinkAddr(data[..6].try_into().unwrap()),
+                3 if len == 32 => NdOption::PrefixInfo {
+                    prefix_len: data[0],
+                    onlink: data[1] & 0x80 != 0,
+                    autonomous: data[1] & 0x40 != 0,
+                    reserved1: data[1] & 0x3f,
+                    valid: u32::from_be_bytes(data[2..6].try_into().unwrap()),
+                    preferred: u32::from_be_bytes(data[6..10].try_into().unwrap()),
+                    reserved2: u32::from_be_bytes(data[10..14].try_into().unwrap()),
+                    prefix: data[14..30].try_into().unwrap(),
+                },
+                5 if len == 8 => NdOption::Mtu {
+                    reserved: u16::from_be_bytes([data[0], data[1]]),
+                    mtu: u32::from_be_bytes(data[2..6].try_into().unwrap()),
+                },
+                1 | 2 | 3 | 5 => {
+                    return Err(ParseError::BadLength {
+                        layer: Layer::ICMPv6,
+                        offset: at + 1,
+                        len,
+                    })
+                }
+                kind => NdOption::Unknown {
+                    kind,
+                    data: data.to_vec(),
+                },
+            });
+            bytes = rest;
+        }
+        Ok(options)
+    }
+
+    /// 选项的长度，包括类型与长度字段
+    pub fn size(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let (kind, data) = match self {
+            NdOption::SourceLinkAddr(mac) => (1, mac.to_vec()),
+            NdOption::TargetLinkAddr(mac) => (2, mac.to_vec()),
+            NdOption::PrefixInfo {
+                prefix_len,
+                onlink,
+                autonomous,
+                reserved1,
+                valid,
+                preferred,
+                reserved2,
+                prefix,
+            } => {
+                let flags = (*onlink as u8) << 7 | (*autonomous as u8) << 6 | (reserved1 & 0x3f);
+                let mut data = vec![*prefix_len, flags];
+                data.extend(valid.to_be_bytes());
+                data.extend(preferred.to_be_bytes());
+                data.extend(reserved2.to_be_bytes());
+                data.extend(prefix);
+                (3, data)
+            }
+            NdOption::Mtu { reserved, mtu } => {
+                let mut data = reserved.to_be_bytes().to_vec();
+                data.extend(mtu.to_be_bytes());
+                (5, data)
+            }
+            NdOption::Unknown { kind, data } => (*kind, data.clone()),
+        };
+        let len = (data.len() + 2).div_ceil(8);
+        let mut bytes = vec![kind, len as u8];
+        bytes.extend(data);
+        bytes.resize(len * 8, 0);
+        bytes
+    }
+}
+
+impl Display for NdOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mac = |mac: &[u8; 6]| {
+            mac.iter()
+                .map(|c| format!("{:02x}", c))
+                .collect::<Vec<_>>()
+                .join(":")
+        };
+        match self {
+            NdOption::SourceLinkAddr(addr) => {
+                write!(f, "{}", msg!("nd.source_link_addr", addr = mac(addr)))
+            }
+            NdOption::TargetLinkAddr(addr) => {
+                write!(f, "{}", msg!("nd.target_link_addr", addr = mac(addr)))
+            }
+            NdOption::PrefixInfo {
+                prefix_len,
+                onlink,
+                autonomous,
+                valid,
+                preferred,
+                prefix,
+                ..
+            } => {
+                let text = msg!(
+                    "nd.prefix",
+                    prefix = Ipv6Addr::from(*prefix),
+                    len = prefix_len,
+                    onlink = *onlink as u8,
+                    autonomous = *autonomous as u8,
+                    valid = valid,
+                    preferred = preferred
+                );
+                write!(f, "{text}")
+            }
+            NdOption::Mtu { mtu, .. } => write!(f, "MTU {mtu}"),
+            NdOption::Unknown { kind, data } => {
+                let text = msg!("nd.unknown", kind = kind, len = data.len());
+                write!(f, "{text}")
+            }
+        }
+    }
+}
+
+/// ICMPv6 报文在类型、代码和校验和之后的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Icmpv6Msg {
+    /// 回显请求或应答
+    Echo(Ping),
+    /// 路由器请求，`reserved` 为 4 字节保留字段
+    RouterSolicit {
+        reserved: u32,
+        options: Vec<NdOption>,
+    },
+    /// 路由器通告。`managed` 和 `other` 分别表示通过 DHCPv6 获取地址和其他配置，
+    /// `flags` 为标志字节中其余的 6 位，包括默认路由器优先级（RFC 4191）等。
+    RouterAdvert {
+        hop_limit: u8,
+        managed: bool,
+        other: bool,
+        flags: u8,
+        lifetime: u16,
+        reachable: u32,
+        retrans: u32,
+        options: Vec<NdOption>,
+    },
+    /// 邻居请求，询问 `target` 的链路层地址。`reserved` 为 4 字节保留字段
+    NeighborSolicit {
+        reserved: u32,
+        target: [u8; 16],
+        options: Vec<NdOption>,
+    },
+    /// 邻居通告。`router` 表示发送方是路由器，`solicited` 表示是对请求的应答，
+    /// `override_` 表示应覆盖已有的缓存表项，`reserved` 为标志之后的 29 个保留位。
+    NeighborAdvert {
+        router: bool,
+        solicited: bool,
+        override_: bool,
+        reserved: u32,
+        target: [u8; 16],
+        options: Vec<NdOption>,
+    },
+}
+
+impl Icmpv6Msg {
+    /// 消息中携带的邻居发现选项
+    pub fn options(&self) -> &[NdOption] {
+        match self {
+            Icmpv6Msg::Echo(_) => &[],
+            Icmpv6Msg::RouterSolicit { options, .. }
+            | Icmpv6Msg::RouterAdvert { options, .. }
+            | Icmpv6Msg::NeighborSolicit { options, .. }
+            | Icmpv6Msg::NeighborAdvert { options, .. } => options,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Icmpv6 {
+    pub typ: u8,
+    pub code: u8,
+    pub chksum: u16,
+    /// 可识别类型的消息内容。其他类型的报文在 4 字节首部之后的内容作为剩余数据返回。
+    pub msg: Option<Icmpv6Msg>,
+}
+
+impl Icmpv6 {
+    pub fn new(typ: u8, code: u8) -> Self {
+        Self {
+            typ,
+            code,
+            chksum: 0,
+            msg: None,
+        }
+    }
+
+    /// 询问 `target` 链路层地址的邻居请求，附带本机地址 `smac`
+    pub fn neighbor_solicit(target: [u8; 16], smac: [u8; 6]) -> Self {
+        Self {
+            msg: Some(Icmpv6Msg::NeighborSolicit {
+                reserved: 0,
+                target,
+                options: vec![NdOption::SourceLinkAddr(smac)],
+            }),
+            ..Self::new(ND_NEIGHBOR_SOLICIT, 0)
+        }
+    }
+
+    /// 根据所在 IPv6 报文的伪首部与数据计算校验和
+    pub fn checksum(mut self, iphdr: &Ipv6Hdr, data: &[u8]) -> Self {
+        self.chksum = 0;
+        let msg = self.clone().to_bytes();
+        let mut bytes = iphdr.pseudo_header(msg.len() + data.len());
+        bytes.extend(msg);
+        bytes.extend_from_slice(data);
+        self.chksum = checksum::checksum(&bytes);
+        self
+    }
+
+    /// 所在 IPv6 报文的伪首部、报文与数据 `data` 的校验和是否正确
+    pub fn verify(&self, iphdr: &Ipv6Hdr, data: &[u8]) -> bool {
+        let msg = self.clone().to_bytes();
+        let mut bytes = iphdr.pseudo_header(msg.len() + data.len());
+        bytes.extend(msg);
+        bytes.extend_from_slice(data);
+        checksum::verify(&bytes)
+    }
+
+    /// 类型与代码的说明，取自消息目录中的 `icmpv6.<类型>.<代码>` 或 `icmpv6.<类型>`
+    pub fn typ_dsc(&self) -> String {
+        i18n::get(&format!("icmpv6.{}.{}", self.typ, self.code))
+            .or_else(|| i18n::get(&format!("icmpv6.{}", self.typ)))
+            .unwrap_or(msg!("icmp.undefined"))
+            .to_string()
+    }
+}
+
+impl Header for Icmpv6 {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, rest) = take(bytes, 4, Layer::ICMPv6)?;
+        let typ = hdr[0];
+        let code = hdr[1];
+        let chksum = u16::from_be_bytes([hdr[2], hdr[3]]);
+        let fixed = match typ {
+            ICMPV6_ECHO_REQUEST | ICMPV6_ECHO_REPLY | ND_ROUTER_SOLICIT => 4,
+            ND_ROUTER_ADVERT => 12,
+            ND_NEIGHBOR_SOLICIT | ND_NEIGHBOR_ADVERT => 20,
+            _ => 0,
+        };
+        let (body, rest) = take(rest, fixed, Layer::ICMPv6).map_err(|e| e.shift(4))?;
+        let at = 4 + fixed;
+        // 邻居发现报文的选项一直延续到报文末尾
+        let (msg, rest) = match typ {
+            ICMPV6_ECHO_REQUEST | ICMPV6_ECHO_REPLY => (
+                Some(Icmpv6Msg::Echo(Ping {
+                    ident: u16::from_be_bytes([body[0], body[1]]),
+                    seqnum: u16::from_be_bytes([body[2], body[3]]),
+                })),
+                rest,
+            ),
+            ND_ROUTER_SOLICIT => (
+                Some(Icmpv6Msg::RouterSolicit {
+                    reserved: u32::from_be_bytes(body[..4].try_into().unwrap()),
+                    options: NdOption::parse_all(rest, at)?,
+                }),
+                &rest[rest.len()..],
+            ),
+            ND_ROUTER_ADVERT => (
+                Some(Icmpv6Msg::RouterAdvert {
+                    hop_limit: body[0],
+                    managed: body[1] & 0x80 != 0,
+                    other: body[1] & 0x40 != 0,
+                    flags: body[1] & 0x3f,
+                    lifetime: u16::from_be_bytes([body[2], body[3]]),
+                    reachable: u32::from_be_bytes(body[4..8].try_into().unwrap()),
+                    retrans: u32::from_be_bytes(body[8..12].try_into().unwrap()),
+                    options: NdOption::parse_all(rest, at)?,
+                }),
+                &rest[rest.len()..],
+            ),
+            ND_NEIGHBOR_SOLICIT => (
+                Some(Icmpv6Msg::NeighborSolicit {
+                    reserved: u32::from_be_bytes(body[..4].try_into().unwrap()),
+                    target: body[4..20].try_into().unwrap(),
+                    options: NdOption::parse_all(rest, at)?,
+                }),
+                &rest[rest.len()..],
+            ),
+            ND_NEIGHBOR_ADVERT => (
+                Some(Icmpv6Msg::NeighborAdvert {
+                    router: body[0] & 0x80 != 0,
+                    solicited: body[0] & 0x40 != 0,
+                    override_: body[0] & 0x20 != 0,
+                    reserved: u32::from_be_bytes(body[..4].try_into().unwrap()) & 0x1fff_ffff,
+                    target: body[4..20].try_into().unwrap(),
+                    options: NdOption::parse_all(rest, at)?,
+                }),
+                &rest[rest.len()..],
+            ),
+            _ => (None, rest),
+        };
+
+        Ok((
+            Icmpv6 {
+                typ,
+                code,
+                chksum,
+                msg,
+            },
+            rest,
+        ))
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![self.typ, self.code];
+        bytes.extend(self.chksum.to_be_bytes());
+        let options = match self.msg {
+            None => vec![],
+            Some(Icmpv6Msg::Echo(ping)) => {
+                bytes.extend(ping.ident.to_be_bytes());
+                bytes.extend(ping.seqnum.to_be_bytes());
+                vec![]
+            }
+            Some(Icmpv6Msg::RouterSolicit { reserved, options }) => {
+                bytes.extend(reserved.to_be_bytes());
+                options
+            }
+            Some(Icmpv6Msg::RouterAdvert {
+                hop_limit,
+                managed,
+                other,
+                flags,
+                lifetime,
+                reachable,
+                retrans,
+                options,
+            }) => {
+                let flags = (managed as u8) << 7 | (other as u8) << 6 | (flags & 0x3f);
+                bytes.extend([hop_limit, flags]);
+                bytes.extend(lifetime.to_be_bytes());
+                bytes.extend(reachable.to_be_bytes());
+                bytes.extend(retrans.to_be_bytes());
+                options
+            }
+            Some(Icmpv6Msg::NeighborSolicit {
+                reserved,
+                target,
+                options,
+            }) => {
+                bytes.extend(reserved.to_be_bytes());
+                bytes.extend(target);
+                options
+            }
+            Some(Icmpv6Msg::NeighborAdvert {
+                router,
+                solicited,
+                override_,
+                reserved,
+                target,
+                options,
+            }) => {
+                let flags =
+                    (router as u32) << 31 | (solicited as u32) << 30 | (override_ as u32) << 29;
+                bytes.extend((flags | (reserved & 0x1fff_ffff)).to_be_bytes());
+                bytes.extend(target);
+                options
+            }
+        };
+        bytes.extend(options.iter().flat_map(NdOption::to_bytes));
+        bytes
+    }
+}
@@ -1,25 +1,321 @@
-use super::Header;
+use std::{fmt::Display, net::Ipv4Addr};
+
+use super::{checksum, take, Header, Layer, ParseError, PseudoHeader};
+use crate::i18n::msg;
 use Protocol::*;
 
+/// 选项区的最大长度
+const MAX_OPTIONS_LEN: usize = 40;
+
+/// IPv4 首部选项（RFC 791）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpOption {
+    /// 选项列表结束
+    Eol,
+    /// 无操作，用于对齐
+    Nop,
+    /// 记录路由。`pointer` 为下一个空位在选项中的位置（从 1 起算，最小为 4），
+    /// `route` 包括已记录的地址和预留的空位。
+    RecordRoute {
+        pointer: u8,
+        route: Vec<[u8; 4]>,
+    },
+    /// 宽松源路由，报文须依次经过 `route` 中的地址，中间可以经过其他路由器
+    LooseSourceRoute {
+        pointer: u8,
+        route: Vec<[u8; 4]>,
+    },
+    /// 严格源路由，报文只能经过 `route` 中的地址
+    StrictSourceRoute {
+        pointer: u8,
+        route: Vec<[u8; 4]>,
+    },
+    /// 网际时间戳。`flag` 为 0 时只记录时间戳，为 1 时同时记录地址，为 3 时只由预先指定的地址记录；
+    /// `overflow` 为因空间不足而没能记录的节点数。
+    Timestamp {
+        pointer: u8,
+        overflow: u8,
+        flag: u8,
+        stamps: Vec<(Option<[u8; 4]>, u32)>,
+    },
+    /// 安全选项（RFC 1108），包括保密级别和保护机构标志
+    Security {
+        level: u8,
+        authority: Vec<u8>,
+    },
+    /// 路由器警告（RFC 2113），值为 0 时要求路由器检查该报文
+    RouterAlert(u16),
+    Unknown {
+        kind: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl IpOption {
+    /// 选项类型字节
+    pub fn kind(&self) -> u8 {
+        match self {
+            IpOption::Eol => 0,
+            IpOption::Nop => 1,
+            IpOption::RecordRoute { .. } => 7,
+            IpOption::Timestamp { .. } => 68,
+            IpOption::Security { .. } => 130,
+            IpOption::LooseSourceRoute { .. } => 131,
+            IpOption::StrictSourceRoute { .. } => 137,
+            IpOption::RouterAlert(_) => 148,
+            IpOption::Unknown { kind, .. } => *kind,
+        }
+    }
+
+    /// 分片时是否复制到每个分片中（类型字节的最高位）
+    pub fn copied(&self) -> bool {
+        self.kind() & 0x80 != 0
+    }
+
+    /// 解析完整的选项区域，`EOL` 之后的字节视为填充，与选项一起返回
+    fn parse_all(mut bytes: &[u8]) -> Result<(Vec<IpOption>, Vec<u8>), ParseError> {
+        let total = bytes.len();
+        let mut options = vec![];
+        while let Some(&kind) = bytes.first() {
+            let at = 20 + total - bytes.len();
+            match kind {
+                0 => {
+                    options.push(IpOption::Eol);
+                    // 末尾的 0 在序列化时会重新补齐，不必保留
+                    let end = bytes.iter().rposition(|&b| b != 0).map_or(1, |i| i + 1);
+                    return Ok((options, bytes[1..end].to_vec()));
+                }
+                1 => {
+                    options.push(IpOption::Nop);
+                    bytes = &bytes[1..];
+                    continue;
+                }
+                _ => {}
+            }
+            let len = match bytes.get(1) {
+                Some(&len) if len >= 2 && len as usize <= bytes.len() => len as usize,
+                _ => {
+                    return Err(ParseError::BadLength {
+                        layer: Layer::IP,
+                        offset: at + 1,
+                        len: bytes.get(1).copied().unwrap_or(0) as usize,
+                    })
+                }
+            };
+            let (opt, rest) = bytes.split_at(len);
+            let data = &opt[2..];
+            let bad_len = || ParseError::BadLength {
+                layer: Layer::IP,
+                offset: at + 1,
+                len,
+            };
+            let route = |data: &[u8]| data[1..].chunks(4).map(|b| b.try_into().unwrap()).collect();
+            options.push(match kind {
+                7 | 131 | 137 if len >= 3 && (len - 3) % 4 == 0 => {
+                    let (pointer, route) = (data[0], route(data));
+                    match kind {
+                        7 => IpOption::RecordRoute { pointer, route },
+                        131 => IpOption::LooseSourceRoute { pointer, route },
+                        _ => IpOption::StrictSourceRoute { pointer, route },
+                    }
+                }
+                68 if len >= 4 => {
+                    let flag = data[1] & 0x0f;
+                    // 只记录时间戳时每项 4 字节，否则为地址加时间戳共 8 字节
+                    let size = if flag == 0 { 4 } else { 8 };
+                    if (len - 4) % size != 0 {
+                        return Err(bad_len());
+                    }
+                    let stamps = data[2..]
+                        .chunks(size)
+                        .map(|b| {
+                            let (addr, ts) = b.split_at(size - 4);
+                            let addr = (size == 8).then(|| addr.try_into().unwrap());
+                            (addr, u32::from_be_bytes(ts.try_into().unwrap()))
+                        })
+                        .collect();
+                    IpOption::Timestamp {
+                        pointer: data[0],
+                        overflow: data[1] >> 4,
+                        flag,
+                        stamps,
+                    }
+                }
+                130 if len >= 3 => IpOption::Security {
+                    level: data[0],
+                    authority: data[1..].to_vec(),
+                },
+                148 if len == 4 => IpOption::RouterAlert(u16::from_be_bytes([data[0], data[1]])),
+                7 | 68 | 130 | 131 | 137 | 148 => return Err(bad_len()),
+                kind => IpOption::Unknown {
+                    kind,
+                    data: data.to_vec(),
+                },
+            });
+            bytes = rest;
+        }
+        Ok((options, vec![]))
+    }
+
+    /// 选项的长度，包括类型与长度字段
+    pub fn size(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        let data = match self {
+            IpOption::Eol => return vec![0],
+            IpOption::Nop => return vec![1],
+            IpOption::RecordRoute { pointer, route }
+            | IpOption::LooseSourceRoute { pointer, route }
+            | IpOption::StrictSourceRoute { pointer, route } => {
+                let mut data = vec![*pointer];
+                data.extend(route.iter().flatten());
+                data
+            }
+            IpOption::Timestamp {
+                pointer,
+                overflow,
+                flag,
+                stamps,
+            } => {
+                let mut data = vec![*pointer, overflow << 4 | (flag & 0x0f)];
+                for (addr, ts) in stamps {
+                    if let Some(addr) = addr {
+                        data.extend(addr);
+                    }
+                    data.extend(ts.to_be_bytes());
+                }
+                data
+            }
+            IpOption::Security { level, authority } => {
+                let mut data = vec![*level];
+                data.extend(authority);
+                data
+            }
+            IpOption::RouterAlert(value) => value.to_be_bytes().to_vec(),
+            IpOption::Unknown { data, .. } => data.clone(),
+        };
+        // 超长的选项无法写出正确的长度，由调用者通过 `options_overflow` 检查
+        let len = u8::try_from(2 + data.len()).unwrap_or(u8::MAX);
+        let mut bytes = vec![self.kind(), len];
+        bytes.extend(data);
+        bytes
+    }
+}
+
+impl Display for IpOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 指针之前的地址是已经记录或经过的
+        let route = |f: &mut std::fmt::Formatter<'_>, name, pointer: u8, route: &[[u8; 4]]| {
+            let done = (pointer.saturating_sub(4) / 4) as usize;
+            let addrs = route
+                .iter()
+                .take(done)
+                .map(|&addr| Ipv4Addr::from(addr).to_string())
+                .collect::<Vec<_>>();
+            let text = msg!(
+                "ip.option.route",
+                name = name,
+                addrs = addrs.join(", "),
+                left = route.len().saturating_sub(done)
+            );
+            write!(f, "{text}")
+        };
+        match self {
+            IpOption::Eol => write!(f, "EOL"),
+            IpOption::Nop => write!(f, "NOP"),
+            IpOption::RecordRoute { pointer, route: r } => {
+                route(f, msg!("ip.option.rr"), *pointer, r)
+            }
+            IpOption::LooseSourceRoute { pointer, route: r } => {
+                route(f, msg!("ip.option.lsrr"), *pointer, r)
+            }
+            IpOption::StrictSourceRoute { pointer, route: r } => {
+                route(f, msg!("ip.option.ssrr"), *pointer, r)
+            }
+            IpOption::Timestamp {
+                pointer,
+                overflow,
+                flag,
+                stamps,
+            } => {
+                let size = if *flag == 0 { 4 } else { 8 };
+                let done = (pointer.saturating_sub(5) / size) as usize;
+                let stamps = stamps
+                    .iter()
+                    .take(done)
+                    .map(|(addr, ts)| match addr {
+                        Some(addr) => format!("{}@{ts}", Ipv4Addr::from(*addr)),
+                        None => ts.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                let text = msg!(
+                    "ip.option.timestamp",
+                    stamps = stamps.join(", "),
+                    flag = flag,
+                    overflow = overflow
+                );
+                write!(f, "{text}")
+            }
+            IpOption::Security { level, authority } => {
+                let text = msg!(
+                    "ip.option.security",
+                    level = format!("{level:#04x}"),
+                    authority = format!("{authority:02x?}")
+                );
+                write!(f, "{text}")
+            }
+            IpOption::RouterAlert(value) => {
+                write!(f, "{}", msg!("ip.option.router_alert", value = value))
+            }
+            IpOption::Unknown { kind, data } => write!(f, "kind={kind} {data:?}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct IPFlag {
+    /// 保留位，应为 0
+    pub rf: bool,
     /// 是否允许分片。取值为 0 时，表示允许分片
     pub df: bool,
     /// 是否还有分片正在传输，设置为 0 时，表示没有更多分片需要发送，或数据报没有分片
     pub mf: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Protocol {
     TCP,
     UDP,
+    #[default]
     ICMP,
+    ICMPv6,
     Other(u8),
 }
 
-impl Default for Protocol {
-    fn default() -> Self {
-        ICMP
+impl From<u8> for Protocol {
+    fn from(p: u8) -> Self {
+        match p {
+            1 => ICMP,
+            6 => TCP,
+            17 => UDP,
+            58 => ICMPv6,
+            p => Other(p),
+        }
+    }
+}
+
+impl From<Protocol> for u8 {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            ICMP => 1,
+            TCP => 6,
+            UDP => 17,
+            ICMPv6 => 58,
+            Other(p) => p,
+        }
     }
 }
 
@@ -36,7 +332,7 @@ pub struct IPHdr {
     pub totlen: u16,
     /// 用来标识数据报，占 16 位。IP 协议在存储器中维持一个计数器。
     pub ident: u16,
-    /// 占 3 位。第一位未使用，其值为 0。
+    /// 占 3 位。第一位为保留位，其值应为 0。
     pub flag: IPFlag,
     /// 占 13 位。当报文被分片后，该字段标记该分片在原报文中的相对位置。 片偏移以 8 个字节为偏移单位。
     /// 所以，除了最后一个分片，其他分片的偏移值都是 8 字节（64 位）的整数倍。
@@ -55,42 +351,58 @@ pub struct IPHdr {
     pub destinaiton: [u8; 4],
     /// 该字段用于一些可选的报头设置，主要用于测试、调试和安全的目的。
     /// 这些选项包括严格源路由（数据报必须经过指定的路由）、网际时间戳（经过每个路由器时的时间戳记录）和安全限制。
-    pub opt_section: Vec<u8>,
+    /// 长度可变，最长 40 字节。
+    pub options: Vec<IpOption>,
+    /// 选项区中 `EOL` 之后的填充字节，不含末尾的 0，通常为空
+    pub padding: Vec<u8>,
 }
 
 impl Header for IPHdr {
-    fn from_bytes(bytes: &[u8]) -> (Self, &[u8]) {
-        let (hbytes, bytes) = bytes.split_at(20);
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hbytes, _) = take(bytes, 20, Layer::IP)?;
 
         let version = hbytes[0] >> 4;
+        if version != 4 {
+            return Err(ParseError::BadVersion {
+                layer: Layer::IP,
+                offset: 0,
+                version,
+            });
+        }
         let ihl = (hbytes[0] & 0x0f) * 4;
+        if ihl < 20 {
+            return Err(ParseError::BadIhl {
+                layer: Layer::IP,
+                offset: 0,
+                ihl,
+            });
+        }
+        let (hbytes, bytes) = take(bytes, ihl as usize, Layer::IP)?;
         let tos = hbytes[1];
-        let totlen = u16::from_be_bytes(hbytes[2..4].try_into().unwrap());
-        let ident = u16::from_be_bytes(hbytes[4..6].try_into().unwrap());
+        let totlen = u16::from_be_bytes([hbytes[2], hbytes[3]]);
+        if totlen < ihl as u16 {
+            return Err(ParseError::BadLength {
+                layer: Layer::IP,
+                offset: 2,
+                len: totlen as usize,
+            });
+        }
+        let ident = u16::from_be_bytes([hbytes[4], hbytes[5]]);
         let flag = IPFlag {
+            rf: (hbytes[6] & 0b1000_0000) > 0,
             df: (hbytes[6] & 0b0100_0000) > 0,
             mf: (hbytes[6] & 0b0010_0000) > 0,
         };
         let offset = (((hbytes[6] & 0b0001_1111) as u16) << 8) | (hbytes[7] as u16);
         let ttl = hbytes[8];
-        let protocol = match hbytes[9] {
-            1 => ICMP,
-            6 => TCP,
-            17 => UDP,
-            p => Other(p),
-        };
-        let checksum = u16::from_be_bytes(hbytes[10..12].try_into().unwrap());
+        let protocol = Protocol::from(hbytes[9]);
+        let checksum = u16::from_be_bytes([hbytes[10], hbytes[11]]);
         let source = hbytes[12..16].try_into().unwrap();
         let destinaiton = hbytes[16..20].try_into().unwrap();
 
-        let opt_len = (ihl - 20) as usize;
-        let opt_section = if opt_len > 0 {
-            hbytes[20..opt_len].into_iter().cloned().collect()
-        } else {
-            Vec::new()
-        };
+        let (options, padding) = IpOption::parse_all(&hbytes[20..])?;
 
-        (
+        Ok((
             IPHdr {
                 version,
                 ihl,
@@ -104,19 +416,31 @@ impl Header for IPHdr {
                 chksum: checksum,
                 source,
                 destinaiton,
-                opt_section,
+                options,
+                padding,
             },
             bytes,
-        )
+        ))
     }
     fn to_bytes(self) -> Vec<u8> {
+        let options = self
+            .options
+            .iter()
+            .flat_map(IpOption::to_bytes)
+            .chain(self.padding)
+            .collect::<Vec<_>>();
+        let ihl = (self.ihl as usize).max(20 + options.len().div_ceil(4) * 4);
+
         let mut bytes = vec![];
-        let ver_ihl = (self.version << 4) | (self.ihl / 4);
+        let ver_ihl = (self.version << 4) | (ihl / 4) as u8;
         bytes.push(ver_ihl);
         bytes.push(self.tos);
         bytes.extend_from_slice(&self.totlen.to_be_bytes());
         bytes.extend_from_slice(&self.ident.to_be_bytes());
         let mut offset = self.offset.to_be_bytes();
+        if self.flag.rf {
+            offset[0] |= 0b1000_0000;
+        }
         if self.flag.df {
             offset[0] |= 0b0100_0000;
         }
@@ -125,16 +449,26 @@ impl Header for IPHdr {
         }
         bytes.extend_from_slice(&offset);
         bytes.push(self.ttl);
-        bytes.push(match self.protocol {
-            ICMP => 1,
-            TCP => 6,
-            UDP => 17,
-            Other(p) => p,
-        });
+        bytes.push(self.protocol.into());
         bytes.extend_from_slice(&self.chksum.to_be_bytes());
         bytes.extend_from_slice(&self.source);
         bytes.extend_from_slice(&self.destinaiton);
-        bytes.extend_from_slice(&self.opt_section);
+        bytes.extend(options);
+        // 选项区不足 4 字节的整数倍时以 0（即 EOL）填充
+        bytes.resize(ihl, 0);
+        bytes
+    }
+}
+
+impl PseudoHeader for IPHdr {
+    /// 12 字节伪首部：源地址、目的地址、0、协议号、传输层长度
+    fn pseudo_header(&self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.source);
+        bytes.extend_from_slice(&self.destinaiton);
+        bytes.push(0);
+        bytes.push(self.protocol.into());
+        bytes.extend_from_slice(&(len as u16).to_be_bytes());
         bytes
     }
 }
@@ -147,7 +481,7 @@ impl IPHdr {
             ident,
             flag: IPFlag {
                 df: true,
-                mf: false,
+                ..Default::default()
             },
             ttl: 64,
             totlen: 20,
@@ -160,10 +494,45 @@ impl IPHdr {
         Self { ttl, ..self }
     }
 
+    pub fn tos(self, tos: u8) -> Self {
+        Self { tos, ..self }
+    }
+
+    pub fn flag(self, df: bool, mf: bool) -> Self {
+        Self {
+            flag: IPFlag {
+                df,
+                mf,
+                ..self.flag
+            },
+            ..self
+        }
+    }
+
+    /// 设置片偏移，单位为 8 字节
+    pub fn offset(self, offset: u16) -> Self {
+        Self { offset, ..self }
+    }
+
     pub fn protocol(self, protocol: Protocol) -> Self {
         Self { protocol, ..self }
     }
 
+    pub fn source(self, addr: [u8; 4]) -> Self {
+        Self {
+            source: addr,
+            ..self
+        }
+    }
+
+    /// 根据首部长度和数据长度设置总长度
+    pub fn payload_len(self, len: u16) -> Self {
+        Self {
+            totlen: self.ihl as u16 + len,
+            ..self
+        }
+    }
+
     pub fn destination(self, addr: [u8; 4]) -> Self {
         Self {
             destinaiton: addr,
@@ -171,40 +540,102 @@ impl IPHdr {
         }
     }
 
+    /// 是否为分片：设置了 MF 标志或片偏移不为 0
+    pub fn is_fragment(&self) -> bool {
+        self.flag.mf || self.offset != 0
+    }
+
+    #[allow(dead_code)]
     pub fn get_chksum(&self) -> u16 {
         self.chksum
     }
 
     pub fn checksum(mut self) -> Self {
         self.chksum = 0;
-        let bytes = self.clone().to_bytes();
+        self.chksum = checksum::checksum(&self.clone().to_bytes());
+        self
+    }
 
-        let mut sum: u32 = bytes
-            .chunks(2)
-            .map(|bs| (bs[0] as u32) << 8 | bs[1] as u32)
-            .sum();
+    /// 首部校验和是否正确
+    pub fn verify(&self) -> bool {
+        checksum::verify(&self.clone().to_bytes())
+    }
 
-        while sum >> 16 != 0 {
-            sum = (sum >> 16) + (sum & 0xffff);
-        }
+    /// 以修改前的首部 `old` 为基准增量更新校验和（RFC 1624），只适用于首部长度不变的修改。
+    /// 与重新计算不同，原校验和错误时结果仍然错误。
+    pub fn update_checksum(mut self, old: &IPHdr) -> Self {
+        let zeroed = |hdr: &IPHdr| {
+            let mut bytes = hdr.clone().to_bytes();
+            bytes[10..12].fill(0);
+            bytes
+        };
+        self.chksum = checksum::update_bytes(old.chksum, &zeroed(old), &zeroed(&self));
+        self
+    }
 
-        self.chksum = !sum as u16;
+    /// 追加一个选项，按 4 字节对齐更新首部长度，总长度随之变化
+    pub fn option(mut self, option: IpOption) -> Self {
+        self.options.push(option);
+        self.fit_ihl()
+    }
 
+    /// 按选项区长度重新计算首部长度，保持数据长度不变，原有的填充随之丢弃。
+    /// 选项区超过上限时首部长度无法表示，保持不变
+    fn fit_ihl(mut self) -> Self {
+        if self.options_overflow() {
+            return self;
+        }
+        self.padding.clear();
+        let ihl = 20 + self.options_len().div_ceil(4) * 4;
+        self.totlen = self.totlen.saturating_sub(self.ihl as u16) + ihl as u16;
+        self.ihl = ihl as u8;
         self
     }
 
-    pub fn append_opt(self, mut opt_section: Vec<u8>) -> Self {
-        let mut len = opt_section.len();
-        let rem = len % 4;
-        if rem > 0 {
-            len += 4;
-            opt_section.extend(vec![0u8; rem]);
-        }
-        Self {
-            ihl: self.ihl + (len as u8) / 4,
-            totlen: self.totlen + (len as u16),
-            opt_section,
-            ..self
-        }
+    /// 选项区的长度（不含填充）
+    pub fn options_len(&self) -> usize {
+        self.options.iter().map(|opt| opt.to_bytes().len()).sum()
+    }
+
+    /// 选项区是否超过 40 字节的上限
+    pub fn options_overflow(&self) -> bool {
+        self.options_len() > MAX_OPTIONS_LEN
+    }
+
+    /// 追加可记录 `n` 个地址的记录路由选项
+    pub fn record_route(self, n: usize) -> Self {
+        self.option(IpOption::RecordRoute {
+            pointer: 4,
+            route: vec![[0; 4]; n],
+        })
+    }
+
+    /// 追加源路由选项，`strict` 为真时为严格源路由
+    pub fn source_route(self, strict: bool, route: Vec<[u8; 4]>) -> Self {
+        self.option(match strict {
+            true => IpOption::StrictSourceRoute { pointer: 4, route },
+            false => IpOption::LooseSourceRoute { pointer: 4, route },
+        })
+    }
+
+    /// 追加可记录 `n` 个时间戳的时间戳选项（只记录时间戳）
+    pub fn timestamp(self, n: usize) -> Self {
+        self.option(IpOption::Timestamp {
+            pointer: 5,
+            overflow: 0,
+            flag: 0,
+            stamps: vec![(None, 0); n],
+        })
+    }
+
+    /// 追加路由器警告选项
+    pub fn router_alert(self) -> Self {
+        self.option(IpOption::RouterAlert(0))
+    }
+
+    /// 只保留需要复制到每个分片中的选项，用于第一片之后的分片
+    pub fn copied_options(mut self) -> Self {
+        self.options.retain(IpOption::copied);
+        self.fit_ihl()
     }
 }
@@ -0,0 +1,269 @@
+use std::fmt::Display;
+
+use super::{take, Header, Layer, ParseError, Protocol, PseudoHeader};
+use crate::i18n::msg;
+
+/// 逐跳选项首部
+pub const NH_HOP_BY_HOP: u8 = 0;
+/// 路由首部
+pub const NH_ROUTING: u8 = 43;
+/// 分片首部
+pub const NH_FRAGMENT: u8 = 44;
+/// 目的选项首部
+pub const NH_DEST_OPTS: u8 = 60;
+
+/// IPv6 扩展首部
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtHdr {
+    /// 逐跳选项，路径上的每个节点都要处理。内容为选项区（不含下一首部与长度字段）。
+    HopByHop(Vec<u8>),
+    /// 路由首部。`segleft` 为尚需经过的节点数，`data` 为类型相关的数据。
+    Routing { typ: u8, segleft: u8, data: Vec<u8> },
+    /// 分片首部。`offset` 以 8 字节为单位，`mf` 表示后面还有分片。
+    Fragment { offset: u16, mf: bool, ident: u32 },
+    /// 目的选项，只由目的节点处理。内容为选项区。
+    DestOpts(Vec<u8>),
+}
+
+impl ExtHdr {
+    /// 该扩展首部的下一首部编号
+    pub fn code(&self) -> u8 {
+        match self {
+            ExtHdr::HopByHop(_) => NH_HOP_BY_HOP,
+            ExtHdr::Routing { .. } => NH_ROUTING,
+            ExtHdr::Fragment { .. } => NH_FRAGMENT,
+            ExtHdr::DestOpts(_) => NH_DEST_OPTS,
+        }
+    }
+
+    /// 编号 `code` 是否为可以解析的扩展首部
+    fn is_ext(code: u8) -> bool {
+        matches!(
+            code,
+            NH_HOP_BY_HOP | NH_ROUTING | NH_FRAGMENT | NH_DEST_OPTS
+        )
+    }
+
+    /// 解析编号为 `code` 的扩展首部，返回扩展首部、其中的下一首部编号以及剩余字节
+    fn parse(code: u8, bytes: &[u8]) -> Result<(Self, u8, &[u8]), ParseError> {
+        let len = match code {
+            NH_FRAGMENT => 8,
+            _ => (take(bytes, 2, Layer::IPv6)?.0[1] as usize + 1) * 8,
+        };
+        let (hdr, rest) = take(bytes, len, Layer::IPv6)?;
+        let ext = match code {
+            NH_HOP_BY_HOP => ExtHdr::HopByHop(hdr[2..].to_vec()),
+            NH_DEST_OPTS => ExtHdr::DestOpts(hdr[2..].to_vec()),
+            NH_ROUTING => ExtHdr::Routing {
+                typ: hdr[2],
+                segleft: hdr[3],
+                data: hdr[4..].to_vec(),
+            },
+            _ => {
+                let offmf = u16::from_be_bytes([hdr[2], hdr[3]]);
+                ExtHdr::Fragment {
+                    offset: offmf >> 3,
+                    mf: offmf & 1 != 0,
+                    ident: u32::from_be_bytes([hdr[4], hdr[5], hdr[6], hdr[7]]),
+                }
+            }
+        };
+        Ok((ext, hdr[0], rest))
+    }
+
+    /// 扩展首部的长度，为 8 字节的整数倍
+    pub fn size(&self) -> usize {
+        self.to_bytes(0).len()
+    }
+
+    /// 以 `next` 为下一首部编号序列化
+    fn to_bytes(&self, next: u8) -> Vec<u8> {
+        let mut bytes = vec![next];
+        match self {
+            ExtHdr::HopByHop(opts) | ExtHdr::DestOpts(opts) => {
+                bytes.push(((opts.len() + 2).div_ceil(8) - 1) as u8);
+                bytes.extend_from_slice(opts);
+            }
+            ExtHdr::Routing { typ, segleft, data } => {
+                bytes.push(((data.len() + 4).div_ceil(8) - 1) as u8);
+                bytes.extend([*typ, *segleft]);
+                bytes.extend_from_slice(data);
+            }
+            ExtHdr::Fragment { offset, mf, ident } => {
+                bytes.push(0);
+                bytes.extend((offset << 3 | *mf as u16).to_be_bytes());
+                bytes.extend(ident.to_be_bytes());
+            }
+        }
+        bytes.resize(bytes.len().div_ceil(8) * 8, 0);
+        bytes
+    }
+}
+
+impl Display for ExtHdr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtHdr::HopByHop(opts) => write!(f, "{}", msg!("ipv6.hop_by_hop", len = opts.len())),
+            ExtHdr::Routing { typ, segleft, .. } => {
+                write!(f, "{}", msg!("ipv6.routing", typ = typ, left = segleft))
+            }
+            ExtHdr::Fragment { offset, mf, ident } => {
+                let text = msg!(
+                    "ipv6.fragment",
+                    offset = *offset as usize * 8,
+                    mf = *mf as u8,
+                    ident = format!("{ident:#x}")
+                );
+                write!(f, "{text}")
+            }
+            ExtHdr::DestOpts(opts) => write!(f, "{}", msg!("ipv6.dest_opts", len = opts.len())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Ipv6Hdr {
+    /// 占 4 位，固定为 6。
+    pub version: u8,
+    /// 流量类别，占 8 位，相当于 IPv4 的 TOS。
+    pub tclass: u8,
+    /// 流标签，占 20 位，标识同一数据流的报文。
+    pub flow: u32,
+    /// 载荷长度，占 16 位，包括扩展首部，不含 40 字节的固定首部。
+    pub plen: u16,
+    /// 跳数限制，占 8 位，相当于 IPv4 的生存期。
+    pub hlim: u8,
+    /// 源 IPv6 地址，占 128 位。
+    pub source: [u8; 16],
+    /// 目的 IPv6 地址，占 128 位。
+    pub destination: [u8; 16],
+    /// 按出现顺序排列的扩展首部
+    pub exts: Vec<ExtHdr>,
+    /// 扩展首部链之后的上层协议
+    pub protocol: Protocol,
+}
+
+impl Ipv6Hdr {
+    pub fn new(source: [u8; 16], destination: [u8; 16], protocol: Protocol) -> Self {
+        Self {
+            version: 6,
+            hlim: 64,
+            source,
+            destination,
+            protocol,
+            ..Default::default()
+        }
+    }
+
+    pub fn hlim(self, hlim: u8) -> Self {
+        Self { hlim, ..self }
+    }
+
+    /// 扩展首部的总长度
+    pub fn ext_len(&self) -> usize {
+        self.exts.iter().map(ExtHdr::size).sum()
+    }
+
+    /// 根据扩展首部和上层数据长度设置载荷长度
+    pub fn payload_len(self, len: u16) -> Self {
+        Self {
+            plen: self.ext_len() as u16 + len,
+            ..self
+        }
+    }
+
+    /// 是否为分片
+    pub fn is_fragment(&self) -> bool {
+        self.exts
+            .iter()
+            .any(|ext| matches!(ext, ExtHdr::Fragment { offset, mf, .. } if *offset != 0 || *mf))
+    }
+}
+
+impl PseudoHeader for Ipv6Hdr {
+    /// 40 字节伪首部：源地址、目的地址、上层长度、0、下一首部
+    fn pseudo_header(&self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&self.source);
+        bytes.extend_from_slice(&self.destination);
+        bytes.extend((len as u32).to_be_bytes());
+        bytes.extend([0, 0, 0, self.protocol.into()]);
+        bytes
+    }
+}
+
+impl Header for Ipv6Hdr {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, mut rest) = take(bytes, 40, Layer::IPv6)?;
+        let version = hdr[0] >> 4;
+        if version != 6 {
+            return Err(ParseError::BadVersion {
+                layer: Layer::IPv6,
+                offset: 0,
+                version,
+            });
+        }
+        let tclass = (hdr[0] << 4) | (hdr[1] >> 4);
+        let flow = u32::from_be_bytes([0, hdr[1] & 0x0f, hdr[2], hdr[3]]);
+        let plen = u16::from_be_bytes([hdr[4], hdr[5]]);
+        let hlim = hdr[7];
+        let source = hdr[8..24].try_into().unwrap();
+        let destination = hdr[24..40].try_into().unwrap();
+
+        let mut exts = vec![];
+        let mut next = hdr[6];
+        while ExtHdr::is_ext(next) {
+            let offset = bytes.len() - rest.len();
+            let (ext, nh, tail) = ExtHdr::parse(next, rest).map_err(|e| e.shift(offset))?;
+            exts.push(ext);
+            next = nh;
+            rest = tail;
+        }
+        // 载荷长度为 0 时为超大包，不检查
+        let ext_len = bytes.len() - rest.len() - 40;
+        if plen != 0 && (plen as usize) < ext_len {
+            return Err(ParseError::BadLength {
+                layer: Layer::IPv6,
+                offset: 4,
+                len: plen as usize,
+            });
+        }
+
+        Ok((
+            Ipv6Hdr {
+                version,
+                tclass,
+                flow,
+                plen,
+                hlim,
+                source,
+                destination,
+                exts,
+                protocol: Protocol::from(next),
+            },
+            rest,
+        ))
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.push((self.version << 4) | (self.tclass >> 4));
+        bytes.push((self.tclass << 4) | ((self.flow >> 16) as u8 & 0x0f));
+        bytes.extend_from_slice(&(self.flow as u16).to_be_bytes());
+        bytes.extend(self.plen.to_be_bytes());
+        let codes = self
+            .exts
+            .iter()
+            .map(ExtHdr::code)
+            .chain([self.protocol.into()])
+            .collect::<Vec<_>>();
+        bytes.push(codes[0]);
+        bytes.push(self.hlim);
+        bytes.extend_from_slice(&self.source);
+        bytes.extend_from_slice(&self.destination);
+        for (ext, &next) in self.exts.iter().zip(&codes[1..]) {
+            bytes.extend(ext.to_bytes(next));
+        }
+        bytes
+    }
+}
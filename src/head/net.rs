@@ -0,0 +1,60 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::{IPHdr, Ipv6Hdr, Protocol, PseudoHeader};
+
+/// 网络层首部，按版本号区分 IPv4 与 IPv6
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetHdr {
+    V4(IPHdr),
+    V6(Ipv6Hdr),
+}
+
+impl NetHdr {
+    /// 上层协议
+    pub fn protocol(&self) -> Protocol {
+        match self {
+            NetHdr::V4(hdr) => hdr.protocol,
+            NetHdr::V6(hdr) => hdr.protocol,
+        }
+    }
+
+    pub fn source(&self) -> IpAddr {
+        match self {
+            NetHdr::V4(hdr) => Ipv4Addr::from(hdr.source).into(),
+            NetHdr::V6(hdr) => Ipv6Addr::from(hdr.source).into(),
+        }
+    }
+
+    pub fn destination(&self) -> IpAddr {
+        match self {
+            NetHdr::V4(hdr) => Ipv4Addr::from(hdr.destinaiton).into(),
+            NetHdr::V6(hdr) => Ipv6Addr::from(hdr.destination).into(),
+        }
+    }
+
+    /// 首部长度，包括 IPv4 选项与 IPv6 扩展首部
+    pub fn header_len(&self) -> usize {
+        match self {
+            NetHdr::V4(hdr) => hdr.ihl as usize,
+            NetHdr::V6(hdr) => 40 + hdr.ext_len(),
+        }
+    }
+
+    /// 由长度字段得出的上层数据长度。IPv6 超大包的载荷长度为 0，返回 `None`
+    pub fn payload_len(&self) -> Option<usize> {
+        match self {
+            NetHdr::V4(hdr) => Some((hdr.totlen as usize).saturating_sub(hdr.ihl as usize)),
+            NetHdr::V6(hdr) if hdr.plen == 0 => None,
+            NetHdr::V6(hdr) => Some((hdr.plen as usize).saturating_sub(hdr.ext_len())),
+        }
+    }
+}
+
+impl PseudoHeader for NetHdr {
+    fn pseudo_header(&self, len: usize) -> Vec<u8> {
+        match self {
+            NetHdr::V4(hdr) => hdr.pseudo_header(len),
+            NetHdr::V6(hdr) => hdr.pseudo_header(len),
+        }
+    }
+}
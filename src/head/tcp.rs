@@ -0,0 +1,394 @@
+use std::fmt::Display;
+
+use super::{checksum, take, Header, Layer, ParseError, PseudoHeader};
+
+/// 选项区的最大长度，此时首部长度为 60 字节
+const MAX_OPTIONS_LEN: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TcpFlag {
+    /// ECN-nonce 隐藏保护（RFC 3540，已废弃）
+    pub ns: bool,
+    /// 拥塞窗口已减小
+    pub cwr: bool,
+    /// ECN 回显
+    pub ece: bool,
+    /// 紧急指针有效
+    pub urg: bool,
+    /// 确认号有效。连接建立后所有报文段都必须把 ACK 置 1
+    pub ack: bool,
+    /// 接收方应尽快将数据交付应用进程
+    pub psh: bool,
+    /// 连接出现严重差错，必须释放连接再重新建立
+    pub rst: bool,
+    /// 同步序号，用于建立连接
+    pub syn: bool,
+    /// 发送方数据已发送完毕，要求释放连接
+    pub fin: bool,
+}
+
+impl TcpFlag {
+    fn from_bits(bits: u16) -> Self {
+        Self {
+            ns: bits & 0x100 > 0,
+            cwr: bits & 0x80 > 0,
+            ece: bits & 0x40 > 0,
+            urg: bits & 0x20 > 0,
+            ack: bits & 0x10 > 0,
+            psh: bits & 0x08 > 0,
+            rst: bits & 0x04 > 0,
+            syn: bits & 0x02 > 0,
+            fin: bits & 0x01 > 0,
+        }
+    }
+
+    pub fn bits(&self) -> u16 {
+        [
+            self.fin, self.syn, self.rst, self.psh, self.ack, self.urg, self.ece, self.cwr, self.ns,
+        ]
+        .into_iter()
+        .enumerate()
+        .fold(0, |bits, (idx, set)| bits | ((set as u16) << idx))
+    }
+}
+
+impl Display for TcpFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = [
+            (self.ns, "NS"),
+            (self.cwr, "CWR"),
+            (self.ece, "ECE"),
+            (self.urg, "URG"),
+            (self.ack, "ACK"),
+            (self.psh, "PSH"),
+            (self.rst, "RST"),
+            (self.syn, "SYN"),
+            (self.fin, "FIN"),
+        ]
+        .into_iter()
+        .filter_map(|(set, name)| set.then_some(name))
+        .collect::<Vec<_>>();
+        write!(f, "[{}]", names.join(","))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TcpOption {
+    /// 选项列表结束
+    Eol,
+    /// 无操作，用于对齐
+    Nop,
+    /// 最大报文段长度
+    Mss(u16),
+    /// 窗口扩大因子
+    WindowScale(u8),
+    /// 允许选择确认
+    SackPermitted,
+    /// 选择确认的数据块，每块为（左边界, 右边界）
+    Sack(Vec<(u32, u32)>),
+    /// 时间戳值与时间戳回显应答
+    Timestamp {
+        val: u32,
+        ecr: u32,
+    },
+    Unknown {
+        kind: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl TcpOption {
+    /// 解析完整的选项区域，`EOL` 之后的字节视为填充，与选项一起返回
+    fn parse_all(mut bytes: &[u8]) -> Result<(Vec<TcpOption>, Vec<u8>), ParseError> {
+        let total = bytes.len();
+        let mut options = vec![];
+        while let Some(&kind) = bytes.first() {
+            let at = 20 + total - bytes.len();
+            match kind {
+                0 => {
+                    options.push(TcpOption::Eol);
+                    // 末尾的 0 在序列化时会重新补齐，不必保留
+                    let end = bytes.iter().rposition(|&b| b != 0).map_or(1, |i| i + 1);
+                    return Ok((options, bytes[1..end].to_vec()));
+                }
+                1 => {
+                    options.push(TcpOption::Nop);
+                    bytes = &bytes[1..];
+                    continue;
+                }
+                _ => {}
+            }
+            let len = match bytes.get(1) {
+                Some(&len) if len >= 2 && len as usize <= bytes.len() => len as usize,
+                _ => {
+                    return Err(ParseError::BadLength {
+                        layer: Layer::TCP,
+                        offset: at + 1,
+                        len: bytes.get(1).copied().unwrap_or(0) as usize,
+                    })
+                }
+            };
+            let (opt, rest) = bytes.split_at(len);
+            let data = &opt[2..];
+            let bad_len = || ParseError::BadLength {
+                layer: Layer::TCP,
+                offset: at + 1,
+                len,
+            };
+            options.push(match kind {
+                2 if len == 4 => TcpOption::Mss(u16::from_be_bytes([data[0], data[1]])),
+                3 if len == 3 => TcpOption::WindowScale(data[0]),
+                4 if len == 2 => TcpOption::SackPermitted,
+                5 if (len - 2) % 8 == 0 => TcpOption::Sack(
+                    data.chunks(8)
+                        .map(|b| {
+                            (
+                                u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+                                u32::from_be_bytes([b[4], b[5], b[6], b[7]]),
+                            )
+                        })
+                        .collect(),
+                ),
+                8 if len == 10 => TcpOption::Timestamp {
+                    val: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                    ecr: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+                },
+                2..=5 | 8 => return Err(bad_len()),
+                kind => TcpOption::Unknown {
+                    kind,
+                    data: data.to_vec(),
+                },
+            });
+            bytes = rest;
+        }
+        Ok((options, vec![]))
+    }
+
+    /// 选项的长度，包括类型与长度字段
+    pub fn size(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            TcpOption::Eol => vec![0],
+            TcpOption::Nop => vec![1],
+            TcpOption::Mss(mss) => {
+                let mut bytes = vec![2, 4];
+                bytes.extend(mss.to_be_bytes());
+                bytes
+            }
+            TcpOption::WindowScale(shift) => vec![3, 3, *shift],
+            TcpOption::SackPermitted => vec![4, 2],
+            TcpOption::Sack(blocks) => {
+                let len = u8::try_from(2 + 8 * blocks.len()).unwrap_or(u8::MAX);
+                let mut bytes = vec![5, len];
+                for (left, right) in blocks {
+                    bytes.extend(left.to_be_bytes());
+                    bytes.extend(right.to_be_bytes());
+                }
+                bytes
+            }
+            TcpOption::Timestamp { val, ecr } => {
+                let mut bytes = vec![8, 10];
+                bytes.extend(val.to_be_bytes());
+                bytes.extend(ecr.to_be_bytes());
+                bytes
+            }
+            TcpOption::Unknown { kind, data } => {
+                let len = u8::try_from(2 + data.len()).unwrap_or(u8::MAX);
+                let mut bytes = vec![*kind, len];
+                bytes.extend(data);
+                bytes
+            }
+        }
+    }
+}
+
+impl Display for TcpOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TcpOption::Eol => write!(f, "EOL"),
+            TcpOption::Nop => write!(f, "NOP"),
+            TcpOption::Mss(mss) => write!(f, "MSS={mss}"),
+            TcpOption::WindowScale(shift) => write!(f, "WS={shift}"),
+            TcpOption::SackPermitted => write!(f, "SACK_PERM"),
+            TcpOption::Sack(blocks) => {
+                let blocks = blocks
+                    .iter()
+                    .map(|(l, r)| format!("{l}-{r}"))
+                    .collect::<Vec<_>>();
+                write!(f, "SACK={}", blocks.join(","))
+            }
+            TcpOption::Timestamp { val, ecr } => write!(f, "TS val={val} ecr={ecr}"),
+            TcpOption::Unknown { kind, data } => write!(f, "kind={kind} {data:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TcpHdr {
+    /// 源端口号，占 16 位。
+    pub sport: u16,
+    /// 目的端口号，占 16 位。
+    pub dport: u16,
+    /// 本报文段所发送数据的第一个字节的序号，占 32 位。
+    pub seqnum: u32,
+    /// 期望收到对方下一个报文段的第一个数据字节的序号，占 32 位。
+    pub acknum: u32,
+    /// 数据偏移，即首部长度，单位为字节。字段本身占 4 位，以 4 字节为单位，最大为 60 字节。
+    pub doff: u8,
+    /// 数据偏移与控制位之间的 3 个保留位
+    pub reserved: u8,
+    /// 控制位
+    pub flag: TcpFlag,
+    /// 接收窗口大小，占 16 位。
+    pub window: u16,
+    /// 检验首部和数据，计算时需加上 12 字节的 IPv4 伪首部。
+    pub chksum: u16,
+    /// 紧急指针，仅在 URG 置 1 时有效，指出紧急数据的末尾在报文段中的位置。
+    pub urgent: u16,
+    /// 选项，长度可变，最长 40 字节。
+    pub options: Vec<TcpOption>,
+    /// 选项区中 `EOL` 之后的填充字节，不含末尾的 0，通常为空
+    pub padding: Vec<u8>,
+}
+
+impl TcpHdr {
+    pub fn new(sport: u16, dport: u16) -> Self {
+        Self {
+            sport,
+            dport,
+            doff: 20,
+            window: 64240,
+            ..Default::default()
+        }
+    }
+
+    pub fn seqnum(self, seqnum: u32) -> Self {
+        Self { seqnum, ..self }
+    }
+
+    #[allow(dead_code)]
+    pub fn acknum(self, acknum: u32) -> Self {
+        Self { acknum, ..self }
+    }
+
+    pub fn flag(self, flag: TcpFlag) -> Self {
+        Self { flag, ..self }
+    }
+
+    #[allow(dead_code)]
+    pub fn window(self, window: u16) -> Self {
+        Self { window, ..self }
+    }
+
+    /// 追加一个选项，并按 4 字节对齐更新首部长度。选项区放不下时丢弃该选项
+    pub fn option(mut self, option: TcpOption) -> Self {
+        if self.options_len() + option.size() > MAX_OPTIONS_LEN {
+            return self;
+        }
+        self.options.push(option);
+        self.padding.clear();
+        self.doff = (20 + self.options_len().div_ceil(4) * 4) as u8;
+        self
+    }
+
+    /// 选项区的长度（不含填充）
+    pub fn options_len(&self) -> usize {
+        self.options.iter().map(TcpOption::size).sum()
+    }
+
+    /// 根据所在 IP 报文的伪首部与数据计算校验和
+    pub fn checksum(mut self, iphdr: &impl PseudoHeader, data: &[u8]) -> Self {
+        self.chksum = 0;
+        let segment = self.clone().to_bytes();
+        let mut bytes = iphdr.pseudo_header(segment.len() + data.len());
+        bytes.extend(segment);
+        bytes.extend_from_slice(data);
+        self.chksum = checksum::checksum(&bytes);
+        self
+    }
+
+    /// 所在 IP 报文的伪首部、首部与数据 `data` 的校验和是否正确
+    pub fn verify(&self, iphdr: &impl PseudoHeader, data: &[u8]) -> bool {
+        let segment = self.clone().to_bytes();
+        let mut bytes = iphdr.pseudo_header(segment.len() + data.len());
+        bytes.extend(segment);
+        bytes.extend_from_slice(data);
+        checksum::verify(&bytes)
+    }
+}
+
+impl Header for TcpHdr {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, _) = take(bytes, 20, Layer::TCP)?;
+        let doff = (hdr[12] >> 4) * 4;
+        if doff < 20 {
+            return Err(ParseError::BadIhl {
+                layer: Layer::TCP,
+                offset: 12,
+                ihl: doff,
+            });
+        }
+        let (hdr, rest) = take(bytes, doff as usize, Layer::TCP)?;
+
+        let sport = u16::from_be_bytes([hdr[0], hdr[1]]);
+        let dport = u16::from_be_bytes([hdr[2], hdr[3]]);
+        let seqnum = u32::from_be_bytes([hdr[4], hdr[5], hdr[6], hdr[7]]);
+        let acknum = u32::from_be_bytes([hdr[8], hdr[9], hdr[10], hdr[11]]);
+        let reserved = (hdr[12] >> 1) & 0x07;
+        let flag = TcpFlag::from_bits(u16::from_be_bytes([hdr[12] & 0x01, hdr[13]]));
+        let window = u16::from_be_bytes([hdr[14], hdr[15]]);
+        let chksum = u16::from_be_bytes([hdr[16], hdr[17]]);
+        let urgent = u16::from_be_bytes([hdr[18], hdr[19]]);
+        let (options, padding) = TcpOption::parse_all(&hdr[20..])?;
+
+        Ok((
+            TcpHdr {
+                sport,
+                dport,
+                seqnum,
+                acknum,
+                doff,
+                reserved,
+                flag,
+                window,
+                chksum,
+                urgent,
+                options,
+                padding,
+            },
+            rest,
+        ))
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        // 首部最长 60 字节，超出选项区的选项不写出
+        let mut options = vec![];
+        for opt in &self.options {
+            let bytes = opt.to_bytes();
+            if options.len() + bytes.len() > MAX_OPTIONS_LEN {
+                break;
+            }
+            options.extend(bytes);
+        }
+        options.extend(&self.padding);
+        let doff = (self.doff as usize).clamp(20 + options.len().div_ceil(4) * 4, 60);
+
+        let mut bytes = vec![];
+        bytes.extend(self.sport.to_be_bytes());
+        bytes.extend(self.dport.to_be_bytes());
+        bytes.extend(self.seqnum.to_be_bytes());
+        bytes.extend(self.acknum.to_be_bytes());
+        let bits = self.flag.bits();
+        bytes.push(((doff / 4) as u8) << 4 | (self.reserved & 0x07) << 1 | (bits >> 8) as u8);
+        bytes.push(bits as u8);
+        bytes.extend(self.window.to_be_bytes());
+        bytes.extend(self.chksum.to_be_bytes());
+        bytes.extend(self.urgent.to_be_bytes());
+        bytes.extend(options);
+        bytes.resize(doff, 0);
+        bytes
+    }
+}
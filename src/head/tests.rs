@@ -0,0 +1,1328 @@
+//! 各首部类型的往返测试：随机构造合法的首部，检查 `from_bytes(to_bytes(x)) == x`，
+//! 以及对随机字节检查 `to_bytes(from_bytes(b)) == b`；校验和的计算、检验与增量更新；
+//! 首部的结构化输出；首部解析的错误路径：截断、字段非法，以及内层错误偏移相对于整个帧
+
+use super::*;
+
+/// 每种首部随机构造的次数
+const ROUNDS: usize = 2000;
+
+/// xorshift64 伪随机数，固定种子以便复现
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        Rng(0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.next() as u8
+    }
+
+    fn u16(&mut self) -> u16 {
+        self.next() as u16
+    }
+
+    fn u32(&mut self) -> u32 {
+        self.next() as u32
+    }
+
+    fn bool(&mut self) -> bool {
+        self.next() & 1 == 1
+    }
+
+    /// `0..n` 中的一个数
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        std::array::from_fn(|_| self.u8())
+    }
+
+    /// `base + step * k` 字节的随机数据，`k` 取 `0..count`
+    fn vec(&mut self, base: usize, step: usize, count: usize) -> Vec<u8> {
+        let len = base + step * self.below(count);
+        (0..len).map(|_| self.u8()).collect()
+    }
+}
+
+/// 序列化后再解析，应得到原首部且没有剩余字节
+fn round_trip<H: Header + Clone + PartialEq + std::fmt::Debug>(hdr: H) {
+    let bytes = hdr.clone().to_bytes();
+    match H::from_bytes(&bytes) {
+        Ok((parsed, rest)) => {
+            assert_eq!(parsed, hdr, "bytes: {bytes:02x?}");
+            assert!(rest.is_empty(), "剩余 {} 字节: {bytes:02x?}", rest.len());
+        }
+        Err(e) => panic!("{hdr:?} 解析失败: {e}, bytes: {bytes:02x?}"),
+    }
+}
+
+/// 解析随机字节，成功时再序列化应得到被解析的那部分字节。返回是否解析成功
+fn reparse<H: Header + std::fmt::Debug>(bytes: &[u8]) -> bool {
+    let Ok((hdr, rest)) = H::from_bytes(bytes) else {
+        return false;
+    };
+    let len = bytes.len() - rest.len();
+    let hdr_dbg = format!("{hdr:?}");
+    assert_eq!(hdr.to_bytes(), &bytes[..len], "{hdr_dbg}");
+    true
+}
+
+/// `len` 字节的随机选项区。类型一半取自 `kinds`，长度字段大多落在 2 到 12 之间
+fn random_options(rng: &mut Rng, kinds: &[u8], len: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    while bytes.len() < len {
+        let kind = match rng.bool() {
+            true => kinds[rng.below(kinds.len())],
+            false => rng.u8(),
+        };
+        let size = match rng.below(8) {
+            0 => rng.u8() as usize,
+            _ => 2 + rng.below(11),
+        };
+        bytes.extend([kind, size as u8]);
+        bytes.extend((2..size).map(|_| rng.u8()));
+    }
+    bytes.truncate(len);
+    bytes
+}
+
+#[test]
+fn ether_kind_is_big_endian() {
+    for org in 0..=u16::MAX {
+        let etype = EtherKind::new(org);
+        assert_eq!(u16::from(etype), org);
+        assert_eq!(etype.to_bytes(), org.to_be_bytes());
+        round_trip(etype);
+    }
+    assert_eq!(EtherKind::Other(0x1234).to_bytes(), [0x12, 0x34]);
+    assert_eq!(EtherKind::new(0x05DC), EtherKind::Length(0x05DC));
+    assert_eq!(EtherKind::new(0x0600), EtherKind::Other(0x0600));
+}
+
+fn gen_llc(rng: &mut Rng) -> LlcHdr {
+    if rng.bool() {
+        return LlcHdr {
+            dsap: 0xAA,
+            ssap: 0xAA,
+            control: 0x03,
+            snap: Some(Snap {
+                oui: rng.bytes(),
+                pid: rng.u16(),
+            }),
+        };
+    }
+    // U 帧的控制字段占 1 字节，I 帧和 S 帧占 2 字节
+    let control = match rng.bool() {
+        true => (rng.u8() | 0x03) as u16,
+        false => rng.u16() & !0x03 | rng.below(3) as u16,
+    };
+    let (dsap, ssap) = match (rng.u8(), rng.u8()) {
+        (0xAA, 0xAA) => (0x42, 0x42),
+        saps => saps,
+    };
+    LlcHdr {
+        dsap,
+        ssap,
+        control,
+        snap: None,
+    }
+}
+
+fn gen_ether(rng: &mut Rng) -> EtherHdr {
+    let vlans = (0..rng.below(4))
+        .map(|_| VlanTag {
+            tpid: [TPID_CTAG, TPID_STAG][rng.below(2)],
+            pcp: rng.u8() & 0x07,
+            dei: rng.bool(),
+            vid: rng.u16() & 0x0fff,
+        })
+        .collect();
+    // VLAN 标签的 TPID 只能出现在标签中
+    let etype = match EtherKind::new(rng.u16()) {
+        EtherKind::Vlan | EtherKind::QinQ => EtherKind::IP,
+        etype => etype,
+    };
+    let llc = matches!(etype, EtherKind::Length(_)).then(|| gen_llc(rng));
+    EtherHdr {
+        dhost: rng.bytes(),
+        shost: rng.bytes(),
+        vlans,
+        etype,
+        llc,
+    }
+}
+
+#[test]
+fn ether_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        round_trip(gen_ether(&mut rng));
+    }
+}
+
+#[test]
+fn ether_8023_llc_snap() {
+    // 802.3 长度字段 + LLC/SNAP 封装的 IPv4
+    let mut frame = vec![0xff; 6];
+    frame.extend([2, 0, 0, 0, 0, 1]);
+    frame.extend(46u16.to_be_bytes());
+    frame.extend([0xAA, 0xAA, 0x03, 0, 0, 0, 0x08, 0x00]);
+    let (ethdr, rest) = EtherHdr::from_bytes(&frame).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(ethdr.etype, EtherKind::Length(46));
+    assert_eq!(ethdr.ethertype(), EtherKind::IP);
+    assert_eq!(ethdr.size(), frame.len());
+}
+
+#[test]
+fn arp_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        round_trip(ArpHdr {
+            oper: rng.u16(),
+            ptype: EtherKind::new(rng.u16()),
+            ..ArpHdr::request(rng.bytes(), rng.bytes(), rng.bytes())
+        });
+    }
+}
+
+fn gen_ip_option(rng: &mut Rng) -> IpOption {
+    let route = |rng: &mut Rng| (0..1 + rng.below(4)).map(|_| rng.bytes()).collect();
+    match rng.below(8) {
+        0 => IpOption::Nop,
+        1 => IpOption::RecordRoute {
+            pointer: rng.u8(),
+            route: route(rng),
+        },
+        2 => IpOption::LooseSourceRoute {
+            pointer: rng.u8(),
+            route: route(rng),
+        },
+        3 => IpOption::StrictSourceRoute {
+            pointer: rng.u8(),
+            route: route(rng),
+        },
+        4 => {
+            let flag = [0, 1, 3][rng.below(3)];
+            let stamps = (0..1 + rng.below(3))
+                .map(|_| ((flag != 0).then(|| rng.bytes()), rng.u32()))
+                .collect();
+            IpOption::Timestamp {
+                pointer: rng.u8(),
+                overflow: rng.u8() & 0x0f,
+                flag,
+                stamps,
+            }
+        }
+        5 => IpOption::Security {
+            level: rng.u8(),
+            authority: rng.vec(0, 1, 4),
+        },
+        6 => IpOption::RouterAlert(rng.u16()),
+        _ => IpOption::Unknown {
+            kind: [25, 82, 133, 145][rng.below(4)],
+            data: rng.vec(0, 1, 6),
+        },
+    }
+}
+
+fn gen_ip(rng: &mut Rng) -> IPHdr {
+    let mut hdr = IPHdr::new(rng.u16())
+        .tos(rng.u8())
+        .ttl(rng.u8())
+        .flag(rng.bool(), rng.bool())
+        .offset(rng.u16() & 0x1fff)
+        .protocol(Protocol::from(rng.u8()))
+        .source(rng.bytes())
+        .destination(rng.bytes());
+    // 选项区最长 40 字节，留出 1 字节给 EOL
+    for _ in 0..rng.below(4) {
+        let opt = gen_ip_option(rng);
+        if hdr.options_len() + opt.to_bytes().len() > 39 {
+            break;
+        }
+        hdr = hdr.option(opt);
+    }
+    if !hdr.options_len().is_multiple_of(4) {
+        hdr = hdr.option(IpOption::Eol);
+    }
+    let mut hdr = hdr.payload_len(rng.u16() / 2);
+    hdr.flag.rf = rng.bool();
+    hdr.chksum = rng.u16();
+    hdr
+}
+
+#[test]
+fn ip_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        round_trip(gen_ip(&mut rng));
+    }
+}
+
+#[test]
+fn ip_reparse() {
+    let mut rng = Rng::new();
+    let mut parsed = 0;
+    for _ in 0..ROUNDS * 4 {
+        let ihl = 20 + 4 * rng.below(11);
+        let mut bytes = rng.vec(20, 0, 1);
+        bytes[0] = 0x40 | (ihl / 4) as u8;
+        let totlen = (ihl + rng.below(1500)) as u16;
+        bytes[2..4].copy_from_slice(&totlen.to_be_bytes());
+        bytes.extend(random_options(
+            &mut rng,
+            &[0, 1, 7, 68, 130, 131, 137, 148],
+            ihl - 20,
+        ));
+        parsed += reparse::<IPHdr>(&bytes) as usize;
+    }
+    assert!(parsed > ROUNDS / 2, "{parsed}");
+}
+
+#[test]
+fn ip_option_padding() {
+    // 记录路由选项 3 + 4 * 2 = 11 字节，补 1 字节到 12
+    let hdr = IPHdr::new(1).record_route(2).payload_len(8);
+    assert_eq!(hdr.ihl, 32);
+    assert_eq!(hdr.totlen, 40);
+    let bytes = hdr.clone().to_bytes();
+    assert_eq!(bytes.len(), 32);
+    assert_eq!(&bytes[20..24], &[7, 11, 4, 0]);
+    let (parsed, _) = IPHdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.options[0], hdr.options[0]);
+    assert_eq!(parsed.options[1], IpOption::Eol);
+}
+
+#[test]
+fn ip_options_overflow() {
+    // 70 个地址的源路由选项共 283 字节，首部长度保持不变，由调用者检查上限
+    let hdr = IPHdr::new(1)
+        .router_alert()
+        .source_route(false, vec![[10, 0, 0, 1]; 70])
+        .payload_len(8);
+    assert!(hdr.options_overflow());
+    assert_eq!((hdr.options_len(), hdr.ihl, hdr.totlen), (4 + 283, 24, 32));
+    // 写出的长度字段不会回绕成一个较小的值
+    assert_eq!(hdr.options[1].to_bytes()[1], u8::MAX);
+    // 9 个地址的源路由选项加上 NOP 恰好 40 字节，仍可表示
+    let hdr = IPHdr::new(1)
+        .source_route(true, vec![[10, 0, 0, 1]; 9])
+        .option(IpOption::Nop);
+    assert!(!hdr.options_overflow());
+    assert_eq!(hdr.ihl, 60);
+    round_trip(hdr);
+}
+
+fn gen_ext(rng: &mut Rng) -> ExtHdr {
+    // 选项型扩展首部的长度为 8 字节的整数倍
+    match rng.below(4) {
+        0 => ExtHdr::HopByHop(rng.vec(6, 8, 4)),
+        1 => ExtHdr::Routing {
+            typ: rng.u8(),
+            segleft: rng.u8(),
+            data: rng.vec(4, 8, 4),
+        },
+        2 => ExtHdr::Fragment {
+            offset: rng.u16() & 0x1fff,
+            mf: rng.bool(),
+            ident: rng.u32(),
+        },
+        _ => ExtHdr::DestOpts(rng.vec(6, 8, 4)),
+    }
+}
+
+#[test]
+fn ipv6_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        // 上层协议号不能是扩展首部的编号
+        let protocol = match rng.u8() {
+            0 | 43 | 44 | 60 => Protocol::UDP,
+            p => Protocol::from(p),
+        };
+        let mut hdr = Ipv6Hdr::new(rng.bytes(), rng.bytes(), protocol).hlim(rng.u8());
+        hdr.tclass = rng.u8();
+        hdr.flow = rng.u32() & 0x000f_ffff;
+        hdr.exts = (0..rng.below(4)).map(|_| gen_ext(&mut rng)).collect();
+        let hdr = hdr.payload_len(rng.u16() / 2);
+        round_trip(hdr);
+    }
+}
+
+#[test]
+fn udp_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        round_trip(UdpHdr {
+            sport: rng.u16(),
+            dport: rng.u16(),
+            len: rng.u16().max(8),
+            chksum: rng.u16(),
+        });
+    }
+}
+
+fn gen_tcp_option(rng: &mut Rng) -> TcpOption {
+    match rng.below(7) {
+        0 => TcpOption::Nop,
+        1 => TcpOption::Mss(rng.u16()),
+        2 => TcpOption::WindowScale(rng.u8()),
+        3 => TcpOption::SackPermitted,
+        4 => TcpOption::Sack(
+            (0..1 + rng.below(3))
+                .map(|_| (rng.u32(), rng.u32()))
+                .collect(),
+        ),
+        5 => TcpOption::Timestamp {
+            val: rng.u32(),
+            ecr: rng.u32(),
+        },
+        _ => TcpOption::Unknown {
+            kind: 9 + rng.below(247) as u8,
+            data: rng.vec(0, 1, 6),
+        },
+    }
+}
+
+#[test]
+fn tcp_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let mut hdr = TcpHdr::new(rng.u16(), rng.u16())
+            .seqnum(rng.u32())
+            .acknum(rng.u32())
+            .window(rng.u16());
+        hdr.flag = TcpFlag {
+            ns: rng.bool(),
+            cwr: rng.bool(),
+            ece: rng.bool(),
+            urg: rng.bool(),
+            ack: rng.bool(),
+            psh: rng.bool(),
+            rst: rng.bool(),
+            syn: rng.bool(),
+            fin: rng.bool(),
+        };
+        hdr.reserved = rng.u8() & 0x07;
+        hdr.chksum = rng.u16();
+        hdr.urgent = rng.u16();
+        // 选项区最长 40 字节，留出 1 字节给 EOL
+        let mut len = 0;
+        for _ in 0..rng.below(5) {
+            let opt = gen_tcp_option(&mut rng);
+            len += opt.to_bytes().len();
+            if len > 39 {
+                break;
+            }
+            hdr = hdr.option(opt);
+        }
+        // 选项区不是 4 字节的整数倍时以 EOL 结束，之后为填充
+        let len: usize = hdr.options.iter().map(|opt| opt.to_bytes().len()).sum();
+        if !len.is_multiple_of(4) {
+            hdr = hdr.option(TcpOption::Eol);
+        }
+        round_trip(hdr);
+    }
+}
+
+#[test]
+fn tcp_reparse() {
+    let mut rng = Rng::new();
+    let mut parsed = 0;
+    for _ in 0..ROUNDS * 4 {
+        let doff = 20 + 4 * rng.below(11);
+        let mut bytes = rng.vec(20, 0, 1);
+        bytes[12] = ((doff / 4) as u8) << 4 | (bytes[12] & 0x0f);
+        bytes.extend(random_options(&mut rng, &[0, 1, 2, 3, 4, 5, 8], doff - 20));
+        parsed += reparse::<TcpHdr>(&bytes) as usize;
+    }
+    assert!(parsed > ROUNDS / 2, "{parsed}");
+}
+
+#[test]
+fn icmp_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let typ = rng.u8();
+        let hdr = ICMP {
+            chksum: rng.u16(),
+            ..ICMP::new(typ, rng.u8())
+        };
+        // 回显、时间戳等类型的报文在首部之后带有标识和序号
+        let hdr = match typ {
+            0 | 8 | 9 | 10 | 13..=18 => hdr.with_ident(rng.u16()).with_seqnum(rng.u16()),
+            _ => hdr,
+        };
+        round_trip(hdr);
+    }
+}
+
+fn gen_nd_option(rng: &mut Rng) -> NdOption {
+    match rng.below(5) {
+        0 => NdOption::SourceLinkAddr(rng.bytes()),
+        1 => NdOption::TargetLinkAddr(rng.bytes()),
+        2 => NdOption::PrefixInfo {
+            prefix_len: rng.u8(),
+            onlink: rng.bool(),
+            autonomous: rng.bool(),
+            reserved1: rng.u8() & 0x3f,
+            valid: rng.u32(),
+            preferred: rng.u32(),
+            reserved2: rng.u32(),
+            prefix: rng.bytes(),
+        },
+        3 => NdOption::Mtu {
+            reserved: rng.u16(),
+            mtu: rng.u32(),
+        },
+        _ => NdOption::Unknown {
+            kind: 6 + rng.below(250) as u8,
+            data: rng.vec(6, 8, 3),
+        },
+    }
+}
+
+#[test]
+fn icmpv6_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let options = (0..rng.below(4)).map(|_| gen_nd_option(&mut rng)).collect();
+        let (typ, msg) = match rng.below(7) {
+            0 => (
+                ICMPV6_ECHO_REQUEST,
+                Some(Icmpv6Msg::Echo(Ping {
+                    ident: rng.u16(),
+                    seqnum: rng.u16(),
+                })),
+            ),
+            1 => (
+                ND_ROUTER_SOLICIT,
+                Some(Icmpv6Msg::RouterSolicit {
+                    reserved: rng.u32(),
+                    options,
+                }),
+            ),
+            2 => (
+                ND_ROUTER_ADVERT,
+                Some(Icmpv6Msg::RouterAdvert {
+                    hop_limit: rng.u8(),
+                    managed: rng.bool(),
+                    other: rng.bool(),
+                    flags: rng.u8() & 0x3f,
+                    lifetime: rng.u16(),
+                    reachable: rng.u32(),
+                    retrans: rng.u32(),
+                    options,
+                }),
+            ),
+            3 => (
+                ND_NEIGHBOR_SOLICIT,
+                Some(Icmpv6Msg::NeighborSolicit {
+                    reserved: rng.u32(),
+                    target: rng.bytes(),
+                    options,
+                }),
+            ),
+            4 => (
+                ND_NEIGHBOR_ADVERT,
+                Some(Icmpv6Msg::NeighborAdvert {
+                    router: rng.bool(),
+                    solicited: rng.bool(),
+                    override_: rng.bool(),
+                    reserved: rng.u32() & 0x1fff_ffff,
+                    target: rng.bytes(),
+                    options,
+                }),
+            ),
+            // 其他类型只有 4 字节首部
+            _ => (rng.below(128) as u8, None),
+        };
+        round_trip(Icmpv6 {
+            typ,
+            code: rng.u8(),
+            chksum: rng.u16(),
+            msg,
+        });
+    }
+}
+
+#[test]
+fn icmpv6_nd_reparse() {
+    let mut rng = Rng::new();
+    let mut parsed = 0;
+    for _ in 0..ROUNDS * 4 {
+        let typ = ND_ROUTER_SOLICIT + rng.below(4) as u8;
+        let fixed = match typ {
+            ND_ROUTER_SOLICIT => 4,
+            ND_ROUTER_ADVERT => 12,
+            _ => 20,
+        };
+        let mut bytes = rng.vec(4 + fixed, 0, 1);
+        bytes[0] = typ;
+        // 选项的长度以 8 字节为单位，大多与类型相符
+        for _ in 0..rng.below(4) {
+            let kind = [1, 2, 3, 5, rng.u8()][rng.below(5)];
+            let units = match (rng.below(8), kind) {
+                (0, _) => rng.below(5),
+                (_, 3) => 4,
+                (_, 1 | 2 | 5) => 1,
+                _ => 1 + rng.below(3),
+            };
+            bytes.extend([kind, units as u8]);
+            bytes.extend((2..(units * 8).max(2)).map(|_| rng.u8()));
+        }
+        parsed += reparse::<Icmpv6>(&bytes) as usize;
+    }
+    assert!(parsed > ROUNDS / 2, "{parsed}");
+}
+
+#[test]
+fn stacked_round_trip() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let mut ethdr = gen_ether(&mut rng);
+        ethdr.etype = EtherKind::IP;
+        ethdr.llc = None;
+        round_trip((ethdr, gen_ip(&mut rng)));
+    }
+}
+
+#[test]
+fn checksum_odd_length() {
+    // 奇数长度的数据末尾按补 0 计算
+    assert_eq!(checksum::sum(&[0x12, 0x34, 0x56]), 0x6834);
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let mut bytes = rng.vec(1, 2, 40);
+        let chksum = checksum::checksum(&bytes);
+        bytes.push(0);
+        assert_eq!(checksum::checksum(&bytes), chksum);
+    }
+}
+
+#[test]
+fn ip_checksum_verify() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let hdr = gen_ip(&mut rng).checksum();
+        assert!(hdr.verify());
+        let mut bad = hdr.clone();
+        bad.chksum = !bad.chksum;
+        assert!(!bad.verify());
+    }
+}
+
+/// 解析后再序列化，应得到原字节
+fn reencode<H: Header + Clone + std::fmt::Debug>(bytes: &[u8]) -> H {
+    let (hdr, rest) = H::from_bytes(bytes).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(hdr.clone().to_bytes(), bytes, "{hdr:?}");
+    hdr
+}
+
+/// 填上 `at` 处的校验和，`pseudo` 为伪首部
+fn fill_checksum(mut bytes: Vec<u8>, at: usize, pseudo: Vec<u8>) -> Vec<u8> {
+    let mut sum = pseudo;
+    sum.extend(&bytes);
+    bytes[at..at + 2].copy_from_slice(&checksum::checksum(&sum).to_be_bytes());
+    bytes
+}
+
+#[test]
+fn reserved_bits_verify() {
+    // IPv4：保留标志位、DF 与 EOL 之后的非零填充
+    let mut ip = vec![0x46, 0, 0, 44, 0x12, 0x34, 0xc0, 0, 64, 6, 0, 0];
+    ip.extend([192, 0, 2, 1, 192, 0, 2, 2, 1, 0, 0xaa, 0]);
+    let ip = fill_checksum(ip, 10, vec![]);
+    let iphdr: IPHdr = reencode(&ip);
+    assert!(iphdr.flag.rf && iphdr.flag.df);
+    assert!(iphdr.verify());
+
+    // TCP：数据偏移之后的 3 个保留位
+    let mut tcp = vec![0x30, 0x39, 0, 80, 0, 0, 0, 1, 0, 0, 0, 0, 0x5e, 0x02];
+    tcp.extend([0xfa, 0xf0, 0, 0, 0, 0]);
+    let tcp = fill_checksum(tcp, 16, iphdr.pseudo_header(20));
+    let tcphdr: TcpHdr = reencode(&tcp);
+    assert_eq!((tcphdr.reserved, tcphdr.flag.ns), (0x07, false));
+    assert!(tcphdr.verify(&iphdr, &[]));
+
+    let [src, dst] =
+        ["fe80::1", "ff02::1"].map(|a| a.parse::<std::net::Ipv6Addr>().unwrap().octets());
+    let ipv6 = Ipv6Hdr::new(src, dst, Protocol::ICMPv6);
+    let nd = |bytes: Vec<u8>| {
+        let pseudo = ipv6.pseudo_header(bytes.len());
+        let bytes = fill_checksum(bytes, 2, pseudo);
+        let hdr: Icmpv6 = reencode(&bytes);
+        assert!(hdr.verify(&ipv6, &[]), "{hdr:?}");
+        hdr
+    };
+    // 路由器通告：M 标志与 RFC 4191 的低优先级，前缀信息与 MTU 选项的保留字段
+    let mut ra = vec![ND_ROUTER_ADVERT, 0, 0, 0, 64, 0x88, 0x07, 0x08];
+    ra.extend([0, 0, 0, 0, 0, 0, 0, 0]);
+    ra.extend([
+        3, 4, 64, 0xc5, 0, 0, 0x0e, 0x10, 0, 0, 0x07, 0x08, 0xde, 0xad, 0xbe, 0xef,
+    ]);
+    ra.extend([0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    ra.extend([5, 1, 0x12, 0x34, 0, 0, 0x05, 0xdc]);
+    let Some(Icmpv6Msg::RouterAdvert {
+        managed,
+        other,
+        flags,
+        options,
+        ..
+    }) = nd(ra).msg
+    else {
+        panic!("不是路由器通告");
+    };
+    assert_eq!((managed, other, flags), (true, false, 0x08));
+    assert!(matches!(
+        options[0],
+        NdOption::PrefixInfo {
+            onlink: true,
+            autonomous: true,
+            reserved1: 0x05,
+            reserved2: 0xdead_beef,
+            ..
+        }
+    ));
+    assert_eq!(
+        options[1],
+        NdOption::Mtu {
+            reserved: 0x1234,
+            mtu: 1500
+        }
+    );
+    // 路由器请求、邻居请求与邻居通告的保留字段
+    nd(vec![ND_ROUTER_SOLICIT, 0, 0, 0, 0xff, 0, 0, 0x01]);
+    let mut ns = vec![ND_NEIGHBOR_SOLICIT, 0, 0, 0, 0, 0, 0x80, 0];
+    ns.extend([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    nd(ns);
+    let mut na = vec![ND_NEIGHBOR_ADVERT, 0, 0, 0, 0xb0, 0, 0, 0x2a];
+    na.extend([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+    let Some(Icmpv6Msg::NeighborAdvert {
+        router,
+        solicited,
+        override_,
+        reserved,
+        ..
+    }) = nd(na).msg
+    else {
+        panic!("不是邻居通告");
+    };
+    assert_eq!((router, solicited, override_), (true, false, true));
+    assert_eq!(reserved, 0x1000_002a);
+}
+
+#[test]
+fn checksum_incremental_update() {
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let old = gen_ip(&mut rng).checksum();
+        // 重组或转发时会改动的字段
+        let new = old
+            .clone()
+            .ttl(rng.u8())
+            .flag(rng.bool(), false)
+            .offset(0)
+            .payload_len(rng.u16() / 2)
+            .update_checksum(&old);
+        assert!(new.verify(), "{new:?}");
+        assert_eq!(new.chksum, new.clone().checksum().chksum);
+    }
+}
+
+#[test]
+fn ip_record_fields() {
+    use crate::output::{self, Value};
+
+    let mut rng = Rng::new();
+    for _ in 0..ROUNDS {
+        let hdr = gen_ip(&mut rng).checksum();
+        let record = output::ipv4(&hdr, checksum::Verdict::Valid);
+        assert_eq!(record.at(&["ttl"]), &Value::from(hdr.ttl));
+        assert_eq!(record.at(&["checksum"]), &Value::from(hdr.chksum));
+        let src = std::net::Ipv4Addr::from(hdr.source).to_string();
+        assert_eq!(record.at(&["src"]), &Value::from(src));
+        assert_eq!(record.at(&["nonexistent", "field"]), &Value::Null);
+    }
+    // 字符串按 JSON 规则转义，CSV 中含逗号或引号的格加引号
+    let text = Value::from("a\"b\\c\n\u{1}");
+    assert_eq!(text.to_string(), r#""a\"b\\c\n\u0001""#);
+    assert_eq!(Value::from("x,\"y\"").csv(), r#""x,""y""""#);
+    assert_eq!(Value::Float(f64::NAN).to_string(), "null");
+}
+
+#[test]
+fn records_ignore_lang() {
+    use crate::{
+        i18n::{with_lang, Lang},
+        output,
+    };
+
+    // 结构化记录中的说明文字不随界面语言变化
+    let ip = IPHdr::new(1).record_route(2).router_alert().checksum();
+    let tcp = TcpHdr::new(1, 2).option(TcpOption::Mss(1460));
+    let icmp = ICMP::new(3, 1);
+    let icmpv6 = Icmpv6::neighbor_solicit([0xfe; 16], [2; 6]);
+    let records = |lang| {
+        with_lang(lang, || {
+            let valid = checksum::Verdict::Valid;
+            [
+                output::ipv4(&ip, valid),
+                output::tcp(&tcp, valid),
+                output::icmp(&icmp, valid),
+                output::icmpv6(&icmpv6, valid),
+            ]
+            .map(|record| record.to_string())
+        })
+    };
+    let en = records(Lang::En);
+    assert_eq!(records(Lang::ZhCn), en);
+    assert!(
+        en[2].contains("\"description\":\"host unreachable\""),
+        "{}",
+        en[2]
+    );
+}
+
+/// 合法首部的每个更短前缀都应报告本层的 `Truncated`，且偏移量加上剩余字节数等于前缀长度
+fn truncated<H: Header>(bytes: &[u8], layer: Layer) {
+    for n in 0..bytes.len() {
+        match H::from_bytes(&bytes[..n]) {
+            Err(e @ ParseError::Truncated { have, need, .. }) => {
+                assert_eq!(e.layer(), layer, "{e}");
+                assert_eq!(e.offset() + have, n, "{e}, bytes: {bytes:02x?}");
+                assert!(have < need, "{e}");
+            }
+            Err(e) => panic!("{n} 字节: {e:?}, bytes: {bytes:02x?}"),
+            Ok(_) => panic!("{n} 字节解析成功, bytes: {bytes:02x?}"),
+        }
+    }
+}
+
+/// 带逐跳选项、路由、分片和目的选项扩展首部的 IPv6 首部
+fn ipv6_with_exts() -> Ipv6Hdr {
+    let mut hdr = Ipv6Hdr::new([0xfd; 16], [0xfe; 16], Protocol::UDP).hlim(3);
+    hdr.tclass = 0xb8;
+    hdr.flow = 0xabcde;
+    hdr.exts = vec![
+        ExtHdr::HopByHop(vec![5, 2, 0, 0, 1, 0]),
+        ExtHdr::Routing {
+            typ: 0,
+            segleft: 1,
+            data: vec![0; 20],
+        },
+        ExtHdr::Fragment {
+            offset: 0x1fff,
+            mf: true,
+            ident: 0xdeadbeef,
+        },
+        ExtHdr::DestOpts(vec![1, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+    ];
+    hdr.payload_len(8)
+}
+
+fn ether(etype: EtherKind) -> EtherHdr {
+    EtherHdr::new([0xff; 6], [2; 6], etype)
+}
+
+/// QinQ：外层服务标签与内层客户标签
+fn qinq() -> EtherHdr {
+    let stag = VlanTag {
+        tpid: TPID_STAG,
+        pcp: 5,
+        dei: true,
+        vid: 100,
+    };
+    ether(EtherKind::IP).vlans(vec![stag, VlanTag::new(200)])
+}
+
+#[test]
+fn truncated_headers() {
+    truncated::<EtherHdr>(&ether(EtherKind::IP).to_bytes(), Layer::Ether);
+    truncated::<EtherHdr>(&qinq().to_bytes(), Layer::Ether);
+    truncated::<IPHdr>(&IPHdr::new(1).to_bytes(), Layer::IP);
+    truncated::<IPHdr>(&IPHdr::new(1).router_alert().to_bytes(), Layer::IP);
+    for icmp in [
+        ICMP::new(8, 0).with_ident(1).with_seqnum(2),
+        ICMP::new(0, 0).with_ident(1).with_seqnum(2),
+        ICMP::new(3, 1),
+        ICMP::new(11, 0),
+    ] {
+        truncated::<ICMP>(&icmp.to_bytes(), Layer::ICMP);
+    }
+    truncated::<UdpHdr>(&UdpHdr::new(1234, 53).to_bytes(), Layer::UDP);
+    let tcp = TcpHdr::new(1234, 80).option(TcpOption::Mss(1460));
+    truncated::<TcpHdr>(&tcp.to_bytes(), Layer::TCP);
+    let arp = ArpHdr::request([2; 6], [10, 0, 0, 1], [10, 0, 0, 2]);
+    truncated::<ArpHdr>(&arp.to_bytes(), Layer::ARP);
+    truncated::<Ipv6Hdr>(&ipv6_with_exts().to_bytes(), Layer::IPv6);
+    // 邻居发现报文在选项边界处截断仍是合法的报文，只检查回显
+    let echo = nd_messages().swap_remove(0);
+    truncated::<Icmpv6>(&echo.to_bytes(), Layer::ICMPv6);
+}
+
+#[test]
+fn vlan_stack() {
+    let ethdr = qinq();
+    assert_eq!(ethdr.size(), 22);
+    let bytes = ethdr.clone().to_bytes();
+    // 每个标签为 TPID 与 TCI，最内层之后才是上层协议类型
+    assert_eq!(
+        bytes[12..22],
+        [0x88, 0xa8, 0xb0, 100, 0x81, 0, 0, 200, 8, 0]
+    );
+    let (parsed, rest) = EtherHdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, ethdr);
+    assert!(rest.is_empty());
+    for tag in &parsed.vlans {
+        assert_eq!(VlanTag::from_tci(tag.tpid, tag.tci()), *tag);
+    }
+}
+
+#[test]
+fn malformed_ip() {
+    let bytes = IPHdr::new(1).to_bytes();
+    let with = |at: usize, value: u8| {
+        let mut bytes = bytes.clone();
+        bytes[at] = value;
+        IPHdr::from_bytes(&bytes).unwrap_err()
+    };
+    let (layer, offset) = (Layer::IP, 0);
+    let version = 6;
+    assert_eq!(
+        with(0, 0x65),
+        ParseError::BadVersion {
+            layer,
+            offset,
+            version
+        }
+    );
+    assert_eq!(
+        with(0, 0x44),
+        ParseError::BadIhl {
+            layer,
+            offset,
+            ihl: 16
+        }
+    );
+    let len = 19;
+    assert_eq!(
+        with(3, 19),
+        ParseError::BadLength {
+            layer,
+            offset: 2,
+            len
+        }
+    );
+    // 首部长度声明了 24 字节，但只有 20 字节
+    let (need, have) = (24, 20);
+    assert_eq!(
+        with(0, 0x46),
+        ParseError::Truncated {
+            layer,
+            offset,
+            need,
+            have
+        }
+    );
+    // 选项长度超出选项区
+    let mut bytes = IPHdr::new(1).option(IpOption::RouterAlert(0)).to_bytes();
+    bytes[21] = 8;
+    let err = IPHdr::from_bytes(&bytes).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::BadLength {
+            layer,
+            offset: 21,
+            len: 8
+        }
+    );
+}
+
+#[test]
+fn ip_options_slice() {
+    // 选项区为第 20 字节到首部长度为止，其后的数据原样留给上层
+    let hdr = IPHdr::new(1)
+        .option(IpOption::RouterAlert(0))
+        .payload_len(3);
+    assert_eq!(hdr.ihl, 24);
+    let mut bytes = hdr.to_bytes();
+    bytes.extend([0xde, 0xad, 0xbe]);
+    let (parsed, rest) = IPHdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.options, [IpOption::RouterAlert(0)]);
+    assert_eq!(rest, [0xde, 0xad, 0xbe]);
+    let (parsed, rest) = IPHdr::from_bytes(&bytes[..24]).unwrap();
+    assert_eq!(parsed.options_len(), 4);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn ipv6_ext_chain() {
+    let hdr = ipv6_with_exts();
+    assert!(hdr.is_fragment());
+    // 扩展首部依次占 8、24、8、16 字节
+    assert_eq!(hdr.ext_len(), 56);
+    assert_eq!(hdr.plen, 64);
+    let mut bytes = hdr.clone().to_bytes();
+    assert_eq!(bytes.len(), 96);
+    assert_eq!(bytes[6], ExtHdr::HopByHop(vec![]).code());
+    bytes.extend(UdpHdr::new(1, 2).to_bytes());
+    let (parsed, rest) = Ipv6Hdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, hdr);
+    assert_eq!(UdpHdr::from_bytes(rest).unwrap().0, UdpHdr::new(1, 2));
+
+    let mut bad = bytes;
+    bad[0] = 0x45;
+    let err = Ipv6Hdr::from_bytes(&bad).unwrap_err();
+    assert_eq!((err.layer(), err.offset()), (Layer::IPv6, 0));
+    assert!(matches!(err, ParseError::BadVersion { version: 4, .. }));
+}
+
+/// 回显请求与各类邻居发现报文
+fn nd_messages() -> Vec<Icmpv6> {
+    let target = [0xfd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    let options = vec![
+        NdOption::PrefixInfo {
+            prefix_len: 64,
+            onlink: true,
+            autonomous: false,
+            reserved1: 0,
+            valid: 3600,
+            preferred: 1800,
+            reserved2: 0,
+            prefix: target,
+        },
+        NdOption::Mtu {
+            reserved: 0,
+            mtu: 1500,
+        },
+        NdOption::Unknown {
+            kind: 25,
+            data: vec![0xaa; 14],
+        },
+    ];
+    let with = |typ: u8, msg: Icmpv6Msg| Icmpv6 {
+        msg: Some(msg),
+        ..Icmpv6::new(typ, 0)
+    };
+    vec![
+        with(
+            ICMPV6_ECHO_REQUEST,
+            Icmpv6Msg::Echo(Ping {
+                ident: 1,
+                seqnum: 2,
+            }),
+        ),
+        with(
+            ND_ROUTER_SOLICIT,
+            Icmpv6Msg::RouterSolicit {
+                reserved: 0,
+                options: vec![],
+            },
+        ),
+        with(
+            ND_ROUTER_ADVERT,
+            Icmpv6Msg::RouterAdvert {
+                hop_limit: 64,
+                managed: true,
+                other: false,
+                flags: 0,
+                lifetime: 1800,
+                reachable: 30000,
+                retrans: 1000,
+                options,
+            },
+        ),
+        Icmpv6::neighbor_solicit(target, [2; 6]),
+        with(
+            ND_NEIGHBOR_ADVERT,
+            Icmpv6Msg::NeighborAdvert {
+                router: false,
+                solicited: true,
+                override_: true,
+                reserved: 0,
+                target,
+                options: vec![NdOption::TargetLinkAddr([4; 6])],
+            },
+        ),
+    ]
+}
+
+#[test]
+fn icmpv6_nd() {
+    let ipv6 = Ipv6Hdr::new([0xfe; 16], [0xff; 16], Protocol::ICMPv6);
+    for icmpv6 in nd_messages() {
+        // 邻居发现报文的选项一直延续到报文末尾，只有回显带数据
+        let data: &[u8] = match icmpv6.msg {
+            Some(Icmpv6Msg::Echo(_)) => b"abc",
+            _ => b"",
+        };
+        let icmpv6 = icmpv6.checksum(&ipv6, data);
+        let mut bytes = icmpv6.clone().to_bytes();
+        bytes.extend(data);
+        let (parsed, rest) = Icmpv6::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, icmpv6);
+        assert_eq!(rest, data);
+        assert!(icmpv6.verify(&ipv6, data), "{icmpv6:?}");
+    }
+
+    // 长度为 0 的选项是非法的，偏移指向长度字段
+    let mut bytes = Icmpv6::neighbor_solicit([0; 16], [2; 6]).to_bytes();
+    bytes[25] = 0;
+    assert_eq!(
+        Icmpv6::from_bytes(&bytes).unwrap_err(),
+        ParseError::BadLength {
+            layer: Layer::ICMPv6,
+            offset: 25,
+            len: 0
+        }
+    );
+}
+
+#[test]
+fn malformed_headers() {
+    let mut udp = UdpHdr::new(1, 2).to_bytes();
+    udp[5] = 7;
+    let err = UdpHdr::from_bytes(&udp).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::BadLength {
+            layer: Layer::UDP,
+            offset: 4,
+            len: 7
+        }
+    );
+
+    let mut tcp = TcpHdr::new(1, 2).to_bytes();
+    tcp[12] = 0x40;
+    let err = TcpHdr::from_bytes(&tcp).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::BadIhl {
+            layer: Layer::TCP,
+            offset: 12,
+            ihl: 16
+        }
+    );
+
+    let arp = ArpHdr::request([2; 6], [10, 0, 0, 1], [10, 0, 0, 2]).to_bytes();
+    let mut bad = arp.clone();
+    bad[1] = 6;
+    let err = ArpHdr::from_bytes(&bad).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnknownType {
+            layer: Layer::ARP,
+            offset: 0,
+            value: 6
+        }
+    );
+    let mut bad = arp;
+    bad[4] = 8;
+    let err = ArpHdr::from_bytes(&bad).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::BadLength {
+            layer: Layer::ARP,
+            offset: 4,
+            len: 8
+        }
+    );
+}
+
+#[test]
+fn arp_request_reply() {
+    // 请求与应答交换收发双方的地址，经以太网帧往返后不变
+    let request = ArpHdr::request([2; 6], [10, 0, 0, 1], [10, 0, 0, 2]);
+    let reply = ArpHdr::reply(&request, [4; 6]);
+    assert_eq!(
+        (reply.oper, reply.sha, reply.spa, reply.tha, reply.tpa),
+        (ARP_REPLY, [4; 6], [10, 0, 0, 2], [2; 6], [10, 0, 0, 1])
+    );
+    for arp in [request, reply] {
+        let mut frame = (ether(EtherKind::ARP), arp.clone()).to_bytes();
+        assert_eq!(frame.len(), 42);
+        frame.resize(60, 0);
+        let ((_, parsed), rest) = <(EtherHdr, ArpHdr)>::from_bytes(&frame).unwrap();
+        assert_eq!(parsed, arp);
+        assert_eq!(rest, [0; 18]);
+    }
+}
+
+#[test]
+fn error_offset_is_shifted() {
+    // 内层首部的错误偏移相对于整个帧
+    let mut frame = (ether(EtherKind::IP), IPHdr::new(1)).to_bytes();
+    frame[14] = 0x65;
+    let err = <(EtherHdr, IPHdr)>::from_bytes(&frame)
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err.layer(), Layer::IP);
+    assert_eq!(err.offset(), 14);
+    let (need, have) = (20, 6);
+    let err = <(EtherHdr, IPHdr)>::from_bytes(&frame[..20])
+        .map(|_| ())
+        .unwrap_err();
+    let layer = Layer::IP;
+    assert_eq!(
+        err,
+        ParseError::Truncated {
+            layer,
+            offset: 14,
+            need,
+            have
+        }
+    );
+}
+
+#[test]
+fn ip_fields() {
+    let iphdr = IPHdr::new(0x1234)
+        .ttl(3)
+        .tos(0xb8)
+        .flag(true, true)
+        .offset(0x1fff)
+        .protocol(Protocol::UDP)
+        .source([192, 0, 2, 1])
+        .destination([192, 0, 2, 2])
+        .payload_len(8)
+        .checksum();
+    let bytes = iphdr.clone().to_bytes();
+    assert_eq!(bytes[1], 0xb8);
+    assert_eq!(bytes[2..4], [0, 28]);
+    assert_eq!(bytes[4..6], [0x12, 0x34]);
+    // DF、MF 与 13 位片偏移共用两个字节
+    assert_eq!(bytes[6..8], [0x7f, 0xff]);
+    assert_eq!(bytes[8], 3);
+    let (parsed, rest) = IPHdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, iphdr);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn udp_checksum() {
+    // 伪首部 192.0.2.1 -> 192.0.2.2，奇数长度的数据末尾补 0
+    let iphdr = IPHdr::new(1)
+        .source([192, 0, 2, 1])
+        .destination([192, 0, 2, 2])
+        .protocol(Protocol::UDP);
+    let udp = UdpHdr::new(1234, 53).checksum(&iphdr, b"abc");
+    assert_eq!((udp.len, udp.chksum), (11, 0xb26a));
+
+    // 与 send 相同的构造顺序：先有源地址，再算 UDP 校验和与 IP 总长度
+    for len in 0..64 {
+        let data = (0..len).map(|b| b as u8 ^ 0x5a).collect::<Vec<_>>();
+        let iphdr = IPHdr::new(len)
+            .source([10, 0, 0, len as u8])
+            .destination([192, 0, 2, 2])
+            .protocol(Protocol::UDP);
+        let udp = UdpHdr::new(4000 + len, 53).checksum(&iphdr, &data);
+        let mut datagram = udp.to_bytes();
+        datagram.extend(&data);
+        let iphdr = iphdr.payload_len(datagram.len() as u16).checksum();
+        let mut packet = iphdr.to_bytes();
+        packet.extend(&datagram);
+
+        let (iphdr, rest) = IPHdr::from_bytes(&packet).unwrap();
+        assert_eq!(iphdr.totlen as usize, packet.len());
+        assert_eq!(iphdr.source, [10, 0, 0, len as u8]);
+        let (udp, data) = UdpHdr::from_bytes(rest).unwrap();
+        assert_eq!(udp.len as usize, datagram.len());
+        assert_ne!(udp.chksum, 0);
+        assert!(iphdr.verify());
+        assert!(udp.verify(&iphdr, data), "{udp:?}");
+    }
+}
+
+#[test]
+fn tcp_options_overflow() {
+    // 11 个 MSS 选项共 44 字节，只有前 10 个放得下
+    let hdr = (0..11).fold(TcpHdr::new(1, 2), |hdr, n| hdr.option(TcpOption::Mss(n)));
+    assert_eq!(
+        (hdr.options.len(), hdr.options_len(), hdr.doff),
+        (10, 40, 60)
+    );
+    let bytes = hdr.clone().to_bytes();
+    let (parsed, rest) = TcpHdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, hdr);
+    assert!(rest.is_empty());
+    // 直接修改字段时也不会写出超过 60 字节的首部
+    let mut bad = hdr.clone();
+    bad.options.push(TcpOption::Mss(10));
+    bad.doff = 255;
+    let bytes = bad.to_bytes();
+    assert_eq!(bytes.len(), 60);
+    assert_eq!(bytes[12] >> 4, 15);
+    let (parsed, rest) = TcpHdr::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed, hdr);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn icmp_echo_checksum() {
+    // 奇数长度的数据末尾按补 0 计算，整个报文的反码和应为全 1
+    for len in 0..64u8 {
+        let data = (0..len).collect::<Vec<_>>();
+        let icmp = ICMP::new(8, 0).with_ident(1).with_seqnum(len as u16);
+        let mut bytes = icmp.checksum(&data).to_bytes();
+        bytes.extend(&data);
+        let mut sum = bytes
+            .chunks(2)
+            .map(|bs| (bs[0] as u32) << 8 | bs.get(1).copied().unwrap_or(0) as u32)
+            .sum::<u32>();
+        while sum >> 16 != 0 {
+            sum = (sum >> 16) + (sum & 0xffff);
+        }
+        assert_eq!(sum, 0xffff, "{bytes:02x?}");
+    }
+}
+
+#[test]
+fn icmp_quoted() {
+    // 超时报文引用原始 IP 首部及其后 8 字节（探测的 UDP 首部）
+    let probe = IPHdr::new(7)
+        .ttl(1)
+        .protocol(Protocol::UDP)
+        .source([192, 0, 2, 1])
+        .destination([198, 51, 100, 7]);
+    let udp = UdpHdr::new(40000, 33434);
+    let mut bytes = ICMP::new(11, 0).to_bytes();
+    bytes.extend([0; 4]);
+    bytes.extend(probe.clone().to_bytes());
+    bytes.extend(udp.clone().to_bytes());
+    let (icmp, rest) = ICMP::from_bytes(&bytes).unwrap();
+    assert!(icmp.is_error());
+    let (quoted, rest) = icmp.quoted(rest).unwrap();
+    assert_eq!(quoted, probe);
+    assert_eq!(UdpHdr::from_bytes(rest).unwrap().0, udp);
+
+    // 引用的首部被截断时，偏移相对于 ICMP 首部
+    let err = icmp.quoted(&bytes[4..20]).unwrap_err();
+    assert_eq!((err.layer(), err.offset()), (Layer::IP, 8));
+
+    // 只有差错报告报文引用原始 IP 首部
+    let bytes = ICMP::new(8, 0).with_ident(1).to_bytes();
+    let (echo, rest) = ICMP::from_bytes(&bytes).unwrap();
+    assert!(!echo.is_error());
+    let err = echo.quoted(rest).unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnknownType {
+            layer: Layer::ICMP,
+            offset: 0,
+            value: 8
+        }
+    );
+}
@@ -0,0 +1,88 @@
+use super::{checksum, take, Header, Layer, ParseError, PseudoHeader};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UdpHdr {
+    /// 源端口号，占 16 位。不需要对方回信时可全为 0。
+    pub sport: u16,
+    /// 目的端口号，占 16 位。
+    pub dport: u16,
+    /// UDP 首部和数据之和，单位为字节，最小值为 8。
+    pub len: u16,
+    /// 检验首部和数据，计算时需加上 IP 伪首部。在 IPv4 中为 0 时表示不校验。
+    pub chksum: u16,
+}
+
+impl UdpHdr {
+    pub fn new(sport: u16, dport: u16) -> Self {
+        Self {
+            sport,
+            dport,
+            len: 8,
+            chksum: 0,
+        }
+    }
+
+    /// 根据所在 IP 报文的伪首部与数据计算长度和校验和
+    pub fn checksum(mut self, iphdr: &impl PseudoHeader, data: &[u8]) -> Self {
+        self.len = 8 + data.len() as u16;
+        self.chksum = 0;
+        let mut bytes = iphdr.pseudo_header(self.len as usize);
+        bytes.extend(self.clone().to_bytes());
+        bytes.extend_from_slice(data);
+
+        // 计算结果为 0 时以全 1 表示，以区别于“不校验”
+        self.chksum = match checksum::checksum(&bytes) {
+            0 => 0xffff,
+            chksum => chksum,
+        };
+
+        self
+    }
+
+    /// 所在 IP 报文的伪首部、首部与数据 `data` 的校验和是否正确。
+    /// 校验和为 0 表示未计算，由调用方区分处理。
+    pub fn verify(&self, iphdr: &impl PseudoHeader, data: &[u8]) -> bool {
+        // 长度字段之后的字节是下层的填充
+        let data = &data[..data.len().min((self.len as usize).saturating_sub(8))];
+        let mut bytes = iphdr.pseudo_header(self.len as usize);
+        bytes.extend(self.clone().to_bytes());
+        bytes.extend_from_slice(data);
+        checksum::verify(&bytes)
+    }
+}
+
+impl Header for UdpHdr {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), ParseError> {
+        let (hdr, rest) = take(bytes, 8, Layer::UDP)?;
+        let sport = u16::from_be_bytes([hdr[0], hdr[1]]);
+        let dport = u16::from_be_bytes([hdr[2], hdr[3]]);
+        let len = u16::from_be_bytes([hdr[4], hdr[5]]);
+        let chksum = u16::from_be_bytes([hdr[6], hdr[7]]);
+        if len < 8 {
+            return Err(ParseError::BadLength {
+                layer: Layer::UDP,
+                offset: 4,
+                len: len as usize,
+            });
+        }
+
+        Ok((
+            UdpHdr {
+                sport,
+                dport,
+                len,
+                chksum,
+            },
+            rest,
+        ))
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(self.sport.to_be_bytes());
+        bytes.extend(self.dport.to_be_bytes());
+        bytes.extend(self.len.to_be_bytes());
+        bytes.extend(self.chksum.to_be_bytes());
+        bytes
+    }
+}
@@ -0,0 +1,164 @@
+//! 消息目录：按当前语言查找界面文本。
+//!
+//! 每条消息由键标识，文本中的 `{name}` 由 [`msg!`] 的同名参数替换。当前语言缺少的
+//! 消息回退到英文目录。命令行帮助的键为 `help.<子命令>.<参数>`，找不到时再查
+//! `help.<参数>`；中文帮助直接取自 `cli.rs` 的文档注释，因此只出现在英文目录中。
+
+mod en;
+#[cfg(test)]
+mod tests;
+mod zh_cn;
+
+use std::{cell::Cell, collections::HashMap, fmt::Display, sync::OnceLock};
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    En,
+    /// 简体中文，未设置语言时的缺省值
+    #[default]
+    ZhCn,
+}
+
+impl Lang {
+    /// 由 `--lang` 的参数或 `LANG` 形式的区域设置识别语言，例如 `en`、`zh-CN`、`zh_CN.UTF-8`
+    pub fn parse(name: &str) -> Option<Lang> {
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        let lang = name.split(['_', '-']).next().unwrap_or_default();
+        match lang.to_ascii_lowercase().as_str() {
+            "zh" => Some(Lang::ZhCn),
+            "en" | "c" | "posix" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    fn messages(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::En => en::MESSAGES,
+            Lang::ZhCn => zh_cn::MESSAGES,
+        }
+    }
+
+    fn catalog(self) -> &'static HashMap<&'static str, &'static str> {
+        static EN: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        static ZH_CN: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        let cell = match self {
+            Lang::En => &EN,
+            Lang::ZhCn => &ZH_CN,
+        };
+        cell.get_or_init(|| self.messages().iter().copied().collect())
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+thread_local! {
+    /// 由 [`with_lang`] 临时指定的当前线程语言
+    static SCOPED: Cell<Option<Lang>> = const { Cell::new(None) };
+}
+
+/// 设置界面语言，只有第一次调用有效
+pub fn init(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    SCOPED
+        .get()
+        .or_else(|| LANG.get().copied())
+        .unwrap_or_default()
+}
+
+/// 临时以语言 `lang` 执行 `f`，用于生成与界面语言无关的结构化输出
+pub fn with_lang<T>(lang: Lang, f: impl FnOnce() -> T) -> T {
+    let outer = SCOPED.replace(Some(lang));
+    let value = f();
+    SCOPED.set(outer);
+    value
+}
+
+/// 按命令行的 `--lang`、环境变量 `LC_ALL`、`LC_MESSAGES`、`LANG` 的顺序确定语言。
+/// 都未设置时为简体中文，设置了无法识别的语言时为英文
+pub fn detect(args: impl IntoIterator<Item = String>) -> Lang {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        let value = match arg.strip_prefix("--lang") {
+            Some("") => args.next(),
+            Some(value) => value.strip_prefix('=').map(str::to_string),
+            None => None,
+        };
+        if let Some(lang) = value.as_deref().and_then(Lang::parse) {
+            return lang;
+        }
+    }
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map_or(Lang::ZhCn, |value| Lang::parse(&value).unwrap_or(Lang::En))
+}
+
+/// 当前语言中的消息，缺少时使用英文
+pub fn get(key: &str) -> Option<&'static str> {
+    lang()
+        .catalog()
+        .get(key)
+        .or_else(|| Lang::En.catalog().get(key))
+        .copied()
+}
+
+/// 仅在当前语言的目录中查找
+pub fn get_exact(key: &str) -> Option<&'static str> {
+    lang().catalog().get(key).copied()
+}
+
+/// 键对应的消息，目录中没有时原样返回键
+pub fn text(key: &'static str) -> &'static str {
+    get(key).unwrap_or(key)
+}
+
+/// 以参数替换消息中的 `{name}`
+pub fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+        let value = tail.find('}').and_then(|end| {
+            let name = &tail[..end];
+            let (_, value) = args.iter().find(|(n, _)| *n == name)?;
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(&value.to_string());
+                rest = &tail[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = tail;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 当前语言的消息：`msg!("key")` 得到 `&'static str`，
+/// `msg!("key", name = value, ...)` 替换参数后得到 `String`
+macro_rules! msg {
+    ($key:literal) => {
+        $crate::i18n::text($key)
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::text($key),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+pub(crate) use msg;
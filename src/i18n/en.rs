@@ -0,0 +1,445 @@
+//! 英文消息目录，也是其他语言缺少消息时的回退
+
+pub const MESSAGES: &[(&str, &str)] = &[
+    (
+        "error.truncated",
+        "{layer} header truncated (offset {offset}): need {need} bytes, have {have}",
+    ),
+    ("error.bad_version", "{layer} bad version (offset {offset}): {version}"),
+    ("error.bad_ihl", "{layer} bad header length (offset {offset}): {ihl} bytes"),
+    ("error.bad_length", "{layer} bad length field (offset {offset}): {len}"),
+    ("error.unknown_type", "{layer} unknown type (offset {offset}): {value}"),
+    ("checksum.valid", "correct"),
+    ("checksum.invalid", "incorrect, should be {expected}"),
+    ("checksum.offloaded", "computed by NIC"),
+    ("checksum.absent", "not computed"),
+    ("checksum.truncated", "not verified, packet truncated"),
+    ("ether.llc", "LLC(DSAP {dsap}, SSAP {ssap}, control {control})"),
+    ("ether.snap", ", SNAP(OUI {oui}, protocol {pid})"),
+    ("ether.vlan", "VLAN {vid}(TPID {tpid}, priority {pcp}"),
+    ("ether.vlan_dei", ", drop eligible"),
+    ("ip.option.route", "{name}[{addrs}] ({left} left)"),
+    ("ip.option.rr", "Record Route"),
+    ("ip.option.lsrr", "Loose Source Route"),
+    ("ip.option.ssrr", "Strict Source Route"),
+    ("ip.option.timestamp", "Timestamp[{stamps}] (flag {flag}, overflow {overflow})"),
+    ("ip.option.security", "Security(level {level}, authority {authority})"),
+    ("ip.option.router_alert", "Router Alert({value})"),
+    ("ipv6.hop_by_hop", "Hop-by-Hop Options({len} bytes)"),
+    ("ipv6.routing", "Routing(type {typ}, {left} segments left)"),
+    ("ipv6.fragment", "Fragment(offset {offset} bytes, MF {mf}, ID {ident})"),
+    ("ipv6.dest_opts", "Destination Options({len} bytes)"),
+    ("nd.source_link_addr", "source link-layer address {addr}"),
+    ("nd.target_link_addr", "target link-layer address {addr}"),
+    (
+        "nd.prefix",
+        "prefix {prefix}/{len} (L={onlink} A={autonomous}, valid {valid}s, preferred {preferred}s)",
+    ),
+    ("nd.unknown", "unknown option {kind}({len} bytes)"),
+    ("icmp.0.0", "echo reply (ping reply)"),
+    ("icmp.8.0", "echo request (ping request)"),
+    ("icmp.9.0", "router advertisement"),
+    ("icmp.10.0", "router solicitation"),
+    ("icmp.13.0", "timestamp request (obsolete)"),
+    ("icmp.14.0", "timestamp reply (obsolete)"),
+    ("icmp.15.0", "information request (obsolete)"),
+    ("icmp.16.0", "information reply (obsolete)"),
+    ("icmp.17.0", "address mask request"),
+    ("icmp.18.0", "address mask reply"),
+    ("icmp.3.0", "network unreachable"),
+    ("icmp.3.1", "host unreachable"),
+    ("icmp.3.2", "protocol unreachable"),
+    ("icmp.3.3", "port unreachable"),
+    ("icmp.3.6", "destination network unknown"),
+    ("icmp.3.7", "destination host unknown"),
+    ("icmp.3.9", "destination network administratively prohibited"),
+    ("icmp.3.10", "destination host administratively prohibited"),
+    ("icmp.3.11", "network unreachable for TOS"),
+    ("icmp.3.12", "host unreachable for TOS"),
+    ("icmp.3.13", "communication administratively prohibited by filtering"),
+    ("icmp.4.0", "source quench"),
+    ("icmp.5.0", "redirect for network"),
+    ("icmp.5.1", "redirect for host"),
+    ("icmp.5.2", "redirect for TOS and network"),
+    ("icmp.5.3", "redirect for TOS and host"),
+    ("icmp.11.0", "TTL exceeded in transit"),
+    ("icmp.11.1", "fragment reassembly time exceeded"),
+    ("icmp.12.0", "bad IP header"),
+    ("icmp.12.1", "required option missing"),
+    ("icmp.undefined", "undefined"),
+    ("icmpv6.1.0", "no route to destination"),
+    ("icmpv6.1.1", "communication with destination administratively prohibited"),
+    ("icmpv6.1.3", "address unreachable"),
+    ("icmpv6.1.4", "port unreachable"),
+    ("icmpv6.2.0", "packet too big"),
+    ("icmpv6.3.0", "hop limit exceeded in transit"),
+    ("icmpv6.3.1", "fragment reassembly time exceeded"),
+    ("icmpv6.4", "parameter problem"),
+    ("icmpv6.128.0", "echo request (ping request)"),
+    ("icmpv6.129.0", "echo reply (ping reply)"),
+    ("icmpv6.133.0", "router solicitation"),
+    ("icmpv6.134.0", "router advertisement"),
+    ("icmpv6.135.0", "neighbor solicitation"),
+    ("icmpv6.136.0", "neighbor advertisement"),
+    ("icmpv6.137.0", "redirect"),
+    ("app.offline", "network interfaces are not available in offline mode"),
+    ("send.version_mismatch", "source and destination addresses are of different IP versions"),
+    ("send.too_long", "payload of {len} bytes exceeds the IPv4 packet limit"),
+    (
+        "send.offset_too_large",
+        "{len} bytes of data at fragment offset {offset} exceed the IPv4 packet limit",
+    ),
+    ("send.df_frag_size", "cannot set a fragment size when the don't-fragment flag is set"),
+    (
+        "send.mtu_df",
+        "packet length {len} exceeds MTU {mtu} of interface {iface} and the don't-fragment flag is set",
+    ),
+    ("send.mtu", "packet length {len} exceeds MTU {mtu} of interface {iface}"),
+    ("send.options_overflow", "IP options take {len} bytes, exceeding the 40-byte limit"),
+    ("send.route_too_long", "a source route holds at most 9 addresses, got {len}"),
+    (
+        "filter.bpf_fallback",
+        "filter is too complex to compile to BPF; all frames will be filtered in userspace",
+    ),
+    ("filter.bpf_too_complex", "filter is too complex to compile to BPF"),
+    ("filter.malformed", "dropped malformed packet ({count} so far): {error}"),
+    ("filter.title", "============IP packet analysis============"),
+    ("filter.end", "=========================================="),
+    ("filter.vlans", "VLAN tags: {tags}"),
+    ("filter.frag_cached", "fragment cached, waiting for reassembly"),
+    ("filter.frag_data", "fragment data:\n{data}"),
+    ("filter.reassembled", "reassembly complete: datagram of {len} bytes"),
+    ("filter.bad_header", "failed to parse {protocol} header: {error}"),
+    ("filter.data", "data:\n{data}"),
+    ("filter.done", "done: {total} frames, {matched} matched, {malformed} malformed"),
+    (
+        "filter.reassembly",
+        "reassembly: {expired} groups timed out, {overlaps} overlapping fragments",
+    ),
+    ("ipv4.version", "IP version: {version}, header length: {ihl} bytes, TOS: {tos}"),
+    ("ipv4.length", "total length: {len}, ID: {ident}"),
+    ("ipv4.flags", "don't fragment: {df}, more fragments: {mf}"),
+    ("ipv4.offset", "fragment offset: {offset} bytes"),
+    ("ipv4.ttl", "TTL: {ttl} hops"),
+    ("ip.protocol", "protocol: {protocol}"),
+    ("ip.addrs", "source: {src}, destination: {dst}"),
+    ("ipv6.version", "IP version: {version}, traffic class: {tclass}, flow label: {flow}"),
+    ("ipv6.length", "payload length: {plen}, hop limit: {hlim} hops"),
+    ("ipv6.exts", "extension headers: {exts}"),
+    ("checksum.line", "checksum: {checksum} ({verdict})"),
+    ("options.line", "options: {options}"),
+    ("transport.ports", "source port: {sport}, destination port: {dport}"),
+    ("tcp.title", "------------TCP segment header------------"),
+    ("tcp.seq", "sequence number: {seq}, acknowledgment number: {ack}"),
+    ("tcp.doff", "header length: {doff} bytes, flags: {flags}"),
+    ("tcp.window", "window: {window}, urgent pointer: {urgent}"),
+    ("udp.title", "------------UDP datagram header------------"),
+    ("udp.len", "length: {len}"),
+    ("icmp.title", "------------ICMP message------------"),
+    ("icmpv6.title", "------------ICMPv6 message------------"),
+    ("icmp.type", "type: {typ}, code: {code} ({description})"),
+    ("icmp.ping", "identifier: {ident}, sequence number: {seq}"),
+    (
+        "nd.ra_flags",
+        "hop limit: {hop_limit}, managed address configuration: {managed}, other configuration: {other}",
+    ),
+    (
+        "nd.ra_timers",
+        "router lifetime: {lifetime} s, reachable time: {reachable} ms, retransmit timer: {retrans} ms",
+    ),
+    ("nd.target", "target address: {target}"),
+    ("nd.na_flags", "router: {router}, solicited: {solicited}, override: {override_}"),
+    ("ping.start", "PING {dst} {size}({total}) bytes of data."),
+    ("ping.reply", "{len} bytes from {src}: icmp_seq={seq} ttl={ttl} time={rtt} ms"),
+    ("ping.stats_title", "--- {dst} ping statistics ---"),
+    (
+        "ping.stats",
+        "{sent} packets transmitted, {received} received, {loss}% packet loss, time {time} ms",
+    ),
+    ("ping.rtt", "rtt min/avg/max/mdev = {rtts} ms"),
+    ("trace.unsupported", "tracing routes with protocol {protocol} is not supported"),
+    ("trace.start", "traceroute to {dst}, {hops} hops max, {queries} {protocol} probes per hop:"),
+    ("arp.timeout", "ARP resolution of {addr} timed out"),
+    ("ndp.timeout", "neighbor discovery of {addr} timed out"),
+    ("analyz.ip_proto", "IP protocol {p}"),
+    ("analyz.ethertype", "type {etype}"),
+    ("analyz.malformed", "malformed"),
+    ("analyz.total", "{packets} frames, {bytes} bytes in {secs} s, average {pps} pps, {rate}"),
+    ("analyz.protocols", "------------protocols------------"),
+    ("analyz.counts", "{packets} frames {bytes} bytes"),
+    ("analyz.src_ip", "source IP"),
+    ("analyz.dst_ip", "destination IP"),
+    ("analyz.src_mac", "source MAC"),
+    ("analyz.dst_mac", "destination MAC"),
+    ("analyz.top", "------------top {top} {title} by traffic------------"),
+    ("analyz.sizes", "------------frame sizes------------"),
+    ("analyz.ttls", "------------TTL distribution (top {top})------------"),
+    ("analyz.fragments", "fragments: {ipv4} IPv4, {ipv6} IPv6"),
+    ("analyz.done", "done: {total} frames, {others} non-IP, {malformed} malformed"),
+    ("decode.error", "at byte {offset} of data: {reason}"),
+    ("decode.utf8", "invalid UTF-8"),
+    ("decode.hex_digit", "{c} is not a hexadecimal digit"),
+    ("decode.hex_odd", "odd number of hexadecimal digits"),
+    ("decode.base64_padding", "data after padding `=`"),
+    ("decode.base64_char", "{c} is not a Base64 character"),
+    ("decode.base64_length", "incomplete Base64 data"),
+    ("decode.escape_missing", "missing escape character after `\\`"),
+    ("decode.escape_hex", "`\\x` must be followed by two hexadecimal digits"),
+    ("decode.escape_unknown", "{c} after `\\` is not a recognized escape"),
+    ("expr.error", "filter expression, character {offset}: {reason}"),
+    ("expr.extra_paren", "unmatched `)`"),
+    ("expr.expect_op", "expected `and` or `or`, found {token}"),
+    ("expr.bad_char", "unrecognized character `{c}`"),
+    ("expr.expect", "expected {what}, found {token}"),
+    ("expr.missing", "incomplete expression, missing {what}"),
+    ("expr.expect_rparen", "expected `)`, found {token} (to match `(` at character {open})"),
+    ("expr.unclosed", "unclosed parenthesis"),
+    ("expr.what.primitive", "a filter primitive"),
+    ("expr.what.after_dir", "`host`, `net`, `port` or an address"),
+    ("expr.what.protocol", "a protocol name or number"),
+    ("expr.what.addr", "an IP address"),
+    ("expr.what.net", "a network address"),
+    ("expr.what.port", "a port number"),
+    ("expr.what.mac", "a MAC address"),
+    ("expr.what.mask", "a mask"),
+    ("expr.what.integer", "an integer"),
+    ("expr.unknown_protocol", "unknown protocol `{word}`"),
+    ("expr.unknown_field", "unknown field `{word}`, available fields are {names}"),
+    ("expr.unknown_primitive", "unknown filter primitive `{word}`"),
+    ("expr.bad_addr", "`{word}` is not a valid IP address"),
+    ("expr.bad_prefix", "prefix length `{len}` must be an integer from 0 to {max}"),
+    ("expr.host_bits", "network address `{word}` has nonzero host bits"),
+    ("expr.bad_port", "port `{word}` must be an integer from 0 to 65535"),
+    ("expr.bad_mac", "`{word}` is not a valid MAC address"),
+    ("error.vlan_id", "VLAN ID `{vid}` must be an integer from 0 to 4095"),
+    ("expr.bad_mask", "mask `{word}` is not a valid integer"),
+    ("expr.expect_cmp", "expected a comparison operator after field `{field}`, found {token}"),
+    (
+        "expr.missing_cmp",
+        "incomplete expression, missing comparison operator after field `{field}`",
+    ),
+    ("expr.bad_integer", "`{word}` is not a valid integer"),
+    ("cli.unknown_format", "unknown file format `{value}`"),
+    ("cli.unknown_encoding", "unknown encoding `{value}`"),
+    ("cli.unknown_output", "unknown output format `{value}`"),
+    ("cli.bad_pcp", "priority `{pcp}` must be an integer from 0 to 7"),
+    ("iface.no_ipv4", "interface {iface} has no IPv4 address"),
+    ("iface.no_ipv6", "interface {iface} has no IPv6 address"),
+    ("iface.not_found", "no interface named {iface}"),
+    ("iface.down", "interface {iface} is down"),
+    ("route.none", "no route to {dst}"),
+    ("pcap.not_pcap", "not a pcap or pcapng file"),
+    ("pcap.linktype", "unsupported link type {linktype}"),
+    ("pcap.bad_bom", "bad pcapng byte-order magic"),
+    ("pcap.bad_shb", "bad pcapng section header block length"),
+    ("pcap.bad_idb", "bad pcapng interface description block length"),
+    ("pcap.bad_block", "bad pcapng block length"),
+    ("pcap.bad_iface", "pcapng references undefined interface {iface}"),
+    ("pcap.bad_packet", "pcapng packet length exceeds block length"),
+    ("cli.unknown_lang", "unknown language `{value}`, expected en or zh-CN"),
+    ("help.about", "Send and capture IP packets, filter and analyze them."),
+    (
+        "help.interface",
+        "Network interface for sending and receiving. Defaults to the routed outgoing interface; captures on all interfaces",
+    ),
+    (
+        "help.lang",
+        "Interface language: en or zh-CN. Defaults to the LC_ALL, LC_MESSAGES or LANG environment variable",
+    ),
+    ("help.interfaces.about", "List local network interfaces and their addresses"),
+    ("help.send.about", "Send an IP datagram"),
+    ("help.ping.about", "Send ICMP echo requests and report round-trip times"),
+    ("help.trace.about", "Trace the route to a host by increasing the TTL hop by hop"),
+    ("help.analyz.about", "Analyze the types and volume of received IP packets"),
+    ("help.filter.about", "Filter received IP packets and display their headers"),
+    ("help.read", "Read frames from a pcap or pcapng file instead of capturing from an interface"),
+    ("help.write", "Write captured frames to a file"),
+    ("help.format", "Format of the written file: pcap or pcapng"),
+    (
+        "help.analyz.count",
+        "Stop after analyzing this many frames. By default runs until Ctrl-C or end of file",
+    ),
+    (
+        "help.analyz.duration",
+        "Maximum capture duration in seconds. Measured by frame timestamps when reading a file",
+    ),
+    (
+        "help.analyz.interval",
+        "Interval in seconds between refreshes of the statistics on a terminal; 0 prints only the final result",
+    ),
+    ("help.analyz.top", "Number of entries shown in traffic rankings"),
+    (
+        "help.analyz.output",
+        "Format of the statistics: text, json (one document at the end), ndjson (one line per refresh) or csv",
+    ),
+    (
+        "help.filter.expression",
+        "Filter expression, e.g. `tcp and port 80`, `net 10.0.0.0/8 and ttl < 5`, `icmp.type == 3 or not (host ::1)`. May span several arguments, joined by spaces",
+    ),
+    ("help.filter.src_mac", "Source MAC address"),
+    ("help.filter.dst_mac", "Destination MAC address"),
+    ("help.filter.shost", "Source IP address, IPv4 or IPv6"),
+    ("help.filter.dhost", "Destination IP address, IPv4 or IPv6"),
+    (
+        "help.filter.vlan",
+        "Only show frames carrying this VLAN ID at any tag level; may be given multiple times",
+    ),
+    (
+        "help.filter.bad_checksum",
+        "Only show packets with incorrect checksums (checksums left to the NIC are not errors)",
+    ),
+    (
+        "help.filter.dump_bpf",
+        "Print the BPF program compiled from the filter in `tcpdump -d` format and exit",
+    ),
+    ("help.filter.output", "Packet output format: text, json, ndjson (one packet per line) or csv"),
+    (
+        "help.filter.verbose",
+        "Show a layered field tree and hex dump (-v layers, -vv fields, -vvv raw bytes)",
+    ),
+    (
+        "help.filter.snaplen",
+        "Keep only the first N bytes of each frame for decoding, display and capture files",
+    ),
+    (
+        "help.filter.highlight",
+        "Highlight this layer in the hex dump: link, network, transport or payload (needs -v)",
+    ),
+    (
+        "help.filter.log",
+        "Write matched packet records to rotating log files in this directory, one JSON record per line",
+    ),
+    (
+        "help.filter.log_size",
+        "Rotate a log file once it reaches this size; accepts K, M, G suffixes, 0 disables",
+    ),
+    (
+        "help.filter.log_interval",
+        "Rotate a log file after this many seconds (frame timestamps when reading a file)",
+    ),
+    ("help.filter.log_keep", "Number of rotated log files to keep; older ones are deleted"),
+    ("help.filter.log_gzip", "Compress rotated log files with gzip"),
+    (
+        "help.send.dhost",
+        "Destination MAC address. By default resolved via ARP for the destination host or gateway",
+    ),
+    (
+        "help.send.vlan",
+        "Add a VLAN tag to the frame, formatted as `VID[:PCP]`. Repeated tags stack from outer to inner, outer ones using 802.1ad. The destination MAC is still resolved on the untagged link; use `--dhost` if needed",
+    ),
+    ("help.send.destip", "Destination IP address, IPv4 or IPv6"),
+    ("help.send.srcip", "Source IP address. Defaults to the address of the outgoing interface"),
+    ("help.send.ttl", "Time to live (hop limit for IPv6)"),
+    ("help.send.tos", "Type of service (traffic class for IPv6)"),
+    ("help.send.ident", "Packet identification, IPv4 only"),
+    ("help.send.df", "Set the don't-fragment (DF) flag, IPv4 only"),
+    ("help.send.mf", "Set the more-fragments (MF) flag, IPv4 only"),
+    ("help.send.offset", "Fragment offset in 8-byte units, IPv4 only"),
+    (
+        "help.send.frag_size",
+        "Force fragmentation at this size in bytes (rounded down to a multiple of 8). By default only packets exceeding the interface MTU are fragmented. IPv4 only",
+    ),
+    (
+        "help.send.record_route",
+        "Add a Record Route option with room for this many addresses, IPv4 only",
+    ),
+    ("help.send.timestamp", "Add a Timestamp option with room for this many timestamps, IPv4 only"),
+    (
+        "help.send.lsrr",
+        "Add a Loose Source Route option; addresses to pass through in order, may be given up to 9 times. IPv4 only",
+    ),
+    (
+        "help.send.ssrr",
+        "Add a Strict Source Route option; addresses to pass through in order, may be given up to 9 times. IPv4 only",
+    ),
+    ("help.send.router_alert", "Add a Router Alert option, IPv4 only"),
+    ("help.send.protocol", "Protocol: TCP, UDP, ICMP, ICMPv6 or a decimal one-byte number"),
+    ("help.send.sport", "UDP source port"),
+    ("help.send.dport", "UDP destination port"),
+    ("help.send.encoding", "Encoding of the packet data: hex, base64, text, escaped or raw"),
+    ("help.send.text", "Packet data"),
+    ("help.send.file", "Path of a file with packet data, decoded according to `--encoding`"),
+    (
+        "help.ping.dhost",
+        "Destination MAC address. By default resolved via ARP for the destination host or gateway",
+    ),
+    ("help.ping.destip", "Destination IP address"),
+    ("help.ping.count", "Number of requests to send. By default sends until interrupted"),
+    ("help.ping.interval", "Interval between requests in seconds"),
+    (
+        "help.ping.size",
+        "Number of data bytes in each request; with the IPv4 and ICMP headers at most 65535",
+    ),
+    ("help.ping.ttl", "Time to live of the requests"),
+    ("help.ping.deadline", "Maximum total running time in seconds"),
+    (
+        "help.trace.dhost",
+        "Destination MAC address (usually the gateway). By default resolved via ARP",
+    ),
+    ("help.trace.destip", "Destination IP address"),
+    ("help.trace.protocol", "Probe protocol: ICMP (echo request), UDP (high ports) or TCP (SYN)"),
+    (
+        "help.trace.port",
+        "Destination port. UDP counts up from 33434 by default, TCP defaults to 80",
+    ),
+    ("help.trace.first_ttl", "Initial time to live"),
+    ("help.trace.max_hops", "Maximum number of hops"),
+    ("help.trace.queries", "Number of probes per hop"),
+    ("help.trace.wait", "Time to wait for a reply to each probe, in seconds"),
+    ("tree.frame", "Frame: {len} bytes on wire, {caplen} bytes captured"),
+    ("tree.range", "[offset {offset}, length {len}]"),
+    ("tree.field", "{name}: {value}"),
+    ("tree.bytes", "{len} bytes"),
+    ("tree.ether", "Ethernet, Src: {src}, Dst: {dst}"),
+    ("tree.ipv4", "Internet Protocol Version 4, Src: {src}, Dst: {dst}"),
+    ("tree.ipv6", "Internet Protocol Version 6, Src: {src}, Dst: {dst}"),
+    (
+        "tree.tcp",
+        "Transmission Control Protocol, Src Port: {sport}, Dst Port: {dport}, Flags: {flags}",
+    ),
+    ("tree.udp", "User Datagram Protocol, Src Port: {sport}, Dst Port: {dport}"),
+    ("tree.icmp", "Internet Control Message Protocol, {description}"),
+    ("tree.icmpv6", "Internet Control Message Protocol v6, {description}"),
+    ("tree.data", "Data ({len} bytes)"),
+    ("tree.fragment", "Fragment data ({len} bytes)"),
+    ("field.dst", "Destination"),
+    ("field.src", "Source"),
+    ("field.etype", "Type"),
+    ("field.version", "Version"),
+    ("field.hdr_len", "Header Length"),
+    ("field.tos", "Type of Service"),
+    ("field.total_len", "Total Length"),
+    ("field.ident", "Identification"),
+    ("field.flags", "Flags"),
+    ("field.frag_offset", "Fragment Offset"),
+    ("field.ttl", "Time to Live"),
+    ("field.protocol", "Protocol"),
+    ("field.checksum", "Checksum"),
+    ("field.options", "Options"),
+    ("field.tclass", "Traffic Class"),
+    ("field.flow", "Flow Label"),
+    ("field.payload_len", "Payload Length"),
+    ("field.next_header", "Next Header"),
+    ("field.hop_limit", "Hop Limit"),
+    ("field.sport", "Source Port"),
+    ("field.dport", "Destination Port"),
+    ("field.seq", "Sequence Number"),
+    ("field.ack", "Acknowledgment Number"),
+    ("field.window", "Window"),
+    ("field.urgent", "Urgent Pointer"),
+    ("field.length", "Length"),
+    ("field.type", "Type"),
+    ("field.code", "Code"),
+    ("field.target", "Target Address"),
+    ("field.lifetime", "Router Lifetime"),
+    ("field.reachable", "Reachable Time"),
+    ("field.retrans", "Retransmit Timer"),
+    ("cli.unknown_section", "unknown layer {value}; expected link, network, transport or payload"),
+    (
+        "cli.bad_size",
+        "invalid size {value}; expected a byte count with an optional K, M or G suffix",
+    ),
+    ("cli.bad_address", "invalid address {value}; expected {parts} parts"),
+    ("cli.bad_seconds", "invalid number of seconds {value}; expected a finite non-negative number"),
+    ("pcap.too_large", "record length {len} exceeds the {max}-byte limit; the file may be corrupt"),
+];
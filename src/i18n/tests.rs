@@ -0,0 +1,53 @@
+//! 消息目录的一致性：各语言的键与占位符相同，以及占位符的替换
+
+use std::collections::HashSet;
+
+use super::*;
+
+/// 消息中 `{name}` 形式的占位符
+fn placeholders(text: &str) -> HashSet<&str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+#[test]
+fn catalogs_match() {
+    let en = Lang::En.catalog();
+    let zh = Lang::ZhCn.catalog();
+    assert_eq!(en.len(), en::MESSAGES.len(), "英文目录有重复的键");
+    assert_eq!(zh.len(), zh_cn::MESSAGES.len(), "中文目录有重复的键");
+    for (key, text) in zh {
+        let Some(en_text) = en.get(key) else {
+            panic!("英文目录缺少 {key}");
+        };
+        assert_eq!(
+            placeholders(text),
+            placeholders(en_text),
+            "{key} 的占位符不一致"
+        );
+    }
+    // 中文帮助取自文档注释
+    for key in en.keys().filter(|key| !key.starts_with("help.")) {
+        assert!(zh.contains_key(key), "中文目录缺少 {key}");
+    }
+}
+
+#[test]
+fn fill_placeholders() {
+    let text = fill("{a} 与 {b}，{a}；{c} {", &[("a", &1), ("b", &"二")]);
+    assert_eq!(text, "1 与 二，1；{c} {");
+}
+
+#[test]
+fn parse_lang() {
+    assert_eq!(Lang::parse("zh_CN.UTF-8"), Some(Lang::ZhCn));
+    assert_eq!(Lang::parse("zh-CN"), Some(Lang::ZhCn));
+    assert_eq!(Lang::parse("en_US.UTF-8"), Some(Lang::En));
+    assert_eq!(Lang::parse("C"), Some(Lang::En));
+    assert_eq!(Lang::parse("fr_FR"), None);
+    let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert_eq!(detect(args(&["--lang", "en", "filter"])), Lang::En);
+    assert_eq!(detect(args(&["filter", "--lang=zh-CN"])), Lang::ZhCn);
+}
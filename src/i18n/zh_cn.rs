@@ -0,0 +1,386 @@
+//! 简体中文消息目录
+
+pub const MESSAGES: &[(&str, &str)] = &[
+    (
+        "error.truncated",
+        "{layer} 首部被截断（偏移 {offset}）：需要 {need} 字节，实际 {have} 字节",
+    ),
+    (
+        "error.bad_version",
+        "{layer} 版本号错误（偏移 {offset}）：{version}",
+    ),
+    (
+        "error.bad_ihl",
+        "{layer} 首部长度错误（偏移 {offset}）：{ihl} byte",
+    ),
+    (
+        "error.bad_length",
+        "{layer} 长度字段错误（偏移 {offset}）：{len}",
+    ),
+    (
+        "error.unknown_type",
+        "{layer} 类型未知（偏移 {offset}）：{value}",
+    ),
+    ("checksum.valid", "正确"),
+    ("checksum.invalid", "错误，应为 {expected}"),
+    ("checksum.offloaded", "由网卡计算"),
+    ("checksum.absent", "未计算"),
+    ("checksum.truncated", "报文不完整，未检验"),
+    ("ether.llc", "LLC(DSAP {dsap}, SSAP {ssap}, 控制 {control})"),
+    ("ether.snap", ", SNAP(OUI {oui}, 协议 {pid})"),
+    ("ether.vlan", "VLAN {vid}(TPID {tpid}, 优先级 {pcp}"),
+    ("ether.vlan_dei", ", 可丢弃"),
+    ("ip.option.route", "{name}[{addrs}]（剩余 {left} 项）"),
+    ("ip.option.rr", "记录路由"),
+    ("ip.option.lsrr", "宽松源路由"),
+    ("ip.option.ssrr", "严格源路由"),
+    (
+        "ip.option.timestamp",
+        "时间戳[{stamps}]（标志 {flag}，溢出 {overflow}）",
+    ),
+    (
+        "ip.option.security",
+        "安全(级别 {level}, 保护机构 {authority})",
+    ),
+    ("ip.option.router_alert", "路由器警告({value})"),
+    ("ipv6.hop_by_hop", "逐跳选项({len} byte)"),
+    ("ipv6.routing", "路由(类型 {typ}, 剩余 {left} 段)"),
+    (
+        "ipv6.fragment",
+        "分片(偏移 {offset} byte, MF {mf}, ID {ident})",
+    ),
+    ("ipv6.dest_opts", "目的选项({len} byte)"),
+    ("nd.source_link_addr", "源链路地址 {addr}"),
+    ("nd.target_link_addr", "目标链路地址 {addr}"),
+    (
+        "nd.prefix",
+        "前缀 {prefix}/{len}（L={onlink} A={autonomous}，有效期 {valid}s，首选期 {preferred}s）",
+    ),
+    ("nd.unknown", "未知选项 {kind}({len} byte)"),
+    ("icmp.0.0", "回显应答（ping应答）"),
+    ("icmp.8.0", "请求回显（ping请求）"),
+    ("icmp.9.0", "路由器通告"),
+    ("icmp.10.0", "路由器请求"),
+    ("icmp.13.0", "时间戳请求（目前已不使用）"),
+    ("icmp.14.0", "时间戳应答（目前已不使用）"),
+    ("icmp.15.0", "信息请求（目前已不使用）"),
+    ("icmp.16.0", "信息应答（目前已不使用）"),
+    ("icmp.17.0", "地址掩码请求"),
+    ("icmp.18.0", "地址掩码应答"),
+    ("icmp.3.0", "网络不可达"),
+    ("icmp.3.1", "主机不可达"),
+    ("icmp.3.2", "协议不可达"),
+    ("icmp.3.3", "端口不可达"),
+    ("icmp.3.6", "目的网络不认识"),
+    ("icmp.3.7", "目的主机不认识"),
+    ("icmp.3.9", "目的网络被强制禁止"),
+    ("icmp.3.10", "目的主机被强制隔离"),
+    ("icmp.3.11", "由于TOS,网络不可达"),
+    ("icmp.3.12", "由于TOS,主机不可达"),
+    ("icmp.3.13", "由于过滤，通信被强制禁止"),
+    ("icmp.4.0", "源端被关闭"),
+    ("icmp.5.0", "对网络重定向"),
+    ("icmp.5.1", "对主机重定向"),
+    ("icmp.5.2", "对服务类型和网络重定向"),
+    ("icmp.5.3", "对服务类型和主机重定向"),
+    ("icmp.11.0", "传输期间生存时间为0"),
+    ("icmp.11.1", "在数据报组装期间生存时间为0"),
+    ("icmp.12.0", "坏的IP首部"),
+    ("icmp.12.1", "缺少必须的选项"),
+    ("icmp.undefined", "未定义"),
+    ("icmpv6.1.0", "没有到目的地址的路由"),
+    ("icmpv6.1.1", "与目的地址的通信被管理性禁止"),
+    ("icmpv6.1.3", "地址不可达"),
+    ("icmpv6.1.4", "端口不可达"),
+    ("icmpv6.2.0", "报文过大"),
+    ("icmpv6.3.0", "传输期间跳数限制为0"),
+    ("icmpv6.3.1", "分片重组超时"),
+    ("icmpv6.4", "参数错误"),
+    ("icmpv6.128.0", "请求回显（ping请求）"),
+    ("icmpv6.129.0", "回显应答（ping应答）"),
+    ("icmpv6.133.0", "路由器请求"),
+    ("icmpv6.134.0", "路由器通告"),
+    ("icmpv6.135.0", "邻居请求"),
+    ("icmpv6.136.0", "邻居通告"),
+    ("icmpv6.137.0", "重定向"),
+    ("app.offline", "离线模式下无法访问网络接口"),
+    ("send.version_mismatch", "源地址与目的地址的协议版本不一致"),
+    ("send.too_long", "数据长度 {len} 超出 IPv4 报文上限"),
+    (
+        "send.offset_too_large",
+        "片偏移 {offset} 处的 {len} 字节数据超出 IPv4 报文上限",
+    ),
+    ("send.df_frag_size", "设置了不分片标志时不能指定分片大小"),
+    (
+        "send.mtu_df",
+        "报文长度 {len} 超过接口 {iface} 的 MTU {mtu}，且设置了不分片标志",
+    ),
+    ("send.mtu", "报文长度 {len} 超过接口 {iface} 的 MTU {mtu}"),
+    (
+        "send.options_overflow",
+        "IP 选项共 {len} 字节，超过 40 字节的上限",
+    ),
+    (
+        "send.route_too_long",
+        "源路由最多经过 9 个地址，指定了 {len} 个",
+    ),
+    (
+        "filter.bpf_fallback",
+        "过滤条件过于复杂，无法编译为 BPF 程序，将在用户态过滤所有帧",
+    ),
+    (
+        "filter.bpf_too_complex",
+        "过滤条件过于复杂，无法编译为 BPF 程序",
+    ),
+    (
+        "filter.malformed",
+        "丢弃畸形报文（累计 {count} 个）：{error}",
+    ),
+    ("filter.title", "============IP报文数据分析============"),
+    ("filter.end", "======================================="),
+    ("filter.vlans", "VLAN标签：{tags}"),
+    ("filter.frag_cached", "分片已缓存，等待重组"),
+    ("filter.frag_data", "分片数据：\n{data}"),
+    ("filter.reassembled", "分片重组完成：数据报共 {len} 字节"),
+    ("filter.bad_header", "{protocol}首部解析失败：{error}"),
+    ("filter.data", "数据：\n{data}"),
+    (
+        "filter.done",
+        "读取完毕：共 {total} 帧，匹配 {matched} 帧，畸形 {malformed} 帧",
+    ),
+    (
+        "filter.reassembly",
+        "分片重组：超时丢弃 {expired} 组，重叠分片 {overlaps} 个",
+    ),
+    (
+        "ipv4.version",
+        "IP版本：{version}, 首部长：{ihl} byte, TOS：{tos}",
+    ),
+    ("ipv4.length", "数据长度: {len}, 报文ID：{ident}"),
+    ("ipv4.flags", "允许分片：{df}, 已分片：{mf}"),
+    ("ipv4.offset", "片偏移：{offset} byte"),
+    ("ipv4.ttl", "生存期：{ttl} 跳"),
+    ("ip.protocol", "协议：{protocol}"),
+    ("ip.addrs", "源: {src}, 目的IP：{dst}"),
+    (
+        "ipv6.version",
+        "IP版本：{version}, 流量类别：{tclass}, 流标签：{flow}",
+    ),
+    ("ipv6.length", "载荷长度：{plen}, 跳数限制：{hlim} 跳"),
+    ("ipv6.exts", "扩展首部：{exts}"),
+    ("checksum.line", "校验和：{checksum}（{verdict}）"),
+    ("options.line", "选项：{options}"),
+    ("transport.ports", "源端口：{sport}, 目的端口：{dport}"),
+    ("tcp.title", "------------TCP报文段首部------------"),
+    ("tcp.seq", "序号：{seq}, 确认号：{ack}"),
+    ("tcp.doff", "首部长：{doff} byte, 控制位：{flags}"),
+    ("tcp.window", "窗口：{window}, 紧急指针：{urgent}"),
+    ("udp.title", "------------UDP数据报首部------------"),
+    ("udp.len", "长度：{len}"),
+    ("icmp.title", "------------ICMP报文------------"),
+    ("icmpv6.title", "------------ICMPv6报文------------"),
+    ("icmp.type", "类型：{typ}, 代码：{code}（{description}）"),
+    ("icmp.ping", "标识：{ident}, 序号：{seq}"),
+    (
+        "nd.ra_flags",
+        "跳数限制：{hop_limit}, 管理地址配置：{managed}, 其他配置：{other}",
+    ),
+    (
+        "nd.ra_timers",
+        "路由器生存期：{lifetime} s, 可达时间：{reachable} ms, 重传间隔：{retrans} ms",
+    ),
+    ("nd.target", "目标地址：{target}"),
+    (
+        "nd.na_flags",
+        "路由器：{router}, 应答请求：{solicited}, 覆盖：{override_}",
+    ),
+    ("ping.start", "正在 Ping {dst}，数据 {size}({total}) 字节："),
+    (
+        "ping.reply",
+        "{len} 字节来自 {src}：icmp_seq={seq} ttl={ttl} 时间={rtt} ms",
+    ),
+    ("ping.stats_title", "--- {dst} ping 统计 ---"),
+    (
+        "ping.stats",
+        "已发送 {sent} 个，已接收 {received} 个，丢失 {loss}%，用时 {time} ms",
+    ),
+    ("ping.rtt", "rtt 最小/平均/最大/mdev = {rtts} ms"),
+    ("trace.unsupported", "不支持以协议 {protocol} 进行路由跟踪"),
+    (
+        "trace.start",
+        "路由跟踪 {dst}，最多 {hops} 跳，每跳 {queries} 个 {protocol} 探测：",
+    ),
+    ("arp.timeout", "ARP 解析 {addr} 超时"),
+    ("ndp.timeout", "邻居发现解析 {addr} 超时"),
+    ("analyz.ip_proto", "IP协议 {p}"),
+    ("analyz.ethertype", "类型 {etype}"),
+    ("analyz.malformed", "畸形报文"),
+    (
+        "analyz.total",
+        "共 {packets} 帧 {bytes} 字节，用时 {secs} s，平均 {pps} pps，{rate}",
+    ),
+    ("analyz.protocols", "------------协议------------"),
+    ("analyz.counts", "{packets} 帧 {bytes} 字节"),
+    ("analyz.src_ip", "源IP"),
+    ("analyz.dst_ip", "目的IP"),
+    ("analyz.src_mac", "源MAC"),
+    ("analyz.dst_mac", "目的MAC"),
+    (
+        "analyz.top",
+        "------------{title}流量前 {top} 位------------",
+    ),
+    ("analyz.sizes", "------------帧长分布------------"),
+    (
+        "analyz.ttls",
+        "------------生存期分布（前 {top} 位）------------",
+    ),
+    ("analyz.fragments", "分片：IPv4 {ipv4} 个，IPv6 {ipv6} 个"),
+    (
+        "analyz.done",
+        "读取完毕：共 {total} 帧，非IP {others} 帧，畸形 {malformed} 帧",
+    ),
+    ("decode.error", "数据第 {offset} 字节处{reason}"),
+    ("decode.utf8", "不是合法的 UTF-8"),
+    ("decode.hex_digit", "的 {c} 不是十六进制数字"),
+    ("decode.hex_odd", "的十六进制数字不成对"),
+    ("decode.base64_padding", "的填充 `=` 之后还有数据"),
+    ("decode.base64_char", "的 {c} 不是 Base64 字符"),
+    ("decode.base64_length", "的 Base64 数据长度不完整"),
+    ("decode.escape_missing", "的 `\\` 后缺少转义字符"),
+    ("decode.escape_hex", "的 `\\x` 后应为两位十六进制数字"),
+    (
+        "decode.escape_unknown",
+        "的 `\\` 后的 {c} 不是可识别的转义字符",
+    ),
+    ("expr.error", "过滤表达式第 {offset} 个字符处{reason}"),
+    ("expr.extra_paren", "多余的 `)`"),
+    ("expr.expect_op", "应为 `and` 或 `or`，却遇到 {token}"),
+    ("expr.bad_char", "有无法识别的字符 `{c}`"),
+    ("expr.expect", "应为{what}，却遇到 {token}"),
+    ("expr.missing", "表达式不完整，缺少{what}"),
+    (
+        "expr.expect_rparen",
+        "应为 `)`，却遇到 {token}（对应第 {open} 个字符处的 `(`）",
+    ),
+    ("expr.unclosed", "括号没有闭合"),
+    ("expr.what.primitive", "过滤条件"),
+    ("expr.what.after_dir", "`host`、`net`、`port` 或地址"),
+    ("expr.what.protocol", "协议名称或协议号"),
+    ("expr.what.addr", "IP 地址"),
+    ("expr.what.net", "网络地址"),
+    ("expr.what.port", "端口号"),
+    ("expr.what.mac", "MAC 地址"),
+    ("expr.what.mask", "掩码"),
+    ("expr.what.integer", "整数"),
+    ("expr.unknown_protocol", "未知的协议 `{word}`"),
+    (
+        "expr.unknown_field",
+        "未知的字段 `{word}`，可用的字段有 {names}",
+    ),
+    ("expr.unknown_primitive", "未知的过滤条件 `{word}`"),
+    ("expr.bad_addr", "`{word}` 不是有效的 IP 地址"),
+    (
+        "expr.bad_prefix",
+        "前缀长度 `{len}` 应为 0 到 {max} 之间的整数",
+    ),
+    ("expr.host_bits", "网络地址 `{word}` 的主机部分不为 0"),
+    (
+        "expr.bad_port",
+        "端口号 `{word}` 应为 0 到 65535 之间的整数",
+    ),
+    ("expr.bad_mac", "`{word}` 不是有效的 MAC 地址"),
+    ("error.vlan_id", "VLAN ID `{vid}` 应为 0 到 4095 之间的整数"),
+    ("expr.bad_mask", "掩码 `{word}` 不是有效的整数"),
+    (
+        "expr.expect_cmp",
+        "字段 `{field}` 之后应为比较运算符，却遇到 {token}",
+    ),
+    (
+        "expr.missing_cmp",
+        "表达式不完整，字段 `{field}` 之后缺少比较运算符",
+    ),
+    ("expr.bad_integer", "`{word}` 不是有效的整数"),
+    ("cli.unknown_format", "未知的文件格式 `{value}`"),
+    ("cli.unknown_encoding", "未知的编码 `{value}`"),
+    ("cli.unknown_output", "未知的输出格式 `{value}`"),
+    ("cli.bad_pcp", "优先级 `{pcp}` 应为 0 到 7 之间的整数"),
+    ("iface.no_ipv4", "接口 {iface} 没有 IPv4 地址"),
+    ("iface.no_ipv6", "接口 {iface} 没有 IPv6 地址"),
+    ("iface.not_found", "没有接口 {iface}"),
+    ("iface.down", "接口 {iface} 未启用"),
+    ("route.none", "没有到 {dst} 的路由"),
+    ("pcap.not_pcap", "不是 pcap 或 pcapng 文件"),
+    ("pcap.linktype", "不支持的链路类型 {linktype}"),
+    ("pcap.bad_bom", "pcapng 字节序标识错误"),
+    ("pcap.bad_shb", "pcapng 节头块长度错误"),
+    ("pcap.bad_idb", "pcapng 接口描述块长度错误"),
+    ("pcap.bad_block", "pcapng 块长度错误"),
+    ("pcap.bad_iface", "pcapng 引用了未定义的接口 {iface}"),
+    ("pcap.bad_packet", "pcapng 分组长度超出块长度"),
+    (
+        "cli.unknown_lang",
+        "未知的语言 `{value}`，可选值有 en、zh-CN",
+    ),
+    ("tree.frame", "帧：线路上 {len} 字节，捕获 {caplen} 字节"),
+    ("tree.range", "[偏移 {offset}，长度 {len}]"),
+    ("tree.field", "{name}：{value}"),
+    ("tree.bytes", "{len} 字节"),
+    ("tree.ether", "以太网，源：{src}，目的：{dst}"),
+    ("tree.ipv4", "IPv4，源：{src}，目的：{dst}"),
+    ("tree.ipv6", "IPv6，源：{src}，目的：{dst}"),
+    (
+        "tree.tcp",
+        "TCP，源端口：{sport}，目的端口：{dport}，控制位：{flags}",
+    ),
+    ("tree.udp", "UDP，源端口：{sport}，目的端口：{dport}"),
+    ("tree.icmp", "ICMP，{description}"),
+    ("tree.icmpv6", "ICMPv6，{description}"),
+    ("tree.data", "数据（{len} 字节）"),
+    ("tree.fragment", "分片数据（{len} 字节）"),
+    ("field.dst", "目的地址"),
+    ("field.src", "源地址"),
+    ("field.etype", "类型"),
+    ("field.version", "版本"),
+    ("field.hdr_len", "首部长度"),
+    ("field.tos", "服务类型"),
+    ("field.total_len", "总长度"),
+    ("field.ident", "标识"),
+    ("field.flags", "标志"),
+    ("field.frag_offset", "片偏移"),
+    ("field.ttl", "生存期"),
+    ("field.protocol", "协议"),
+    ("field.checksum", "校验和"),
+    ("field.options", "选项"),
+    ("field.tclass", "流量类别"),
+    ("field.flow", "流标签"),
+    ("field.payload_len", "载荷长度"),
+    ("field.next_header", "下一首部"),
+    ("field.hop_limit", "跳数限制"),
+    ("field.sport", "源端口"),
+    ("field.dport", "目的端口"),
+    ("field.seq", "序号"),
+    ("field.ack", "确认号"),
+    ("field.window", "窗口"),
+    ("field.urgent", "紧急指针"),
+    ("field.length", "长度"),
+    ("field.type", "类型"),
+    ("field.code", "代码"),
+    ("field.target", "目标地址"),
+    ("field.lifetime", "路由器生存期"),
+    ("field.reachable", "可达时间"),
+    ("field.retrans", "重传间隔"),
+    (
+        "cli.unknown_section",
+        "未知的层 {value}，可选值有 link、network、transport、payload",
+    ),
+    (
+        "cli.bad_size",
+        "无效的大小 {value}，应为字节数，可带 K、M、G 后缀",
+    ),
+    ("cli.bad_address", "无效的地址 {value}，应由 {parts} 段组成"),
+    ("cli.bad_seconds", "无效的秒数 {value}，应为非负的有限数"),
+    (
+        "pcap.too_large",
+        "记录长度 {len} 超过上限 {max} 字节，文件可能已损坏",
+    ),
+];
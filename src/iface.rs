@@ -0,0 +1,195 @@
+use std::{
+    collections::BTreeMap,
+    ffi::CStr,
+    fmt::Display,
+    fs,
+    io::ErrorKind,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::i18n::msg;
+
+/// 网络接口信息
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Interface {
+    pub name: String,
+    /// 接口索引
+    pub index: i32,
+    pub mac: [u8; 6],
+    pub mtu: u32,
+    /// `IFF_*` 标志位
+    pub flags: u32,
+    /// IPv4 地址及其前缀长度
+    pub ipv4: Vec<([u8; 4], u8)>,
+    /// IPv6 地址及其前缀长度
+    pub ipv6: Vec<([u8; 16], u8)>,
+}
+
+impl Interface {
+    pub fn is_up(&self) -> bool {
+        self.flags & libc::IFF_UP as u32 != 0
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.flags & libc::IFF_LOOPBACK as u32 != 0
+    }
+
+    /// 接口的首个 IPv4 地址
+    pub fn addr(&self) -> std::io::Result<[u8; 4]> {
+        self.ipv4.first().map(|&(addr, _)| addr).ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::AddrNotAvailable,
+                msg!("iface.no_ipv4", iface = self.name),
+            )
+        })
+    }
+
+    /// 发往 `dst` 时使用的 IPv6 源地址：目的地址为链路本地地址时使用链路本地地址，
+    /// 否则优先使用全局地址
+    pub fn addr6(&self, dst: [u8; 16]) -> std::io::Result<[u8; 16]> {
+        let link_local = Ipv6Addr::from(dst).is_unicast_link_local();
+        self.ipv6
+            .iter()
+            .map(|&(addr, _)| addr)
+            .find(|&addr| Ipv6Addr::from(addr).is_unicast_link_local() == link_local)
+            .or_else(|| self.ipv6.first().map(|&(addr, _)| addr))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    ErrorKind::AddrNotAvailable,
+                    msg!("iface.no_ipv6", iface = self.name),
+                )
+            })
+    }
+
+    /// 标志位名称，与 `ip link` 的写法一致
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        [
+            (libc::IFF_UP, "UP"),
+            (libc::IFF_BROADCAST, "BROADCAST"),
+            (libc::IFF_LOOPBACK, "LOOPBACK"),
+            (libc::IFF_POINTOPOINT, "POINTOPOINT"),
+            (libc::IFF_RUNNING, "RUNNING"),
+            (libc::IFF_NOARP, "NOARP"),
+            (libc::IFF_PROMISC, "PROMISC"),
+            (libc::IFF_MULTICAST, "MULTICAST"),
+            (libc::IFF_LOWER_UP, "LOWER_UP"),
+        ]
+        .into_iter()
+        .filter(|&(flag, _)| self.flags & flag as u32 != 0)
+        .map(|(_, name)| name)
+        .collect()
+    }
+}
+
+impl Display for Interface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: {} <{}> mtu {}",
+            self.index,
+            self.name,
+            self.flag_names().join(","),
+            self.mtu
+        )?;
+        let mac = self
+            .mac
+            .iter()
+            .map(|c| format!("{:02x}", c))
+            .collect::<Vec<_>>()
+            .join(":");
+        write!(f, "    link {mac}")?;
+        for (addr, prefix) in &self.ipv4 {
+            write!(f, "\n    inet {}/{prefix}", Ipv4Addr::from(*addr))?;
+        }
+        for (addr, prefix) in &self.ipv6 {
+            write!(f, "\n    inet6 {}/{prefix}", Ipv6Addr::from(*addr))?;
+        }
+        Ok(())
+    }
+}
+
+/// 列出本机所有网络接口，按接口索引排序
+pub fn interfaces() -> std::io::Result<Vec<Interface>> {
+    let mut ifap = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut ifap) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut ifaces = BTreeMap::<String, Interface>::new();
+    let mut cur = ifap;
+    while let Some(ifa) = unsafe { cur.as_ref() } {
+        cur = ifa.ifa_next;
+        let name = unsafe { CStr::from_ptr(ifa.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+        let iface = ifaces.entry(name.clone()).or_insert_with(|| Interface {
+            name,
+            flags: ifa.ifa_flags,
+            ..Default::default()
+        });
+        let Some(addr) = (unsafe { ifa.ifa_addr.as_ref() }) else {
+            continue;
+        };
+        match addr.sa_family as libc::c_int {
+            libc::AF_PACKET => {
+                let ll = unsafe { &*ifa.ifa_addr.cast::<libc::sockaddr_ll>() };
+                iface.index = ll.sll_ifindex;
+                if ll.sll_halen == 6 {
+                    iface.mac.copy_from_slice(&ll.sll_addr[..6]);
+                }
+            }
+            libc::AF_INET => {
+                let sin = unsafe { &*ifa.ifa_addr.cast::<libc::sockaddr_in>() };
+                let mask = match unsafe { ifa.ifa_netmask.cast::<libc::sockaddr_in>().as_ref() } {
+                    Some(mask) => mask.sin_addr.s_addr.count_ones() as u8,
+                    None => 32,
+                };
+                iface.ipv4.push((sin.sin_addr.s_addr.to_ne_bytes(), mask));
+            }
+            libc::AF_INET6 => {
+                let sin6 = unsafe { &*ifa.ifa_addr.cast::<libc::sockaddr_in6>() };
+                let mask = match unsafe { ifa.ifa_netmask.cast::<libc::sockaddr_in6>().as_ref() } {
+                    Some(mask) => mask
+                        .sin6_addr
+                        .s6_addr
+                        .iter()
+                        .map(|b| b.count_ones() as u8)
+                        .sum(),
+                    None => 128,
+                };
+                iface.ipv6.push((sin6.sin6_addr.s6_addr, mask));
+            }
+            _ => {}
+        }
+    }
+    unsafe { libc::freeifaddrs(ifap) };
+
+    let mut ifaces = ifaces
+        .into_values()
+        .map(|mut iface| {
+            iface.mtu = fs::read_to_string(format!("/sys/class/net/{}/mtu", iface.name))
+                .ok()
+                .and_then(|mtu| mtu.trim().parse().ok())
+                .unwrap_or(0);
+            iface
+        })
+        .collect::<Vec<_>>();
+    ifaces.sort_by_key(|iface| iface.index);
+    Ok(ifaces)
+}
+
+/// 按名称查找接口，接口不存在或未启用时返回错误
+pub fn find<'a>(ifaces: &'a [Interface], name: &str) -> std::io::Result<&'a Interface> {
+    let iface = ifaces
+        .iter()
+        .find(|iface| iface.name == name)
+        .ok_or_else(|| {
+            std::io::Error::new(ErrorKind::NotFound, msg!("iface.not_found", iface = name))
+        })?;
+    if !iface.is_up() {
+        return Err(std::io::Error::new(
+            ErrorKind::NetworkDown,
+            msg!("iface.down", iface = name),
+        ));
+    }
+    Ok(iface)
+}
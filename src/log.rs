@@ -0,0 +1,254 @@
+//! 过滤结果的日志：按大小或时间轮转的 NDJSON 文件。
+//!
+//! 日志文件位于指定目录中，名为 `ipwrapper-<开始时间>.ndjson`。每个文件的第一行是记录
+//! 接口、过滤条件与开始时间的文件头，其后每行一个报文记录，格式与 `--output ndjson` 相同。
+//! 轮转出的文件可以压缩为 `.ndjson.gz`，目录中只保留最新的若干个。
+
+mod gzip;
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::output::{self, Value};
+
+/// 日志文件名的前缀
+const PREFIX: &str = "ipwrapper-";
+const EXT: &str = ".ndjson";
+const GZ_EXT: &str = ".ndjson.gz";
+
+/// 轮转策略
+#[derive(Debug, Clone, Default)]
+pub struct Rotation {
+    /// 文件达到此字节数后轮转
+    pub size: Option<u64>,
+    /// 文件写入此时长后轮转，按记录的时间戳计算
+    pub interval: Option<Duration>,
+    /// 保留的已轮转文件数
+    pub keep: usize,
+    /// 压缩已轮转的文件
+    pub gzip: bool,
+}
+
+/// 正在写入的日志文件
+#[derive(Debug)]
+struct Current {
+    path: PathBuf,
+    out: BufWriter<File>,
+    /// 文件中第一条记录的时间
+    start: SystemTime,
+    /// 已写入的字节数
+    size: u64,
+}
+
+#[derive(Debug)]
+pub struct LogWriter {
+    dir: PathBuf,
+    rotation: Rotation,
+    /// 每个文件的文件头，打开文件时补上开始时间
+    header: Value,
+    /// 尚未写入记录时没有打开的文件
+    current: Option<Current>,
+    /// 上一个文件名中的开始时间与序号，避免重用已删除文件的名字
+    last: Option<(String, usize)>,
+}
+
+impl LogWriter {
+    /// 在目录 `dir` 中写日志，目录不存在时创建。第一条记录写入时才创建文件
+    pub fn create(dir: &Path, rotation: Rotation, header: Value) -> std::io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            rotation,
+            header,
+            current: None,
+            last: None,
+        })
+    }
+
+    /// 写入时间为 `ts` 的一条记录，需要时先轮转
+    pub fn write(&mut self, record: &Value, ts: SystemTime) -> std::io::Result<()> {
+        if self.current.as_ref().is_some_and(|cur| self.due(cur, ts)) {
+            self.rotate()?;
+        }
+        let cur = match self.current.take() {
+            Some(cur) => cur,
+            None => self.open(ts)?,
+        };
+        let cur = self.current.insert(cur);
+        let line = format!("{record}\n");
+        cur.out.write_all(line.as_bytes())?;
+        cur.out.flush()?;
+        cur.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// 写完当前文件。当前文件不压缩，之后再次轮转时才计入保留数
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        match self.current.take() {
+            Some(mut cur) => cur.out.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// 时间为 `ts` 的记录是否应写入新文件
+    fn due(&self, cur: &Current, ts: SystemTime) -> bool {
+        let full = self.rotation.size.is_some_and(|size| cur.size >= size);
+        let elapsed = ts.duration_since(cur.start).unwrap_or_default();
+        full || self.rotation.interval.is_some_and(|iv| elapsed >= iv)
+    }
+
+    /// 以 `ts` 为开始时间创建新文件并写入文件头
+    fn open(&mut self, ts: SystemTime) -> std::io::Result<Current> {
+        let stamp = Utc::from(ts);
+        let name = format!("{PREFIX}{}", stamp.compact());
+        // 同一秒内轮转多次时加上递增的序号
+        let first = match &self.last {
+            Some((last, seq)) if *last == name => seq + 1,
+            _ => 0,
+        };
+        let (seq, path) = (first..)
+            .map(|n| match n {
+                0 => (n, self.dir.join(format!("{name}{EXT}"))),
+                n => (n, self.dir.join(format!("{name}-{n}{EXT}"))),
+            })
+            .find(|(_, path)| !path.exists() && !gz_path(path).exists())
+            .unwrap();
+        self.last = Some((name, seq));
+        let mut header = self.header.clone();
+        header.push("start", stamp.to_string());
+        header.push("ts", output::timestamp(ts));
+        let line = format!("{header}\n");
+        let mut out = BufWriter::new(File::create_new(&path)?);
+        out.write_all(line.as_bytes())?;
+        out.flush()?;
+        Ok(Current {
+            path,
+            out,
+            start: ts,
+            size: line.len() as u64,
+        })
+    }
+
+    /// 关闭当前文件，按需压缩，并删除超出保留数的旧文件
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let Some(mut cur) = self.current.take() else {
+            return Ok(());
+        };
+        cur.out.flush()?;
+        drop(cur.out);
+        if self.rotation.gzip {
+            let data = fs::read(&cur.path)?;
+            fs::write(gz_path(&cur.path), gzip::compress(&data))?;
+            fs::remove_file(&cur.path)?;
+        }
+        self.prune()
+    }
+
+    /// 按文件名中的开始时间与序号只保留最新的 `keep` 个已关闭的日志文件，包括之前运行留下的。
+    /// 不依赖修改时间，文件系统的时间精度较粗时也能区分同一秒内轮转出的文件
+    fn prune(&self) -> std::io::Result<()> {
+        let mut files = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some((stamp, seq)) = parse_name(&name) else {
+                continue;
+            };
+            if entry.file_type()?.is_file() {
+                files.push((stamp.to_string(), seq, entry.path()));
+            }
+        }
+        files.sort();
+        let excess = files.len().saturating_sub(self.rotation.keep);
+        for (_, _, path) in &files[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// 由日志文件名取出开始时间与序号，不是日志文件时为 `None`。
+/// 开始时间为定长的紧凑形式，按字符串比较即按时间先后
+fn parse_name(name: &str) -> Option<(&str, usize)> {
+    let stem = name.strip_prefix(PREFIX)?;
+    let stem = stem
+        .strip_suffix(GZ_EXT)
+        .or_else(|| stem.strip_suffix(EXT))?;
+    match stem.split_once('-') {
+        Some((stamp, seq)) => Some((stamp, seq.parse().ok()?)),
+        None => Some((stem, 0)),
+    }
+}
+
+/// 压缩后的文件路径
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    name.into()
+}
+
+/// UTC 时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Utc {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    micros: u32,
+}
+
+impl Utc {
+    /// 用于文件名的紧凑形式，例如 `20261017T081500Z`
+    fn compact(&self) -> String {
+        format!(
+            "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl From<SystemTime> for Utc {
+    fn from(ts: SystemTime) -> Self {
+        let ts = ts.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = ts.as_secs();
+        let (days, rem) = ((secs / 86400) as i64, (secs % 86400) as u32);
+        // 由 1970-01-01 起的天数换算公历日期，以 3 月 1 日为一年的开始
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        let year = yoe + era * 400 + (month <= 2) as i64;
+        Utc {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem / 60 % 60,
+            second: rem % 60,
+            micros: ts.subsec_micros(),
+        }
+    }
+}
+
+/// RFC 3339 格式，例如 `2026-10-17T08:15:00.123456Z`
+impl std::fmt::Display for Utc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second, self.micros
+        )
+    }
+}
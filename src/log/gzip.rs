@@ -0,0 +1,218 @@
+//! gzip 压缩（RFC 1951、RFC 1952）。
+//!
+//! 数据以 LZ77 匹配加固定哈夫曼编码写成单个 deflate 块，不需要构造动态码表，
+//! 对日志这类重复较多的文本仍有不错的压缩率。
+
+/// 滑动窗口的大小，也是匹配的最大距离
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// 每个位置最多比较的候选数
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+/// 长度码 257~285 表示的最小长度与额外位数
+const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LEN_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// 距离码 0~29 表示的最小距离与额外位数
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// CRC-32（IEEE 802.3）查找表
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+/// gzip 尾部使用的 CRC-32
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// 压缩为完整的 gzip 文件内容
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // 无文件名、无修改时间，操作系统未知
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    out.extend(deflate(data));
+    out.extend(crc32(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+/// 按字节中从低到高的顺序写出的位流
+#[derive(Default)]
+struct Bits {
+    out: Vec<u8>,
+    acc: u64,
+    len: u32,
+}
+
+impl Bits {
+    /// 写出 `value` 的低 `len` 位，低位在前
+    fn put(&mut self, value: u32, len: u32) {
+        self.acc |= (value as u64) << self.len;
+        self.len += len;
+        while self.len >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    /// 写出 `len` 位的哈夫曼码，高位在前
+    fn code(&mut self, code: u32, len: u32) {
+        self.put(code.reverse_bits() >> (32 - len), len);
+    }
+
+    /// 固定码表中字面值或长度码 `sym` 的编码
+    fn symbol(&mut self, sym: u32) {
+        match sym {
+            0..=143 => self.code(0x30 + sym, 8),
+            144..=255 => self.code(0x190 + sym - 144, 9),
+            256..=279 => self.code(sym - 256, 7),
+            _ => self.code(0xc0 + sym - 280, 8),
+        }
+    }
+
+    /// 距离为 `dist`、长度为 `len` 的匹配
+    fn matched(&mut self, len: usize, dist: usize) {
+        let idx = LEN_BASE.iter().rposition(|&b| b as usize <= len).unwrap();
+        self.symbol(257 + idx as u32);
+        self.put((len - LEN_BASE[idx] as usize) as u32, LEN_EXTRA[idx] as u32);
+        let idx = DIST_BASE.iter().rposition(|&b| b as usize <= dist).unwrap();
+        self.code(idx as u32, 5);
+        self.put(
+            (dist - DIST_BASE[idx] as usize) as u32,
+            DIST_EXTRA[idx] as u32,
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+/// 按开头 3 字节的哈希值串起的各位置，用于查找匹配
+struct Chains<'a> {
+    data: &'a [u8],
+    /// 各哈希值最近出现的位置
+    head: Vec<usize>,
+    /// 以位置对窗口取模为下标，同一哈希值上一次出现的位置
+    prev: Vec<usize>,
+}
+
+impl<'a> Chains<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            prev: vec![usize::MAX; WINDOW],
+        }
+    }
+
+    fn hash(&self, i: usize) -> usize {
+        let d = self.data;
+        let h = (d[i] as usize) << 10 ^ (d[i + 1] as usize) << 5 ^ d[i + 2] as usize;
+        h & ((1 << HASH_BITS) - 1)
+    }
+
+    /// 与位置 `i` 哈希值相同的最近位置，`i` 之后不足 3 字节时为 `usize::MAX`
+    fn head(&self, i: usize) -> usize {
+        match i + MIN_MATCH <= self.data.len() {
+            true => self.head[self.hash(i)],
+            false => usize::MAX,
+        }
+    }
+
+    fn prev(&self, i: usize) -> usize {
+        self.prev[i % WINDOW]
+    }
+
+    fn insert(&mut self, i: usize) {
+        if i + MIN_MATCH <= self.data.len() {
+            let h = self.hash(i);
+            self.prev[i % WINDOW] = self.head[h];
+            self.head[h] = i;
+        }
+    }
+}
+
+/// 以固定哈夫曼编码的单个 deflate 块
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut bits = Bits::default();
+    // BFINAL = 1，BTYPE = 01
+    bits.put(1, 1);
+    bits.put(1, 2);
+
+    let mut chains = Chains::new(data);
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+        let max = MAX_MATCH.min(data.len() - i);
+        let mut cand = chains.head(i);
+        for _ in 0..MAX_CHAIN {
+            if cand == usize::MAX || i - cand > WINDOW {
+                break;
+            }
+            let len = data[cand..]
+                .iter()
+                .zip(&data[i..i + max])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if len > best_len {
+                (best_len, best_dist) = (len, i - cand);
+                if len == max {
+                    break;
+                }
+            }
+            // 环形缓冲区中的位置可能已被更新的位置覆盖
+            let next = chains.prev(cand);
+            if next >= cand {
+                break;
+            }
+            cand = next;
+        }
+        if best_len >= MIN_MATCH {
+            bits.matched(best_len, best_dist);
+            (i..i + best_len).for_each(|pos| chains.insert(pos));
+            i += best_len;
+        } else {
+            bits.symbol(data[i] as u32);
+            chains.insert(i);
+            i += 1;
+        }
+    }
+    bits.symbol(256);
+    bits.finish()
+}
@@ -0,0 +1,160 @@
+//! gzip 编码的已知结果、UTC 时间换算，以及日志文件的轮转与保留
+
+use super::*;
+
+/// 每个测试独占的临时目录
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ipwrapper-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    let mut names = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn crc32() {
+    assert_eq!(gzip::crc32(b""), 0);
+    assert_eq!(gzip::crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn deflate_fixed_block() {
+    // 与 zlib 以固定哈夫曼编码输出的结果相同
+    let empty = gzip::compress(b"");
+    assert_eq!(&empty[..3], &[0x1f, 0x8b, 8]);
+    assert_eq!(&empty[10..], &[0x03, 0x00, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let one = gzip::compress(b"a");
+    assert_eq!(&one[10..13], &[0x4b, 0x04, 0x00]);
+    assert_eq!(&one[13..], &[0x43, 0xbe, 0xb7, 0xe8, 1, 0, 0, 0]);
+    // 重复的内容以匹配编码，长度远小于原文
+    let text = "{\"version\":1,\"len\":98}\n".repeat(200);
+    assert!(gzip::compress(text.as_bytes()).len() < text.len() / 10);
+}
+
+#[test]
+fn utc() {
+    let at = |secs: u64| Utc::from(UNIX_EPOCH + Duration::from_secs(secs)).to_string();
+    assert_eq!(at(0), "1970-01-01T00:00:00.000000Z");
+    assert_eq!(at(951_782_400), "2000-02-29T00:00:00.000000Z");
+    assert_eq!(at(1_700_000_000), "2023-11-14T22:13:20.000000Z");
+    let ts = UNIX_EPOCH + Duration::from_micros(1_792_224_900_123_456);
+    assert_eq!(Utc::from(ts).compact(), "20261017T081500Z");
+}
+
+#[test]
+fn rotate_and_keep() {
+    let dir = temp_dir("log");
+    let rotation = Rotation {
+        size: Some(1),
+        keep: 2,
+        gzip: true,
+        ..Default::default()
+    };
+    let header = Value::object([("interface", "eth0".into())]);
+    let mut log = LogWriter::create(&dir, rotation, header).unwrap();
+    let ts = UNIX_EPOCH + Duration::from_secs(1_792_224_900);
+    for n in 0..5 {
+        log.write(&Value::object([("n", n.into())]), ts).unwrap();
+    }
+    log.finish().unwrap();
+    // 两个已轮转的压缩文件，加上最后写入的文件
+    let names = files(&dir);
+    assert_eq!(
+        names,
+        [
+            "ipwrapper-20261017T081500Z-2.ndjson.gz",
+            "ipwrapper-20261017T081500Z-3.ndjson.gz",
+            "ipwrapper-20261017T081500Z-4.ndjson",
+        ]
+    );
+    let last = fs::read_to_string(dir.join(&names[2])).unwrap();
+    let lines = last.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "{\"interface\":\"eth0\",\"start\":\"2026-10-17T08:15:00.000000Z\",\"ts\":1792224900}",
+            "{\"n\":4}",
+        ]
+    );
+    let gz = fs::read(dir.join(&names[0])).unwrap();
+    assert_eq!(&gz[..2], &[0x1f, 0x8b]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn prune_by_name() {
+    let dir = temp_dir("log-prune");
+    fs::create_dir_all(&dir).unwrap();
+    // 之前运行留下的文件，修改时间与文件名的先后相反
+    let old = [
+        "ipwrapper-20261017T081459Z-3.ndjson",
+        "ipwrapper-20261017T081500Z.ndjson.gz",
+        "ipwrapper-20261017T081500Z-1.ndjson.gz",
+        "ipwrapper-20261017T081500Z-10.ndjson.gz",
+        "notes.txt",
+    ];
+    for (n, name) in old.iter().enumerate() {
+        let file = File::create(dir.join(name)).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(100 - n as u64))
+            .unwrap();
+    }
+    assert_eq!(parse_name(old[3]), Some(("20261017T081500Z", 10)));
+    assert_eq!(parse_name(old[4]), None);
+
+    let rotation = Rotation {
+        size: Some(1),
+        keep: 3,
+        ..Default::default()
+    };
+    let mut log = LogWriter::create(&dir, rotation, Value::object([])).unwrap();
+    let ts = UNIX_EPOCH + Duration::from_secs(1_792_224_901);
+    for n in 0..2 {
+        log.write(&Value::object([("n", n.into())]), ts).unwrap();
+    }
+    log.finish().unwrap();
+    assert_eq!(
+        files(&dir),
+        [
+            "ipwrapper-20261017T081500Z-1.ndjson.gz",
+            "ipwrapper-20261017T081500Z-10.ndjson.gz",
+            "ipwrapper-20261017T081501Z-1.ndjson",
+            "ipwrapper-20261017T081501Z.ndjson",
+            "notes.txt",
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rotate_by_interval() {
+    let dir = temp_dir("log-interval");
+    let rotation = Rotation {
+        interval: Some(Duration::from_secs(60)),
+        keep: 10,
+        ..Default::default()
+    };
+    let mut log = LogWriter::create(&dir, rotation, Value::object([])).unwrap();
+    let start = UNIX_EPOCH + Duration::from_secs(1_792_224_900);
+    for secs in [0, 30, 59, 60, 90, 150] {
+        let record = Value::object([("t", secs.into())]);
+        log.write(&record, start + Duration::from_secs(secs))
+            .unwrap();
+    }
+    log.finish().unwrap();
+    assert_eq!(
+        files(&dir),
+        [
+            "ipwrapper-20261017T081500Z.ndjson",
+            "ipwrapper-20261017T081600Z.ndjson",
+            "ipwrapper-20261017T081730Z.ndjson",
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,44 +1,72 @@
 mod app;
 mod cli;
+mod dissect;
+mod encoding;
+mod filter;
+mod frag;
 mod head;
+mod i18n;
+mod iface;
+mod log;
+mod output;
+mod pcap;
+mod route;
+mod signal;
 mod socket;
+mod source;
 
-use clap::Parser;
-use cli::{Args, Command};
+use cli::{Args, CaptureArgs, Command};
 
-use crate::app::App;
+use crate::{app::App, i18n::msg};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+/// 捕获时使用的缓冲区大小，即快照长度
+const SNAPLEN: usize = 65536;
+
+/// 按捕获参数打开数据源与捕获文件
+fn capture(args: &CaptureArgs, interface: Option<&str>) -> std::io::Result<App<SNAPLEN>> {
+    let mut app = match &args.read {
+        Some(path) => App::open(path)?,
+        None => App::new(interface)?,
+    };
+    app.open_capture(args)?;
+    Ok(app)
+}
 
-    let mut app = App::<256>::new()?;
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    i18n::init(i18n::detect(std::env::args().skip(1)));
+    let args = Args::parse_localized();
+    let interface = args.interface.as_deref();
 
     match args.command {
-        Command::Send {
-            dhost,
-            destip,
-            protocol,
-            radix,
-            text,
-            file,
-        } => {
+        Command::Interfaces => {
+            for iface in iface::interfaces()? {
+                println!("{iface}");
+            }
+        }
+        Command::Send(args) => {
             let id_count = 0;
-            if let Some(file) = file {
-                app.send_file(id_count, dhost, destip, protocol, &file, radix)?;
-            } else if let Some(text) = text {
-                app.send(id_count, dhost, destip, protocol, &text, radix)?;
-            } else {
-                app.send(id_count, dhost, destip, protocol, "", radix)?;
+            App::<SNAPLEN>::new(interface)?.send(id_count, &args)?;
+        }
+        Command::Ping(args) => App::<SNAPLEN>::new(interface)?.ping(&args)?,
+        Command::Trace(args) => App::<SNAPLEN>::new(interface)?.trace(&args)?,
+        Command::Analyz(args) => capture(&args.capture, interface)?.analyz(&args)?,
+        Command::Filter(args) => {
+            let expr = args.filter().unwrap_or_else(|e| {
+                Args::localized()
+                    .error(clap::error::ErrorKind::InvalidValue, e)
+                    .exit()
+            });
+            if args.dump_bpf {
+                match filter::bpf::compile(expr.as_ref()) {
+                    Some(program) => print!("{program}"),
+                    None => eprintln!("{}", msg!("filter.bpf_too_complex")),
+                }
+                return Ok(());
             }
+            let mut app = capture(&args.capture, interface)?;
+            app.open_log(&args, expr.as_ref())?;
+            app.filter(&args, expr.as_ref())?
         }
-        Command::Analyz => app.analyz()?,
-        Command::Filter {
-            src_mac,
-            dst_mac,
-            shost,
-            dhost,
-            log,
-        } => app.filter(src_mac, dst_mac, shost, dhost, log)?,
     }
     Ok(())
 }
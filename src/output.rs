@@ -0,0 +1,519 @@
+//! 结构化输出：把解码出的首部转换为 JSON 值，按 JSON、NDJSON 或 CSV 输出。
+//!
+//! 每条记录和每个汇总文档都带有 `version` 字段，取值为 [`SCHEMA_VERSION`]。
+//! 只增加字段时不改变版本号；删除、改名或改变字段含义时递增版本号。
+//! 记录中的说明文字一律取自英文目录，不随 `--lang` 变化，见 [`english`]。
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fmt::Display,
+    io::Write,
+    net::{Ipv4Addr, Ipv6Addr},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    head::{
+        checksum::Verdict, EtherHdr, ExtHdr, IPHdr, Icmpv6, Icmpv6Msg, Ipv6Hdr, LlcHdr, TcpHdr,
+        UdpHdr, VlanTag, ICMP,
+    },
+    i18n::{self, Lang},
+};
+
+/// 输出记录的格式版本
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// 供人阅读的文本
+    #[default]
+    Text,
+    /// 单个 JSON 文档
+    Json,
+    /// 每行一个 JSON 对象
+    Ndjson,
+    /// 首行为列名的 CSV
+    Csv,
+}
+
+/// JSON 值。对象保持字段的插入顺序，使输出稳定
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+/// 不存在的字段
+static NULL: Value = Value::Null;
+
+impl Value {
+    /// 由字段名与值构造对象
+    pub fn object<const N: usize>(fields: [(&'static str, Value); N]) -> Self {
+        Value::Object(fields.into())
+    }
+
+    /// 向对象追加一个字段
+    pub fn push(&mut self, key: &'static str, value: impl Into<Value>) {
+        if let Value::Object(fields) = self {
+            fields.push((key, value.into()));
+        }
+    }
+
+    /// 按字段名逐层查找，不存在时为 `null`
+    pub fn at(&self, path: &[&str]) -> &Value {
+        path.iter().fold(self, |value, key| match value {
+            Value::Object(fields) => fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&NULL, |(_, v)| v),
+            _ => &NULL,
+        })
+    }
+
+    /// 自身为 `null` 时取 `other`
+    pub fn or<'a>(&'a self, other: &'a Value) -> &'a Value {
+        match self {
+            Value::Null => other,
+            value => value,
+        }
+    }
+
+    /// CSV 中的一格：字符串按需加引号，数组以 `;` 连接，`null` 为空
+    pub fn csv(&self) -> String {
+        let text = match self {
+            Value::Null => return String::new(),
+            Value::Str(s) => s.clone(),
+            Value::Array(items) => items.iter().map(Value::csv).collect::<Vec<_>>().join(";"),
+            value => value.to_string(),
+        };
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Int(n) => write!(f, "{n}"),
+            Value::Float(x) if x.is_finite() => write!(f, "{x}"),
+            Value::Float(_) => write!(f, "null"),
+            Value::Str(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\r' => write!(f, "\\r")?,
+                        '\t' => write!(f, "\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(fields) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{value}", Value::Str(key.to_string()))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+macro_rules! from_int {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Value {
+            fn from(n: $t) -> Self {
+                Value::Int(n as i64)
+            }
+        })*
+    };
+}
+
+from_int!(u8, u16, u32, u64, usize, i32, i64);
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Float(x)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+/// 以 `:` 分隔的 MAC 地址
+pub fn mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|c| format!("{:02x}", c))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 十六进制字符串
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 自 1970 年以来的秒数，精确到微秒
+pub fn timestamp(ts: SystemTime) -> Value {
+    let ts = ts.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Value::Float(ts.as_micros() as f64 / 1e6)
+}
+
+/// 校验和的值与检验结果
+fn checksum(chksum: u16, verdict: Verdict) -> [(&'static str, Value); 3] {
+    let (status, expected) = match verdict {
+        Verdict::Valid => ("valid", None),
+        Verdict::Invalid { expected } => ("invalid", Some(expected)),
+        Verdict::Offloaded => ("offloaded", None),
+        Verdict::Absent => ("absent", None),
+        Verdict::Truncated => ("truncated", None),
+    };
+    [
+        ("checksum", chksum.into()),
+        ("checksum_status", status.into()),
+        ("checksum_expected", expected.into()),
+    ]
+}
+
+impl From<&VlanTag> for Value {
+    fn from(tag: &VlanTag) -> Self {
+        Value::object([
+            ("tpid", tag.tpid.into()),
+            ("pcp", tag.pcp.into()),
+            ("dei", tag.dei.into()),
+            ("vid", tag.vid.into()),
+        ])
+    }
+}
+
+impl From<&LlcHdr> for Value {
+    fn from(llc: &LlcHdr) -> Self {
+        let snap = llc
+            .snap
+            .map(|snap| Value::object([("oui", hex(&snap.oui).into()), ("pid", snap.pid.into())]));
+        Value::object([
+            ("dsap", llc.dsap.into()),
+            ("ssap", llc.ssap.into()),
+            ("control", llc.control.into()),
+            ("snap", snap.into()),
+        ])
+    }
+}
+
+impl From<&EtherHdr> for Value {
+    fn from(hdr: &EtherHdr) -> Self {
+        Value::object([
+            ("src", mac(&hdr.shost).into()),
+            ("dst", mac(&hdr.dhost).into()),
+            ("type", u16::from(hdr.ethertype()).into()),
+            (
+                "vlans",
+                hdr.vlans.iter().map(Value::from).collect::<Vec<_>>().into(),
+            ),
+            ("llc", hdr.llc.as_ref().map(Value::from).into()),
+        ])
+    }
+}
+
+/// 以英文目录生成结构化输出中的说明文字
+pub fn english<T>(f: impl FnOnce() -> T) -> T {
+    i18n::with_lang(Lang::En, f)
+}
+
+/// IPv4 首部及其校验和的检验结果
+pub fn ipv4(hdr: &IPHdr, verdict: Verdict) -> Value {
+    let options = hdr
+        .options
+        .iter()
+        .map(|opt| {
+            Value::object([
+                ("kind", opt.kind().into()),
+                ("copied", opt.copied().into()),
+                ("text", english(|| opt.to_string()).into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let mut value = Value::object([
+        ("version", 4u8.into()),
+        ("ihl", hdr.ihl.into()),
+        ("tos", hdr.tos.into()),
+        ("len", hdr.totlen.into()),
+        ("ident", hdr.ident.into()),
+        ("df", hdr.flag.df.into()),
+        ("mf", hdr.flag.mf.into()),
+        ("offset", hdr.offset.into()),
+        ("ttl", hdr.ttl.into()),
+        ("protocol", u8::from(hdr.protocol).into()),
+    ]);
+    for (key, v) in checksum(hdr.chksum, verdict) {
+        value.push(key, v);
+    }
+    value.push("src", Ipv4Addr::from(hdr.source).to_string());
+    value.push("dst", Ipv4Addr::from(hdr.destinaiton).to_string());
+    value.push("options", options);
+    value
+}
+
+impl From<&ExtHdr> for Value {
+    fn from(ext: &ExtHdr) -> Self {
+        let mut value = Value::object([("type", ext.code().into())]);
+        match ext {
+            ExtHdr::HopByHop(data) | ExtHdr::DestOpts(data) => value.push("data", hex(data)),
+            ExtHdr::Routing { typ, segleft, data } => {
+                value.push("routing_type", *typ);
+                value.push("segleft", *segleft);
+                value.push("data", hex(data));
+            }
+            ExtHdr::Fragment { offset, mf, ident } => {
+                value.push("offset", *offset);
+                value.push("mf", *mf);
+                value.push("ident", *ident);
+            }
+        }
+        value
+    }
+}
+
+impl From<&Ipv6Hdr> for Value {
+    fn from(hdr: &Ipv6Hdr) -> Self {
+        Value::object([
+            ("version", 6u8.into()),
+            ("tclass", hdr.tclass.into()),
+            ("flow", hdr.flow.into()),
+            ("plen", hdr.plen.into()),
+            ("hlim", hdr.hlim.into()),
+            ("protocol", u8::from(hdr.protocol).into()),
+            ("src", Ipv6Addr::from(hdr.source).to_string().into()),
+            ("dst", Ipv6Addr::from(hdr.destination).to_string().into()),
+            (
+                "exts",
+                hdr.exts.iter().map(Value::from).collect::<Vec<_>>().into(),
+            ),
+        ])
+    }
+}
+
+pub fn tcp(hdr: &TcpHdr, verdict: Verdict) -> Value {
+    let flag = hdr.flag;
+    let flags = Value::object([
+        ("ns", flag.ns.into()),
+        ("cwr", flag.cwr.into()),
+        ("ece", flag.ece.into()),
+        ("urg", flag.urg.into()),
+        ("ack", flag.ack.into()),
+        ("psh", flag.psh.into()),
+        ("rst", flag.rst.into()),
+        ("syn", flag.syn.into()),
+        ("fin", flag.fin.into()),
+    ]);
+    let mut value = Value::object([
+        ("sport", hdr.sport.into()),
+        ("dport", hdr.dport.into()),
+        ("seq", hdr.seqnum.into()),
+        ("ack", hdr.acknum.into()),
+        ("doff", hdr.doff.into()),
+        ("flags", flag.bits().into()),
+        ("flag", flags),
+        ("window", hdr.window.into()),
+    ]);
+    for (key, v) in checksum(hdr.chksum, verdict) {
+        value.push(key, v);
+    }
+    value.push("urgent", hdr.urgent);
+    let options = hdr
+        .options
+        .iter()
+        .map(|opt| english(|| opt.to_string()))
+        .collect::<Vec<_>>();
+    value.push("options", options);
+    value
+}
+
+pub fn udp(hdr: &UdpHdr, verdict: Verdict) -> Value {
+    let mut value = Value::object([
+        ("sport", hdr.sport.into()),
+        ("dport", hdr.dport.into()),
+        ("len", hdr.len.into()),
+    ]);
+    for (key, v) in checksum(hdr.chksum, verdict) {
+        value.push(key, v);
+    }
+    value
+}
+
+pub fn icmp(hdr: &ICMP, verdict: Verdict) -> Value {
+    let mut value = Value::object([
+        ("type", hdr.typ.into()),
+        ("code", hdr.code.into()),
+        ("description", english(|| hdr.typ_dsc()).into()),
+    ]);
+    for (key, v) in checksum(hdr.chksum, verdict) {
+        value.push(key, v);
+    }
+    value.push("ident", hdr.msg.as_ref().map(|ping| ping.ident));
+    value.push("seq", hdr.msg.as_ref().map(|ping| ping.seqnum));
+    value
+}
+
+pub fn icmpv6(hdr: &Icmpv6, verdict: Verdict) -> Value {
+    let mut value = Value::object([
+        ("type", hdr.typ.into()),
+        ("code", hdr.code.into()),
+        ("description", english(|| hdr.typ_dsc()).into()),
+    ]);
+    for (key, v) in checksum(hdr.chksum, verdict) {
+        value.push(key, v);
+    }
+    let ping = match &hdr.msg {
+        Some(Icmpv6Msg::Echo(ping)) => Some(ping),
+        _ => None,
+    };
+    value.push("ident", ping.map(|ping| ping.ident));
+    value.push("seq", ping.map(|ping| ping.seqnum));
+    let target = match &hdr.msg {
+        Some(
+            Icmpv6Msg::NeighborSolicit { target, .. } | Icmpv6Msg::NeighborAdvert { target, .. },
+        ) => Some(Ipv6Addr::from(*target).to_string()),
+        _ => None,
+    };
+    value.push("target", target);
+    let options = hdr
+        .msg
+        .as_ref()
+        .map_or(&[][..], |msg| msg.options())
+        .iter()
+        .map(|opt| english(|| opt.to_string()))
+        .collect::<Vec<_>>();
+    value.push("options", options);
+    value
+}
+
+/// CSV 的一列：列名与取值方法
+pub type Column = (&'static str, fn(&Value) -> &Value);
+
+/// CSV 的表头行
+pub fn csv_header(columns: &[Column]) -> String {
+    columns
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 记录对应的 CSV 行
+pub fn csv_row(columns: &[Column], record: &Value) -> String {
+    columns
+        .iter()
+        .map(|(_, get)| get(record).csv())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 按输出格式逐条写出记录：JSON 为一个带汇总的文档，NDJSON 每行一条，CSV 每条一行
+pub struct RecordWriter<W: Write> {
+    out: W,
+    format: OutputFormat,
+    columns: &'static [Column],
+    count: usize,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// 写出 JSON 文档的开头或 CSV 的表头
+    pub fn new(
+        mut out: W,
+        format: OutputFormat,
+        columns: &'static [Column],
+    ) -> std::io::Result<Self> {
+        match format {
+            OutputFormat::Json => write!(out, "{{\"version\":{SCHEMA_VERSION},\"packets\":[")?,
+            OutputFormat::Csv => writeln!(out, "{}", csv_header(columns))?,
+            OutputFormat::Text | OutputFormat::Ndjson => {}
+        }
+        Ok(Self {
+            out,
+            format,
+            columns,
+            count: 0,
+        })
+    }
+
+    pub fn write(&mut self, record: &Value) -> std::io::Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                let sep = if self.count == 0 { "" } else { "," };
+                write!(self.out, "{sep}\n{record}")?
+            }
+            OutputFormat::Ndjson => writeln!(self.out, "{record}")?,
+            OutputFormat::Csv => writeln!(self.out, "{}", csv_row(self.columns, record))?,
+            OutputFormat::Text => {}
+        }
+        self.count += 1;
+        self.out.flush()
+    }
+
+    /// 结束输出。JSON 文档以 `summary` 结尾，其他格式不输出汇总
+    pub fn finish(mut self, summary: Value) -> std::io::Result<()> {
+        if self.format == OutputFormat::Json {
+            writeln!(self.out, "\n],\"summary\":{summary}}}")?;
+        }
+        self.out.flush()
+    }
+}
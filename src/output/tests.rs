@@ -0,0 +1,65 @@
+//! 各输出格式的文档结构、CSV 的列与转义
+
+use super::*;
+
+const COLUMNS: &[Column] = &[("n", |r| r.at(&["n"])), ("name", |r| r.at(&["name"]))];
+
+/// 按 `format` 写出两条记录与汇总
+fn write_all(format: OutputFormat) -> String {
+    let mut out = vec![];
+    let mut writer = RecordWriter::new(&mut out, format, COLUMNS).unwrap();
+    writer
+        .write(&Value::object([("n", 1.into()), ("name", "a,b".into())]))
+        .unwrap();
+    writer.write(&Value::object([("n", 2.into())])).unwrap();
+    writer
+        .finish(Value::object([("frames", 2.into())]))
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn record_writer_formats() {
+    assert_eq!(
+        write_all(OutputFormat::Json),
+        "{\"version\":1,\"packets\":[\n{\"n\":1,\"name\":\"a,b\"},\n{\"n\":2}\n],\"summary\":{\"frames\":2}}\n"
+    );
+    // NDJSON 与 CSV 不输出汇总
+    assert_eq!(
+        write_all(OutputFormat::Ndjson),
+        "{\"n\":1,\"name\":\"a,b\"}\n{\"n\":2}\n"
+    );
+    // 缺少的字段为空格
+    assert_eq!(write_all(OutputFormat::Csv), "n,name\n1,\"a,b\"\n2,\n");
+    assert_eq!(write_all(OutputFormat::Text), "");
+}
+
+#[test]
+fn json_without_records() {
+    let mut out = vec![];
+    let writer = RecordWriter::new(&mut out, OutputFormat::Json, COLUMNS).unwrap();
+    writer.finish(Value::Null).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "{\"version\":1,\"packets\":[\n],\"summary\":null}\n"
+    );
+}
+
+#[test]
+fn value_lookup() {
+    let mut value = Value::object([("a", Value::object([("b", true.into())]))]);
+    value.push("c", vec![1u8, 2]);
+    value.push("d", None::<u8>);
+    assert_eq!(value.at(&["a", "b"]), &Value::Bool(true));
+    assert_eq!(value.at(&["a", "b", "c"]), &Value::Null);
+    assert_eq!(
+        value.at(&["d"]).or(value.at(&["a", "b"])),
+        &Value::Bool(true)
+    );
+    assert_eq!(value.at(&["c"]).csv(), "1;2");
+    assert_eq!(value.to_string(), r#"{"a":{"b":true},"c":[1,2],"d":null}"#);
+    // 非对象上追加字段没有效果
+    let mut text = Value::from("x");
+    text.push("y", 1u8);
+    assert_eq!(text, Value::from("x"));
+}
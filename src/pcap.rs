@@ -0,0 +1,432 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    i18n::msg,
+    socket::if_index,
+    source::{Frame, PacketSource},
+};
+
+/// 以太网链路类型（LINKTYPE_ETHERNET）
+pub const LINKTYPE_ETHERNET: u16 = 1;
+
+/// 读取时单个记录或块的长度上限，超出时视为文件损坏，以免按其分配内存
+const MAX_RECORD_LEN: usize = 256 * 1024;
+
+/// 捕获文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PcapFormat {
+    /// 经典 libpcap 格式，微秒时间戳
+    Pcap,
+    /// pcapng 格式，每个接口对应一个接口描述块
+    #[default]
+    Pcapng,
+}
+
+/// 将捕获的以太网帧写入 pcap 或 pcapng 文件
+#[derive(Debug)]
+pub struct PcapWriter<W: Write> {
+    out: W,
+    format: PcapFormat,
+}
+
+impl PcapWriter<BufWriter<File>> {
+    pub fn create(
+        path: &Path,
+        format: PcapFormat,
+        snaplen: u32,
+        ifaces: &[(String, [u8; 6])],
+    ) -> std::io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), format, snaplen, ifaces)
+    }
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// 写入文件头。pcapng 格式下 `ifaces` 的顺序即为接口编号。
+    pub fn new(
+        mut out: W,
+        format: PcapFormat,
+        snaplen: u32,
+        ifaces: &[(String, [u8; 6])],
+    ) -> std::io::Result<Self> {
+        match format {
+            PcapFormat::Pcap => {
+                let mut hdr = vec![];
+                hdr.extend(0xa1b2_c3d4u32.to_le_bytes());
+                hdr.extend(2u16.to_le_bytes());
+                hdr.extend(4u16.to_le_bytes());
+                hdr.extend(0i32.to_le_bytes());
+                hdr.extend(0u32.to_le_bytes());
+                hdr.extend(snaplen.to_le_bytes());
+                hdr.extend((LINKTYPE_ETHERNET as u32).to_le_bytes());
+                out.write_all(&hdr)?;
+            }
+            PcapFormat::Pcapng => {
+                // 节头块：字节序标识、版本 1.0、节长度未知
+                let mut body = vec![];
+                body.extend(0x1a2b_3c4du32.to_le_bytes());
+                body.extend(1u16.to_le_bytes());
+                body.extend(0u16.to_le_bytes());
+                body.extend((-1i64).to_le_bytes());
+                body.extend(option(
+                    4,
+                    concat!("ipwrapper ", env!("CARGO_PKG_VERSION")).as_bytes(),
+                ));
+                body.extend(option(0, &[]));
+                out.write_all(&block(0x0a0d_0d0a, &body))?;
+
+                for (name, mac) in ifaces {
+                    let mut body = vec![];
+                    body.extend(LINKTYPE_ETHERNET.to_le_bytes());
+                    body.extend(0u16.to_le_bytes());
+                    body.extend(snaplen.to_le_bytes());
+                    body.extend(option(2, name.as_bytes()));
+                    body.extend(option(6, mac));
+                    body.extend(option(0, &[]));
+                    out.write_all(&block(1, &body))?;
+                }
+            }
+        }
+        Ok(Self { out, format })
+    }
+
+    /// 写入一帧。`iface` 为接口在文件头中的编号，`origlen` 为帧在线路上的原始长度。
+    pub fn write_packet(
+        &mut self,
+        iface: u32,
+        ts: SystemTime,
+        data: &[u8],
+        origlen: u32,
+    ) -> std::io::Result<()> {
+        let ts = ts.duration_since(UNIX_EPOCH).unwrap_or_default();
+        match self.format {
+            PcapFormat::Pcap => {
+                let mut rec = vec![];
+                rec.extend((ts.as_secs() as u32).to_le_bytes());
+                rec.extend(ts.subsec_micros().to_le_bytes());
+                rec.extend((data.len() as u32).to_le_bytes());
+                rec.extend(origlen.to_le_bytes());
+                rec.extend_from_slice(data);
+                self.out.write_all(&rec)
+            }
+            PcapFormat::Pcapng => {
+                let micros = ts.as_micros() as u64;
+                let mut body = vec![];
+                body.extend(iface.to_le_bytes());
+                body.extend(((micros >> 32) as u32).to_le_bytes());
+                body.extend((micros as u32).to_le_bytes());
+                body.extend((data.len() as u32).to_le_bytes());
+                body.extend(origlen.to_le_bytes());
+                body.extend_from_slice(data);
+                body.resize(body.len().next_multiple_of(4), 0);
+                self.out.write_all(&block(6, &body))
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// pcapng 接口描述块中读取的接口信息
+#[derive(Debug, Clone)]
+struct Iface {
+    linktype: u16,
+    /// 每秒的时间戳单位数
+    tsresol: u64,
+    /// 本机上同名接口的索引
+    ifindex: Option<i32>,
+}
+
+/// 从 pcap 或 pcapng 文件中读取以太网帧
+#[derive(Debug)]
+pub struct PcapReader<R: Read> {
+    input: R,
+    format: PcapFormat,
+    big_endian: bool,
+    /// pcap 格式下每秒的时间戳单位数
+    tsresol: u64,
+    /// pcapng 格式下当前节的接口
+    ifaces: Vec<Iface>,
+}
+
+impl PcapReader<BufReader<File>> {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    /// 读取文件头并识别格式与字节序
+    pub fn new(mut input: R) -> std::io::Result<Self> {
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        let mut reader = Self {
+            input,
+            format: PcapFormat::Pcap,
+            big_endian: false,
+            tsresol: 1_000_000,
+            ifaces: vec![],
+        };
+        match magic {
+            [0x0a, 0x0d, 0x0d, 0x0a] => {
+                reader.format = PcapFormat::Pcapng;
+                reader.read_shb()?;
+            }
+            _ => {
+                let (big_endian, tsresol) = match u32::from_le_bytes(magic) {
+                    0xa1b2_c3d4 => (false, 1_000_000),
+                    0xa1b2_3c4d => (false, 1_000_000_000),
+                    0xd4c3_b2a1 => (true, 1_000_000),
+                    0x4d3c_b2a1 => (true, 1_000_000_000),
+                    _ => return Err(invalid(msg!("pcap.not_pcap"))),
+                };
+                reader.big_endian = big_endian;
+                reader.tsresol = tsresol;
+                let mut hdr = [0u8; 20];
+                reader.input.read_exact(&mut hdr)?;
+                let linktype = reader.u32(&hdr[16..20]) & 0xffff;
+                if linktype != LINKTYPE_ETHERNET as u32 {
+                    return Err(invalid(&msg!("pcap.linktype", linktype = linktype)));
+                }
+            }
+        }
+        Ok(reader)
+    }
+
+    fn u16(&self, b: &[u8]) -> u16 {
+        let b = [b[0], b[1]];
+        if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        }
+    }
+
+    fn u32(&self, b: &[u8]) -> u32 {
+        let b = [b[0], b[1], b[2], b[3]];
+        if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    }
+
+    /// 读满 `buf`。在任何字节都未读到时遇到文件结尾返回 `false`
+    fn fill(&mut self, buf: &mut [u8]) -> std::io::Result<bool> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.input.read(&mut buf[read..]) {
+                Ok(0) if read == 0 => return Ok(false),
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+
+    /// 读取节头块的剩余部分（块类型已读取）
+    fn read_shb(&mut self) -> std::io::Result<()> {
+        let mut head = [0u8; 8];
+        self.input.read_exact(&mut head)?;
+        self.big_endian = match &head[4..8] {
+            [0x1a, 0x2b, 0x3c, 0x4d] => true,
+            [0x4d, 0x3c, 0x2b, 0x1a] => false,
+            _ => return Err(invalid(msg!("pcap.bad_bom"))),
+        };
+        let len = self.u32(&head[0..4]) as usize;
+        if len < 28 {
+            return Err(invalid(msg!("pcap.bad_shb")));
+        }
+        check_len(len)?;
+        let mut rest = vec![0u8; len - 12];
+        self.input.read_exact(&mut rest)?;
+        self.ifaces.clear();
+        Ok(())
+    }
+
+    /// 解析接口描述块
+    fn read_idb(&mut self, body: &[u8]) -> std::io::Result<()> {
+        if body.len() < 8 {
+            return Err(invalid(msg!("pcap.bad_idb")));
+        }
+        let mut iface = Iface {
+            linktype: self.u16(&body[0..2]),
+            tsresol: 1_000_000,
+            ifindex: None,
+        };
+        let mut opts = &body[8..];
+        while opts.len() >= 4 {
+            let code = self.u16(&opts[0..2]);
+            let len = self.u16(&opts[2..4]) as usize;
+            let Some(value) = opts.get(4..4 + len) else {
+                break;
+            };
+            match code {
+                0 => break,
+                2 => {
+                    let name = String::from_utf8_lossy(value);
+                    iface.ifindex = if_index(name.trim_end_matches('\0'))
+                        .ok()
+                        .map(|idx| idx as i32);
+                }
+                9 if len == 1 => {
+                    let exp = (value[0] & 0x7f) as u32;
+                    iface.tsresol = if value[0] & 0x80 > 0 {
+                        2u64.saturating_pow(exp)
+                    } else {
+                        10u64.saturating_pow(exp)
+                    };
+                }
+                _ => {}
+            }
+            opts = &opts[(4 + len).next_multiple_of(4).min(opts.len())..];
+        }
+        self.ifaces.push(iface);
+        Ok(())
+    }
+
+    fn next_pcap(&mut self) -> std::io::Result<Option<Frame>> {
+        let mut rec = [0u8; 16];
+        if !self.fill(&mut rec)? {
+            return Ok(None);
+        }
+        let secs = self.u32(&rec[0..4]) as u64;
+        let frac = self.u32(&rec[4..8]) as u64;
+        let caplen = self.u32(&rec[8..12]) as usize;
+        let origlen = self.u32(&rec[12..16]);
+        check_len(caplen)?;
+        let mut data = vec![0u8; caplen];
+        self.input.read_exact(&mut data)?;
+        let ts = UNIX_EPOCH
+            + Duration::from_secs(secs)
+            + Duration::from_nanos(frac * 1_000_000_000 / self.tsresol);
+        Ok(Some(Frame {
+            data,
+            ts,
+            origlen,
+            ifindex: None,
+            offloaded: false,
+        }))
+    }
+
+    fn next_pcapng(&mut self) -> std::io::Result<Option<Frame>> {
+        loop {
+            let mut typ = [0u8; 4];
+            if !self.fill(&mut typ)? {
+                return Ok(None);
+            }
+            if typ == [0x0a, 0x0d, 0x0d, 0x0a] {
+                self.read_shb()?;
+                continue;
+            }
+            let typ = self.u32(&typ);
+            let mut len = [0u8; 4];
+            self.input.read_exact(&mut len)?;
+            let len = self.u32(&len) as usize;
+            if len < 12 || !len.is_multiple_of(4) {
+                return Err(invalid(msg!("pcap.bad_block")));
+            }
+            check_len(len)?;
+            let mut body = vec![0u8; len - 8];
+            self.input.read_exact(&mut body)?;
+            body.truncate(len - 12);
+
+            let (iface, ts, caplen, origlen, data) = match typ {
+                1 => {
+                    self.read_idb(&body)?;
+                    continue;
+                }
+                // 增强分组块
+                6 if body.len() >= 20 => {
+                    let iface = self.u32(&body[0..4]) as usize;
+                    let ts = (self.u32(&body[4..8]) as u64) << 32 | self.u32(&body[8..12]) as u64;
+                    let caplen = self.u32(&body[12..16]) as usize;
+                    let origlen = self.u32(&body[16..20]);
+                    (iface, ts, caplen, origlen, &body[20..])
+                }
+                // 简单分组块，没有时间戳，属于第一个接口
+                3 if body.len() >= 4 => {
+                    let origlen = self.u32(&body[0..4]);
+                    let caplen = (origlen as usize).min(body.len() - 4);
+                    (0, 0, caplen, origlen, &body[4..])
+                }
+                _ => continue,
+            };
+            let Some(info) = self.ifaces.get(iface) else {
+                return Err(invalid(&msg!("pcap.bad_iface", iface = iface)));
+            };
+            if info.linktype != LINKTYPE_ETHERNET {
+                continue;
+            }
+            let Some(data) = data.get(..caplen) else {
+                return Err(invalid(msg!("pcap.bad_packet")));
+            };
+            let ts = UNIX_EPOCH
+                + Duration::from_secs(ts / info.tsresol)
+                + Duration::from_nanos(
+                    ((ts % info.tsresol) as u128 * 1_000_000_000 / info.tsresol as u128) as u64,
+                );
+            return Ok(Some(Frame {
+                data: data.to_vec(),
+                ts,
+                origlen,
+                ifindex: info.ifindex,
+                offloaded: false,
+            }));
+        }
+    }
+}
+
+impl<R: Read> PacketSource for PcapReader<R> {
+    fn next_frame(&mut self) -> std::io::Result<Option<Frame>> {
+        match self.format {
+            PcapFormat::Pcap => self.next_pcap(),
+            PcapFormat::Pcapng => self.next_pcapng(),
+        }
+    }
+}
+
+fn invalid(msg: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, msg.to_string())
+}
+
+/// 记录或块的长度不超过 [`MAX_RECORD_LEN`]
+fn check_len(len: usize) -> std::io::Result<()> {
+    if len > MAX_RECORD_LEN {
+        let text = msg!("pcap.too_large", len = len, max = MAX_RECORD_LEN);
+        return Err(invalid(&text));
+    }
+    Ok(())
+}
+
+/// 组装 pcapng 块：类型、总长度、内容、总长度
+fn block(typ: u32, body: &[u8]) -> Vec<u8> {
+    let len = (12 + body.len()) as u32;
+    let mut bytes = vec![];
+    bytes.extend(typ.to_le_bytes());
+    bytes.extend(len.to_le_bytes());
+    bytes.extend_from_slice(body);
+    bytes.extend(len.to_le_bytes());
+    bytes
+}
+
+/// 组装 pcapng 选项，值按 4 字节对齐填充
+fn option(code: u16, value: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend(code.to_le_bytes());
+    bytes.extend((value.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(value);
+    bytes.resize(bytes.len().next_multiple_of(4), 0);
+    bytes
+}
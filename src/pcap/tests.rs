@@ -0,0 +1,272 @@
+//! 写入文件的布局、写入后再读出的往返测试、损坏的长度字段，以及用 `tests/data` 中的捕获文件检查各首部的解析
+
+use std::io::Cursor;
+
+use super::*;
+use crate::head::{
+    EtherHdr, EtherKind, Header, IPHdr, Icmpv6, Icmpv6Msg, Ipv6Hdr, NdOption, Protocol, UdpHdr,
+    ICMP, TPID_CTAG, TPID_STAG,
+};
+
+/// 读出 `tests/data` 中捕获文件的全部帧
+fn fixture(name: &str) -> Vec<Frame> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name);
+    let mut reader = PcapReader::open(&path).unwrap();
+    let mut frames = vec![];
+    while let Some(frame) = reader.next_frame().unwrap() {
+        frames.push(frame);
+    }
+    frames
+}
+
+fn round_trip(format: PcapFormat) {
+    let ifaces = [
+        ("ipw-test0".to_string(), [2; 6]),
+        ("ipw-test1".to_string(), [4; 6]),
+    ];
+    // 长度不是 4 的整数倍的帧在 pcapng 中需要填充
+    let frames = (0..20u32)
+        .map(|n| {
+            let ts =
+                UNIX_EPOCH + Duration::from_micros(1_792_224_900_000_000 + n as u64 * 1_234_567);
+            let data = (0..n * 7).map(|b| b as u8).collect::<Vec<_>>();
+            (n % 2, ts, data, n * 7 + n % 3)
+        })
+        .collect::<Vec<_>>();
+    let mut file = vec![];
+    let mut writer = PcapWriter::new(&mut file, format, 65535, &ifaces).unwrap();
+    for (iface, ts, data, origlen) in &frames {
+        writer.write_packet(*iface, *ts, data, *origlen).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let mut reader = PcapReader::new(Cursor::new(file)).unwrap();
+    assert_eq!(reader.format, format);
+    for (_, ts, data, origlen) in &frames {
+        let frame = reader.next_frame().unwrap().unwrap();
+        assert_eq!(&frame.data, data);
+        assert_eq!(frame.ts, *ts);
+        assert_eq!(frame.origlen, *origlen);
+    }
+    assert!(reader.next_frame().unwrap().is_none());
+}
+
+/// 1 秒后再过 5 微秒
+fn ts() -> SystemTime {
+    UNIX_EPOCH + Duration::from_micros(1_000_005)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+#[test]
+fn pcap_layout() {
+    let mut file = vec![];
+    let mut writer = PcapWriter::new(&mut file, PcapFormat::Pcap, 65535, &[]).unwrap();
+    writer.write_packet(0, ts(), &[1, 2, 3], 60).unwrap();
+    writer.flush().unwrap();
+
+    // 24 字节文件头：魔数、版本 2.4、快照长度与链路类型
+    assert_eq!(u32_at(&file, 0), 0xa1b2_c3d4);
+    assert_eq!(file[4..8], [2, 0, 4, 0]);
+    assert_eq!(u32_at(&file, 16), 65535);
+    assert_eq!(u32_at(&file, 20), LINKTYPE_ETHERNET as u32);
+    // 记录头：秒、微秒、保存的长度、原始长度
+    let rec = &file[24..];
+    assert_eq!([0, 4, 8, 12].map(|at| u32_at(rec, at)), [1, 5, 3, 60]);
+    assert_eq!(&rec[16..], [1, 2, 3]);
+}
+
+#[test]
+fn pcapng_layout() {
+    let ifaces = [
+        ("ipw-test0".to_string(), [2; 6]),
+        ("ipw-test1".to_string(), [4; 6]),
+    ];
+    let mut file = vec![];
+    let mut writer = PcapWriter::new(&mut file, PcapFormat::Pcapng, 65535, &ifaces).unwrap();
+    writer.write_packet(1, ts(), &[1, 2, 3, 4, 5], 5).unwrap();
+    writer.flush().unwrap();
+
+    // 依次是节头块、每个接口一个接口描述块和增强分组块，块的首尾都记录块长度
+    let mut blocks = vec![];
+    let mut rest = &file[..];
+    while !rest.is_empty() {
+        let len = u32_at(rest, 4) as usize;
+        assert_eq!(len % 4, 0);
+        assert_eq!(u32_at(rest, len - 4) as usize, len);
+        blocks.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    let types = blocks.iter().map(|b| u32_at(b, 0)).collect::<Vec<_>>();
+    assert_eq!(types, [0x0a0d_0d0a, 1, 1, 6]);
+    assert_eq!(u32_at(blocks[0], 8), 0x1a2b_3c4d);
+    for (idb, (name, mac)) in blocks[1..3].iter().zip(&ifaces) {
+        assert_eq!(idb[8..10], LINKTYPE_ETHERNET.to_le_bytes());
+        assert_eq!(u32_at(idb, 12), 65535);
+        // if_name 选项的值按 4 字节对齐填充，其后为 if_MACaddr 选项
+        assert_eq!(idb[16..18], 2u16.to_le_bytes());
+        assert_eq!(&idb[20..20 + name.len()], name.as_bytes());
+        let mac_opt = 20 + name.len().next_multiple_of(4);
+        assert_eq!(idb[mac_opt..mac_opt + 2], 6u16.to_le_bytes());
+        assert_eq!(&idb[mac_opt + 4..mac_opt + 10], mac);
+    }
+    // 接口编号、微秒时间戳的高低 32 位、保存的长度、原始长度，数据填充到 4 字节
+    let epb = blocks[3];
+    assert_eq!(epb.len(), 12 + 20 + 8);
+    assert_eq!(
+        [8, 12, 16, 20, 24].map(|at| u32_at(epb, at)),
+        [1, 0, 1_000_005, 5, 5]
+    );
+    assert_eq!(epb[28..36], [1, 2, 3, 4, 5, 0, 0, 0]);
+}
+
+#[test]
+fn pcap_round_trip() {
+    round_trip(PcapFormat::Pcap);
+}
+
+#[test]
+fn pcapng_round_trip() {
+    round_trip(PcapFormat::Pcapng);
+}
+
+#[test]
+fn pcap_big_endian_nanos() {
+    // 大端字节序、纳秒时间戳的文件头
+    let mut file = vec![];
+    file.extend(0xa1b2_3c4du32.to_be_bytes());
+    file.extend([0, 2, 0, 4]);
+    file.extend([0; 8]);
+    file.extend(65535u32.to_be_bytes());
+    file.extend(1u32.to_be_bytes());
+    for n in [1u32, 500, 4, 60] {
+        file.extend(n.to_be_bytes());
+    }
+    file.extend([1, 2, 3, 4]);
+    let mut reader = PcapReader::new(Cursor::new(file)).unwrap();
+    let frame = reader.next_frame().unwrap().unwrap();
+    assert_eq!(frame.ts, UNIX_EPOCH + Duration::new(1, 500));
+    assert_eq!((frame.data, frame.origlen), (vec![1, 2, 3, 4], 60));
+    assert!(reader.next_frame().unwrap().is_none());
+}
+
+/// 长度字段过大的记录应报告文件损坏，而不是按其分配内存
+#[test]
+fn oversized_records() {
+    let mut file = vec![];
+    PcapWriter::new(&mut file, PcapFormat::Pcap, 65535, &[]).unwrap();
+    file.extend([0; 8]);
+    file.extend(u32::MAX.to_le_bytes());
+    file.extend(u32::MAX.to_le_bytes());
+    let mut reader = PcapReader::new(Cursor::new(file)).unwrap();
+    let err = reader.next_frame().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let ifaces = [("eth0".to_string(), [2; 6])];
+    let mut file = vec![];
+    PcapWriter::new(&mut file, PcapFormat::Pcapng, 65535, &ifaces).unwrap();
+    file.extend(6u32.to_le_bytes());
+    file.extend(0xffff_fff0u32.to_le_bytes());
+    let mut reader = PcapReader::new(Cursor::new(file)).unwrap();
+    let err = reader.next_frame().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    // 节头块同样受限
+    let mut file = vec![0x0a, 0x0d, 0x0d, 0x0a];
+    file.extend(0x1000_0000u32.to_le_bytes());
+    file.extend(0x1a2b_3c4du32.to_le_bytes());
+    let err = PcapReader::new(Cursor::new(file)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn decode_icmp_echo() {
+    let frames = fixture("icmp-echo.pcap");
+    assert_eq!(frames.len(), 2);
+    for (frame, (typ, src, dst)) in frames.iter().zip([(8, 2, 1), (0, 1, 2)]) {
+        let ((ethdr, iphdr), rest) = <(EtherHdr, IPHdr)>::from_bytes(&frame.data).unwrap();
+        assert_eq!(ethdr.etype, EtherKind::IP);
+        assert_eq!(iphdr.protocol, Protocol::ICMP);
+        assert_eq!(
+            (iphdr.source, iphdr.destinaiton),
+            ([192, 0, 2, src], [192, 0, 2, dst])
+        );
+        assert_eq!(iphdr.totlen as usize, frame.data.len() - 14);
+        assert!(iphdr.verify());
+        let (icmp, data) = ICMP::from_bytes(rest).unwrap();
+        assert_eq!((icmp.typ, icmp.code), (typ, 0));
+        let ping = icmp.msg.clone().unwrap();
+        assert_eq!((ping.ident, ping.seqnum), (16509, 1));
+        assert_eq!(data, (0..56).collect::<Vec<u8>>());
+        assert!(icmp.verify(data));
+    }
+}
+
+#[test]
+fn decode_ipv4_fragments() {
+    let frames = fixture("ipv4-frag.pcap");
+    let fields = frames
+        .iter()
+        .map(|frame| {
+            let ((_, iphdr), rest) = <(EtherHdr, IPHdr)>::from_bytes(&frame.data).unwrap();
+            assert!(iphdr.is_fragment());
+            assert_eq!(rest.len(), 16);
+            (iphdr.ident, iphdr.flag.mf, iphdr.offset)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(fields, [(0, true, 0), (0, true, 2), (0, false, 4)]);
+}
+
+#[test]
+fn decode_ipv6_nd() {
+    let frames = fixture("ipv6-nd.pcap");
+    assert_eq!(frames.len(), 4);
+    let ((ethdr, ipv6), rest) = <(EtherHdr, Ipv6Hdr)>::from_bytes(&frames[0].data).unwrap();
+    assert_eq!(ethdr.etype, EtherKind::IPv6);
+    assert_eq!(
+        (ipv6.hlim, ipv6.plen, ipv6.protocol),
+        (255, 32, Protocol::ICMPv6)
+    );
+    let (icmpv6, data) = Icmpv6::from_bytes(rest).unwrap();
+    assert!(icmpv6.verify(&ipv6, data));
+    let Some(Icmpv6Msg::NeighborSolicit {
+        target, options, ..
+    }) = &icmpv6.msg
+    else {
+        panic!("{icmpv6:?}");
+    };
+    assert_eq!(target[..2], [0xfd, 0]);
+    assert_eq!(target[15], 1);
+    assert_eq!(options, &[NdOption::SourceLinkAddr(ethdr.shost)]);
+
+    let ((_, ipv6), rest) = <(EtherHdr, Ipv6Hdr)>::from_bytes(&frames[3].data).unwrap();
+    assert_eq!(ipv6.protocol, Protocol::UDP);
+    let (udp, data) = UdpHdr::from_bytes(rest).unwrap();
+    assert_eq!((udp.len, data), (10, &b"hi"[..]));
+    assert!(udp.verify(&ipv6, data));
+}
+
+#[test]
+fn decode_qinq() {
+    let frames = fixture("qinq.pcapng");
+    assert_eq!(frames.len(), 1);
+    let ((ethdr, iphdr), rest) = <(EtherHdr, IPHdr)>::from_bytes(&frames[0].data).unwrap();
+    let tags = ethdr
+        .vlans
+        .iter()
+        .map(|tag| (tag.tpid, tag.pcp, tag.vid))
+        .collect::<Vec<_>>();
+    assert_eq!(tags, [(TPID_STAG, 5, 100), (TPID_CTAG, 0, 200)]);
+    assert_eq!(ethdr.size(), 22);
+    assert_eq!(
+        (iphdr.source, iphdr.destinaiton),
+        ([10, 100, 0, 2], [10, 100, 0, 1])
+    );
+    let (udp, data) = UdpHdr::from_bytes(rest).unwrap();
+    assert_eq!((udp.dport, data), (9, &b"hi"[..]));
+    assert!(udp.verify(&iphdr, data));
+}
@@ -0,0 +1,109 @@
+use std::{fs, io::ErrorKind, net::Ipv6Addr};
+
+use crate::i18n::msg;
+
+/// 到达某一目的地址的路由，`A` 为 IPv4 或 IPv6 地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route<A = [u8; 4]> {
+    /// 出接口名
+    pub iface: String,
+    /// 下一跳网关，目的地址在链路上时为 `None`
+    pub gateway: Option<A>,
+}
+
+impl<A: Copy> Route<A> {
+    /// 链路层应当发往的下一跳地址
+    pub fn next_hop(&self, dstip: A) -> A {
+        self.gateway.unwrap_or(dstip)
+    }
+}
+
+/// 按最长前缀匹配在 `/proc/net/route` 中查找到 `dstip` 的路由。
+/// 环回地址总是经由 `lo`。
+pub fn lookup(dstip: [u8; 4]) -> std::io::Result<Route> {
+    if dstip[0] == 127 {
+        return Ok(Route {
+            iface: "lo".to_string(),
+            gateway: None,
+        });
+    }
+    let table = fs::read_to_string("/proc/net/route")?;
+    let dst = u32::from_be_bytes(dstip);
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols = line.split_whitespace().collect::<Vec<_>>();
+            let field = |idx: usize| {
+                cols.get(idx)
+                    .and_then(|col| u32::from_str_radix(col, 16).ok())
+            };
+            // 地址字段按主机字节序（小端）以十六进制书写
+            let (dest, gateway, flags, mask) = (
+                field(1)?.swap_bytes(),
+                field(2)?.to_le_bytes(),
+                field(3)?,
+                field(7)?.swap_bytes(),
+            );
+            // RTF_UP
+            if flags & 0x1 == 0 || dst & mask != dest {
+                return None;
+            }
+            // RTF_GATEWAY
+            let gateway = (flags & 0x2 != 0).then_some(gateway);
+            Some((mask, cols[0].to_string(), gateway))
+        })
+        .max_by_key(|(mask, _, _)| mask.count_ones())
+        .map(|(_, iface, gateway)| Route { iface, gateway })
+        .ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::NetworkUnreachable,
+                msg!("route.none", dst = dstip.map(|n| n.to_string()).join(".")),
+            )
+        })
+}
+
+/// 按最长前缀匹配在 `/proc/net/ipv6_route` 中查找到 `dstip` 的路由。
+/// 环回地址总是经由 `lo`。
+pub fn lookup6(dstip: [u8; 16]) -> std::io::Result<Route<[u8; 16]>> {
+    if dstip == Ipv6Addr::LOCALHOST.octets() {
+        return Ok(Route {
+            iface: "lo".to_string(),
+            gateway: None,
+        });
+    }
+    let table = fs::read_to_string("/proc/net/ipv6_route")?;
+    let dst = u128::from_be_bytes(dstip);
+    table
+        .lines()
+        .filter_map(|line| {
+            let cols = line.split_whitespace().collect::<Vec<_>>();
+            // 地址字段按网络字节序以十六进制书写
+            let addr = |idx: usize| {
+                cols.get(idx)
+                    .and_then(|col| u128::from_str_radix(col, 16).ok())
+            };
+            let (dest, plen, gateway) = (
+                addr(0)?,
+                u32::from_str_radix(cols.get(1)?, 16).ok()?,
+                addr(4)?,
+            );
+            let flags = u32::from_str_radix(cols.get(8)?, 16).ok()?;
+            let mask = u128::MAX.checked_shl(128 - plen).unwrap_or(0);
+            // RTF_UP，且不是 RTF_REJECT
+            if flags & 0x1 == 0 || flags & 0x200 != 0 || dst & mask != dest {
+                return None;
+            }
+            // RTF_GATEWAY
+            let gateway = (flags & 0x2 != 0).then_some(gateway.to_be_bytes());
+            Some((plen, cols.get(9)?.to_string(), gateway))
+        })
+        .max_by_key(|(plen, _, _)| *plen)
+        .map(|(_, iface, gateway)| Route { iface, gateway })
+        .ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::NetworkUnreachable,
+                msg!("route.none", dst = Ipv6Addr::from(dstip)),
+            )
+        })
+}
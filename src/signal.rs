@@ -0,0 +1,31 @@
+use std::{
+    mem,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// 是否收到了 SIGINT
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// 捕获 Ctrl-C，此后由 [`interrupted`] 查询而不是直接退出。
+/// 不设置 `SA_RESTART`，阻塞中的接收会以 `EINTR` 返回，以便及时检查。
+pub fn catch_interrupt() -> std::io::Result<()> {
+    let mut action: libc::sigaction = unsafe { mem::zeroed() };
+    action.sa_sigaction = on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    let ret = unsafe {
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut())
+    };
+    if ret == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// 自调用 [`catch_interrupt`] 以来是否按下了 Ctrl-C
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
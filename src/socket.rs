@@ -1,10 +1,35 @@
 use std::{
-    mem::MaybeUninit,
+    io::ErrorKind,
+    mem::{self, MaybeUninit},
     ops::{Deref, DerefMut},
+    os::fd::AsRawFd,
+    time::Instant,
 };
 
 use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 
+/// `SOL_PACKET` 选项：随每帧附带 [`TpacketAuxdata`] 控制消息
+const PACKET_AUXDATA: libc::c_int = 8;
+/// 传输层校验和交由网卡计算，帧中尚未填入
+pub const TP_STATUS_CSUMNOTREADY: u32 = 1 << 3;
+/// `tp_vlan_tci` 有效
+const TP_STATUS_VLAN_VALID: u32 = 1 << 4;
+/// `tp_vlan_tpid` 有效
+const TP_STATUS_VLAN_TPID_VALID: u32 = 1 << 6;
+
+/// 内核的 `struct tpacket_auxdata`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TpacketAuxdata {
+    tp_status: u32,
+    tp_len: u32,
+    tp_snaplen: u32,
+    tp_mac: u16,
+    tp_net: u16,
+    tp_vlan_tci: u16,
+    tp_vlan_tpid: u16,
+}
+
 #[derive(Debug)]
 pub struct PackSocket<const SIZE: usize> {
     pub socket: Socket,
@@ -13,24 +38,105 @@ pub struct PackSocket<const SIZE: usize> {
 
 impl<const SIZE: usize> PackSocket<SIZE> {
     pub fn new(protocol: libc::c_int) -> std::io::Result<Self> {
+        let socket = Socket::new(
+            Domain::PACKET,
+            Type::RAW,
+            Some(Protocol::from((protocol as i16).to_be() as i32)),
+        )?;
+        // 内核会把 VLAN 标签从接收的帧中剥离，只在辅助数据中给出
+        let on: libc::c_int = 1;
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                libc::SOL_PACKET,
+                PACKET_AUXDATA,
+                (&on as *const libc::c_int).cast(),
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
         Ok(PackSocket {
-            socket: Socket::new(
-                Domain::PACKET,
-                Type::RAW,
-                Some(Protocol::from((protocol as i16).to_be() as i32)),
-            )?,
+            socket,
             buf: [MaybeUninit::uninit(); SIZE],
         })
     }
 
+    /// 在套接字上挂载 BPF 程序，内核只把程序接受的帧交给本进程
+    pub fn attach_filter(&self, insns: &[libc::sock_filter]) -> std::io::Result<()> {
+        let prog = libc::sock_fprog {
+            len: insns.len() as u16,
+            filter: insns.as_ptr().cast_mut(),
+        };
+        let ret = unsafe {
+            libc::setsockopt(
+                self.socket.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_FILTER,
+                (&prog as *const libc::sock_fprog).cast(),
+                mem::size_of::<libc::sock_fprog>() as libc::socklen_t,
+            )
+        };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// 接收一帧。被内核剥离的 VLAN 标签会重新插入到源 MAC 地址之后
     pub fn recive(&mut self) -> std::io::Result<(Vec<u8>, SockAddr)> {
-        let (len, addr) = self.socket.recv_from(&mut self.buf)?;
-        Ok((
-            (0..len)
-                .map(|idx| unsafe { self.buf[idx].assume_init() })
-                .collect(),
-            addr,
-        ))
+        self.recive_status().map(|(data, addr, _)| (data, addr))
+    }
+
+    /// 接收一帧，同时返回内核给出的 `TP_STATUS_*` 状态位
+    pub fn recive_status(&mut self) -> std::io::Result<(Vec<u8>, SockAddr, u32)> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        // 以 u64 对齐控制消息缓冲区
+        let mut control = [0u64; 8];
+        let mut iov = libc::iovec {
+            iov_base: self.buf.as_mut_ptr().cast(),
+            iov_len: SIZE,
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = (&mut storage as *mut libc::sockaddr_storage).cast();
+        msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = mem::size_of_val(&control);
+
+        let len = unsafe { libc::recvmsg(self.socket.as_raw_fd(), &mut msg, 0) };
+        if len == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut data = (0..len as usize)
+            .map(|idx| unsafe { self.buf[idx].assume_init() })
+            .collect::<Vec<u8>>();
+        let aux = unsafe { auxdata(&msg) };
+        if let Some(tag) = aux.as_ref().and_then(stripped_vlan) {
+            if data.len() >= 12 {
+                data.splice(12..12, tag);
+            }
+        }
+        let addr = unsafe { SockAddr::new(storage, msg.msg_namelen) };
+        Ok((data, addr, aux.map_or(0, |aux| aux.tp_status)))
+    }
+
+    /// 在 `until` 之前接收一帧，超时返回 `None`
+    pub fn recive_until(&mut self, until: Instant) -> std::io::Result<Option<Vec<u8>>> {
+        let now = Instant::now();
+        if now >= until {
+            return Ok(None);
+        }
+        self.socket.set_read_timeout(Some(until - now))?;
+        let result = match self.recive() {
+            Ok((data, _)) => Ok(Some(data)),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(None),
+            Err(e) => Err(e),
+        };
+        self.socket.set_read_timeout(None)?;
+        result
     }
 }
 
@@ -46,3 +152,72 @@ impl<const S: usize> DerefMut for PackSocket<S> {
         &mut self.socket
     }
 }
+
+/// 从 `recvmsg` 的控制消息中取出 `tpacket_auxdata`
+///
+/// # Safety
+///
+/// `msg` 必须是刚由 `recvmsg` 填充的消息，其控制消息缓冲区仍然有效。
+unsafe fn auxdata(msg: &libc::msghdr) -> Option<TpacketAuxdata> {
+    let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+    while let Some(hdr) = cmsg.as_ref() {
+        if hdr.cmsg_level == libc::SOL_PACKET && hdr.cmsg_type == PACKET_AUXDATA {
+            return Some(
+                libc::CMSG_DATA(hdr)
+                    .cast::<TpacketAuxdata>()
+                    .read_unaligned(),
+            );
+        }
+        cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+    }
+    None
+}
+
+/// 被内核剥离的 VLAN 标签（TPID 与 TCI）
+fn stripped_vlan(aux: &TpacketAuxdata) -> Option<[u8; 4]> {
+    if aux.tp_status & TP_STATUS_VLAN_VALID == 0 {
+        return None;
+    }
+    let tpid = match aux.tp_status & TP_STATUS_VLAN_TPID_VALID {
+        0 => 0x8100,
+        _ => aux.tp_vlan_tpid,
+    };
+    let mut tag = [0; 4];
+    tag[..2].copy_from_slice(&tpid.to_be_bytes());
+    tag[2..].copy_from_slice(&aux.tp_vlan_tci.to_be_bytes());
+    Some(tag)
+}
+
+/// 取出 `AF_PACKET` 地址中的接口索引
+pub fn ifindex(addr: &SockAddr) -> Option<i32> {
+    if addr.family() as libc::c_int != libc::AF_PACKET {
+        return None;
+    }
+    // `AF_PACKET` 地址的存储布局即为 `sockaddr_ll`
+    let ll = unsafe { &*(addr.as_ptr() as *const libc::sockaddr_ll) };
+    Some(ll.sll_ifindex)
+}
+
+/// 查询接口名对应的接口索引
+pub fn if_index(name: &str) -> std::io::Result<u32> {
+    let name = std::ffi::CString::new(name)?;
+    match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+        0 => Err(std::io::Error::last_os_error()),
+        idx => Ok(idx),
+    }
+}
+
+/// 构造发往接口 `ifindex` 的 `AF_PACKET` 地址
+pub fn link_addr(ifindex: i32, protocol: libc::c_int) -> SockAddr {
+    unsafe {
+        let mut storage: libc::sockaddr_storage = mem::zeroed();
+        let ll = &mut *(&mut storage as *mut libc::sockaddr_storage).cast::<libc::sockaddr_ll>();
+        ll.sll_family = libc::AF_PACKET as libc::sa_family_t;
+        ll.sll_protocol = (protocol as u16).to_be();
+        ll.sll_ifindex = ifindex;
+        SockAddr::new(
+            storage,
+            mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t,
+        )
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::SystemTime;
+
+use crate::socket::{ifindex, PackSocket, TP_STATUS_CSUMNOTREADY};
+
+/// 捕获到的一帧
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// 以太网帧数据，可能已按快照长度截断
+    pub data: Vec<u8>,
+    /// 捕获时间
+    pub ts: SystemTime,
+    /// 帧在线路上的原始长度
+    pub origlen: u32,
+    /// 捕获该帧的接口索引，未知时为 `None`
+    pub ifindex: Option<i32>,
+    /// 本机发出的帧，传输层校验和交由网卡计算，捕获时尚未填入
+    pub offloaded: bool,
+}
+
+/// 帧的来源，例如网络接口或捕获文件
+pub trait PacketSource {
+    /// 读取下一帧。数据源耗尽时返回 `None`
+    fn next_frame(&mut self) -> std::io::Result<Option<Frame>>;
+}
+
+impl<const S: usize> PacketSource for PackSocket<S> {
+    fn next_frame(&mut self) -> std::io::Result<Option<Frame>> {
+        let (data, addr, status) = self.recive_status()?;
+        Ok(Some(Frame {
+            origlen: data.len() as u32,
+            data,
+            ts: SystemTime::now(),
+            ifindex: ifindex(&addr),
+            offloaded: status & TP_STATUS_CSUMNOTREADY != 0,
+        }))
+    }
+}